@@ -0,0 +1,9 @@
+#![no_main]
+
+// Same harness and sequence runner as `sdk/tests/instruction_sequences.rs`
+#[path = "../../sdk/tests/common/mod.rs"]
+mod common;
+
+use common::sequence::{self, Action};
+
+libfuzzer_sys::fuzz_target!(|actions: Vec<Action>| sequence::run(&actions));
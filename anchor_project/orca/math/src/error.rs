@@ -0,0 +1,21 @@
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MathError {
+    Overflow,
+    DivisionByZero,
+    TickOutOfBounds,
+    PriceOutOfBounds,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            MathError::Overflow => "math overflow",
+            MathError::DivisionByZero => "division by zero",
+            MathError::TickOutOfBounds => "tick out of bounds",
+            MathError::PriceOutOfBounds => "price out of bounds",
+        };
+        f.write_str(msg)
+    }
+}
@@ -0,0 +1,44 @@
+use crate::MathError;
+
+/// `a * b / denominator` with a 256-bit intermediate product, rounded down
+/// unless `round_up` is set.
+pub fn mul_div(a: u128, b: u128, denominator: u128, round_up: bool) -> Result<u128, MathError> {
+    if denominator == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    let (hi, lo) = full_mul(a, b);
+    if hi >= denominator {
+        return Err(MathError::Overflow);
+    }
+    // Schoolbook long division; `hi < denominator` keeps the quotient within 128 bits.
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    if round_up && remainder > 0 {
+        quotient = quotient.checked_add(1).ok_or(MathError::Overflow)?;
+    }
+    Ok(quotient)
+}
+
+/// Full 256-bit product of two `u128`s as `(high, low)` halves.
+pub fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (middle << 64) | (lo_lo & MASK);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (hi, lo)
+}
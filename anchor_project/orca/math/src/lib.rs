@@ -0,0 +1,40 @@
+//! Fixed-point math for the orca AMM, shared by the on-chain program and
+//! off-chain quoting so both produce identical results.
+//!
+//! Prices are square roots in Q64.64 (`sqrt_price_x64`). Every function that
+//! has to round does so in the pool's favour:
+//!
+//! * token amounts the pool *receives* round up,
+//! * token amounts the pool *pays out* round down,
+//! * liquidity minted for a deposit rounds down,
+//! * fees round up.
+//!
+//! The `round_up` flag on the lower-level helpers exists so callers can pick
+//! the side explicitly; the swap and liquidity helpers already pass the right one.
+
+#![no_std]
+
+mod error;
+mod full_math;
+mod liquidity_math;
+mod swap_math;
+mod tick_math;
+
+pub use error::MathError;
+pub use full_math::{full_mul, mul_div};
+pub use liquidity_math::{
+    amounts_for_liquidity, amounts_for_range_liquidity, liquidity_from_amount_0, liquidity_from_amount_1,
+    liquidity_from_amounts, sqrt_price_from_amounts,
+};
+pub use swap_math::{
+    compute_swap_step, compute_swap_step_exact_out, get_amount_0_delta, get_amount_1_delta,
+    get_next_sqrt_price_from_input, get_next_sqrt_price_from_output, SwapStep,
+    FEE_RATE_DENOMINATOR,
+};
+pub use tick_math::{
+    sqrt_price_to_tick, tick_to_sqrt_price, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64,
+    MIN_TICK,
+};
+
+/// 1.0 in Q64.64.
+pub const Q64: u128 = 1u128 << 64;
@@ -0,0 +1,76 @@
+use crate::{full_mul, get_amount_0_delta, get_amount_1_delta, mul_div, MathError, Q64};
+
+/// Largest full-range liquidity both amounts can fund at `sqrt_price_x64`,
+/// rounded down.
+pub fn liquidity_from_amounts(sqrt_price_x64: u128, amount_token_0: u64, amount_token_1: u64) -> Result<u128, MathError> {
+    let liquidity_0 = mul_div(amount_token_0 as u128, sqrt_price_x64, Q64, false)?;
+    let liquidity_1 = mul_div(amount_token_1 as u128, Q64, sqrt_price_x64, false)?;
+    Ok(liquidity_0.min(liquidity_1))
+}
+
+/// Token amounts backing full-range `liquidity` at `sqrt_price_x64`:
+/// `L / sqrt(P)` of token 0 and `L * sqrt(P)` of token 1. Round up for
+/// deposits and down for withdrawals.
+pub fn amounts_for_liquidity(sqrt_price_x64: u128, liquidity: u128, round_up: bool) -> Result<(u64, u64), MathError> {
+    let amount_0 = mul_div(liquidity, Q64, sqrt_price_x64, round_up)?;
+    let amount_1 = mul_div(liquidity, sqrt_price_x64, Q64, round_up)?;
+    Ok((
+        u64::try_from(amount_0).map_err(|_| MathError::Overflow)?,
+        u64::try_from(amount_1).map_err(|_| MathError::Overflow)?,
+    ))
+}
+
+/// Largest liquidity over `[lower, upper]` that `amount` of token 0 funds
+/// while the price is at or below `lower`, rounded down.
+pub fn liquidity_from_amount_0(sqrt_price_lower_x64: u128, sqrt_price_upper_x64: u128, amount: u64) -> Result<u128, MathError> {
+    let scaled = mul_div(amount as u128, sqrt_price_lower_x64, Q64, false)?;
+    mul_div(scaled, sqrt_price_upper_x64, sqrt_price_upper_x64 - sqrt_price_lower_x64, false)
+}
+
+/// Largest liquidity over `[lower, upper]` that `amount` of token 1 funds
+/// while the price is at or above `upper`, rounded down.
+pub fn liquidity_from_amount_1(sqrt_price_lower_x64: u128, sqrt_price_upper_x64: u128, amount: u64) -> Result<u128, MathError> {
+    mul_div(amount as u128, Q64, sqrt_price_upper_x64 - sqrt_price_lower_x64, false)
+}
+
+/// Token amounts backing `liquidity` over `[lower, upper]` at
+/// `sqrt_price_x64`: all token 0 below the range, all token 1 above it and a
+/// mix inside. Round up for deposits and down for withdrawals.
+pub fn amounts_for_range_liquidity(
+    sqrt_price_x64: u128,
+    sqrt_price_lower_x64: u128,
+    sqrt_price_upper_x64: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64), MathError> {
+    let sqrt_price_x64 = sqrt_price_x64.clamp(sqrt_price_lower_x64, sqrt_price_upper_x64);
+    let amount_0 = get_amount_0_delta(sqrt_price_x64, sqrt_price_upper_x64, liquidity, round_up)?;
+    let amount_1 = get_amount_1_delta(sqrt_price_lower_x64, sqrt_price_x64, liquidity, round_up)?;
+    Ok((
+        u64::try_from(amount_0).map_err(|_| MathError::Overflow)?,
+        u64::try_from(amount_1).map_err(|_| MathError::Overflow)?,
+    ))
+}
+
+/// Price at which `amount_token_0` and `amount_token_1` are a full-range
+/// deposit of equal value: `sqrt(amount_1 / amount_0)`, rounded down.
+pub fn sqrt_price_from_amounts(amount_token_0: u64, amount_token_1: u64) -> Result<u128, MathError> {
+    if amount_token_0 == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    // sqrt(a0 * a1) / a0 keeps the 256-bit radicand exact where a1 / a0 would not be.
+    let product = amount_token_0 as u128 * amount_token_1 as u128;
+    Ok(sqrt_x128(product) / amount_token_0 as u128)
+}
+
+/// `floor(sqrt(value * 2^128))`, one result bit at a time.
+fn sqrt_x128(value: u128) -> u128 {
+    let mut root = 0u128;
+    for bit in (0..128).rev() {
+        let candidate = root | (1 << bit);
+        if full_mul(candidate, candidate) <= (value, 0) {
+            root = candidate;
+        }
+    }
+    root
+}
@@ -0,0 +1,139 @@
+use crate::{mul_div, MathError, Q64};
+
+/// `fee_rate` is expressed in hundredths of a basis point.
+pub const FEE_RATE_DENOMINATOR: u128 = 1_000_000;
+
+/// Outcome of swapping against a single liquidity range until either the
+/// input runs out or the price reaches the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Exact-input swap step. `amount_in` rounds up and `amount_out` rounds down;
+/// the fee is rounded up when the target is reached and otherwise absorbs
+/// whatever part of `amount_remaining` was not swapped, so
+/// `amount_in + fee_amount <= amount_remaining` always holds.
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u16,
+    zero_for_one: bool,
+) -> Result<SwapStep, MathError> {
+    let fee_rate = fee_rate as u128;
+    let amount_remaining_less_fee = mul_div(amount_remaining as u128, FEE_RATE_DENOMINATOR - fee_rate, FEE_RATE_DENOMINATOR, false)?;
+    let amount_in_to_target = if zero_for_one {
+        get_amount_0_delta(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, true)?
+    } else {
+        get_amount_1_delta(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, true)?
+    };
+    let reaches_target = amount_remaining_less_fee >= amount_in_to_target;
+    let sqrt_price_next_x64 = if reaches_target {
+        sqrt_price_target_x64
+    } else {
+        get_next_sqrt_price_from_input(sqrt_price_current_x64, liquidity, amount_remaining_less_fee, zero_for_one)?
+    };
+    let (amount_in, amount_out) = if zero_for_one {
+        let amount_in = if reaches_target { amount_in_to_target } else { get_amount_0_delta(sqrt_price_next_x64, sqrt_price_current_x64, liquidity, true)? };
+        (amount_in, get_amount_1_delta(sqrt_price_next_x64, sqrt_price_current_x64, liquidity, false)?)
+    } else {
+        let amount_in = if reaches_target { amount_in_to_target } else { get_amount_1_delta(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, true)? };
+        (amount_in, get_amount_0_delta(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, false)?)
+    };
+    let amount_in = u64::try_from(amount_in).map_err(|_| MathError::Overflow)?;
+    let amount_out = u64::try_from(amount_out).map_err(|_| MathError::Overflow)?;
+    // Short of the target the whole remainder is consumed, so whatever was not swapped is the fee.
+    let (amount_in, fee_amount) = if reaches_target {
+        let fee = mul_div(amount_in as u128, fee_rate, FEE_RATE_DENOMINATOR - fee_rate, true)?;
+        let fee = u64::try_from(fee).map_err(|_| MathError::Overflow)?;
+        (amount_in, fee.min(amount_remaining - amount_in))
+    } else {
+        // The price only moved as far as `amount_remaining_less_fee` pays for; capping
+        // `amount_in` there keeps a rounded-up delta from eating into the fee.
+        let amount_in = amount_in.min(amount_remaining_less_fee as u64);
+        (amount_in, amount_remaining - amount_in)
+    };
+    Ok(SwapStep { sqrt_price_next_x64, amount_in, amount_out, fee_amount })
+}
+
+/// Exact-output swap step. `amount_remaining` is the output still wanted;
+/// `amount_out` rounds down and never exceeds it, while `amount_in` and the
+/// fee on top of it round up.
+pub fn compute_swap_step_exact_out(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u16,
+    zero_for_one: bool,
+) -> Result<SwapStep, MathError> {
+    let fee_rate = fee_rate as u128;
+    let amount_out_to_target = if zero_for_one {
+        get_amount_1_delta(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, false)?
+    } else {
+        get_amount_0_delta(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, false)?
+    };
+    let reaches_target = amount_remaining as u128 >= amount_out_to_target;
+    let sqrt_price_next_x64 = if reaches_target {
+        sqrt_price_target_x64
+    } else {
+        get_next_sqrt_price_from_output(sqrt_price_current_x64, liquidity, amount_remaining as u128, zero_for_one)?
+    };
+    let (amount_in, amount_out) = if zero_for_one {
+        let amount_out = if reaches_target { amount_out_to_target } else { get_amount_1_delta(sqrt_price_next_x64, sqrt_price_current_x64, liquidity, false)? };
+        (get_amount_0_delta(sqrt_price_next_x64, sqrt_price_current_x64, liquidity, true)?, amount_out)
+    } else {
+        let amount_out = if reaches_target { amount_out_to_target } else { get_amount_0_delta(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, false)? };
+        (get_amount_1_delta(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, true)?, amount_out)
+    };
+    let amount_in = u64::try_from(amount_in).map_err(|_| MathError::Overflow)?;
+    let amount_out = u64::try_from(amount_out).map_err(|_| MathError::Overflow)?.min(amount_remaining);
+    let fee_amount = mul_div(amount_in as u128, fee_rate, FEE_RATE_DENOMINATOR - fee_rate, true)?;
+    let fee_amount = u64::try_from(fee_amount).map_err(|_| MathError::Overflow)?;
+    Ok(SwapStep { sqrt_price_next_x64, amount_in, amount_out, fee_amount })
+}
+
+/// Token 0 between two prices: `L * (upper - lower) / (upper * lower)`.
+/// Rounding is applied to both divisions, so `round_up` results are at most
+/// two units above `round_down` ones.
+pub fn get_amount_0_delta(sqrt_price_lower_x64: u128, sqrt_price_upper_x64: u128, liquidity: u128, round_up: bool) -> Result<u128, MathError> {
+    let scaled = mul_div(liquidity, sqrt_price_upper_x64 - sqrt_price_lower_x64, sqrt_price_upper_x64, round_up)?;
+    mul_div(scaled, Q64, sqrt_price_lower_x64, round_up)
+}
+
+/// Token 1 between two prices: `L * (upper - lower)`.
+pub fn get_amount_1_delta(sqrt_price_lower_x64: u128, sqrt_price_upper_x64: u128, liquidity: u128, round_up: bool) -> Result<u128, MathError> {
+    mul_div(liquidity, sqrt_price_upper_x64 - sqrt_price_lower_x64, Q64, round_up)
+}
+
+/// Price after adding `amount` of the input token. Selling token 0 rounds the
+/// new price up and selling token 1 rounds it down, so the price moves no
+/// further than the input pays for.
+pub fn get_next_sqrt_price_from_input(sqrt_price_x64: u128, liquidity: u128, amount: u128, zero_for_one: bool) -> Result<u128, MathError> {
+    if zero_for_one {
+        // `L * P / (L + amount * P)`; flooring the denominator keeps the result at or below `P`.
+        let denominator = liquidity.checked_add(mul_div(amount, sqrt_price_x64, Q64, false)?).ok_or(MathError::Overflow)?;
+        mul_div(liquidity, sqrt_price_x64, denominator, true)
+    } else {
+        let delta = mul_div(amount, Q64, liquidity, false)?;
+        sqrt_price_x64.checked_add(delta).ok_or(MathError::Overflow)
+    }
+}
+
+/// Price after removing `amount` of the output token. Buying token 1 rounds the
+/// new price down and buying token 0 rounds it up, so the input charged for the
+/// move is never less than the output is worth.
+pub fn get_next_sqrt_price_from_output(sqrt_price_x64: u128, liquidity: u128, amount: u128, zero_for_one: bool) -> Result<u128, MathError> {
+    if zero_for_one {
+        let delta = mul_div(amount, Q64, liquidity, true)?;
+        sqrt_price_x64.checked_sub(delta).ok_or(MathError::Overflow)
+    } else {
+        let denominator = liquidity.checked_sub(mul_div(amount, sqrt_price_x64, Q64, true)?).filter(|d| *d > 0).ok_or(MathError::Overflow)?;
+        mul_div(liquidity, sqrt_price_x64, denominator, true)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{full_mul, mul_div, MathError};
+
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = 443636;
+/// `tick_to_sqrt_price(MIN_TICK)`
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+/// `tick_to_sqrt_price(MAX_TICK)`
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579061;
+
+/// `2^128 / sqrt(1.0001)^(2^i)`, rounded down, for each bit `i` of a tick.
+const RATIOS_X128: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e2139,
+    0xfff2e50f5f656932ef12357cf3c7fdcb,
+    0xffe5caca7e10e4e61c3624eaa0941ccf,
+    0xffcb9843d60f6159c9db58835c926643,
+    0xff973b41fa98c081472e6896dfb254bf,
+    0xff2ea16466c96a3843ec78b326b52860,
+    0xfe5dee046a99a2a811c461f1969c3052,
+    0xfcbe86c7900a88aedcffc83b479aa3a3,
+    0xf987a7253ac413176f2b074cf7815e53,
+    0xf3392b0822b70005940c7a398e4b70f2,
+    0xe7159475a2c29b7443b29c7fa6e889d8,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e4,
+    0x70d869a156d2a1b890bb3df62baf32f6,
+    0x31be135f97d08fd981231505542fcfa5,
+    0x9aa508b5b7a84e1c677de54f3e99bc8,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe97,
+];
+
+/// `2 / log2(1.0001)` in Q32.32: converts `log2(sqrt_price)` into ticks.
+const TICKS_PER_LOG2_X32: i128 = 59543866431248;
+
+/// `sqrt(1.0001^tick)` in Q64.64.
+///
+/// Negative ticks truncate the product of the Q128 ratios; positive ticks take
+/// the floored reciprocal of that product. Either way the result is within a
+/// few units of the exact value and strictly increasing in `tick`.
+pub fn tick_to_sqrt_price(tick: i32) -> Result<u128, MathError> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(MathError::TickOutOfBounds);
+    }
+    let abs_tick = tick.unsigned_abs();
+    // `ratio` is sqrt(1.0001)^-|tick| in Q128, always below 2^128 once a bit is applied.
+    let mut ratio: Option<u128> = None;
+    for (bit, factor) in RATIOS_X128.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = Some(match ratio {
+                None => *factor,
+                Some(r) => full_mul(r, *factor).0,
+            });
+        }
+    }
+    match ratio {
+        None => Ok(1u128 << 64),
+        Some(ratio) if tick < 0 => Ok(ratio >> 64),
+        // 2^192 / ratio, i.e. the reciprocal moved from Q128 to Q64.
+        Some(ratio) => mul_div(1u128 << 96, 1u128 << 96, ratio, false),
+    }
+}
+
+/// The greatest tick whose price does not exceed `sqrt_price_x64`, so that
+/// `tick_to_sqrt_price(t) <= sqrt_price_x64 < tick_to_sqrt_price(t + 1)`.
+pub fn sqrt_price_to_tick(sqrt_price_x64: u128) -> Result<i32, MathError> {
+    if !(MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        return Err(MathError::PriceOutOfBounds);
+    }
+    // log2 in Q32.32: integer part from the most significant bit, then one
+    // fractional bit per squaring of the mantissa normalised to [1, 2) in Q63.
+    let msb = 127 - sqrt_price_x64.leading_zeros() as i32;
+    let mut log2_x32 = ((msb - 64) as i128) << 32;
+    let mut mantissa = if msb >= 63 { sqrt_price_x64 >> (msb - 63) } else { sqrt_price_x64 << (63 - msb) };
+    for bit in (0..32).rev() {
+        mantissa = (mantissa * mantissa) >> 63;
+        if mantissa >= 1u128 << 64 {
+            mantissa >>= 1;
+            log2_x32 |= 1 << bit;
+        }
+    }
+    // The estimate is within a tick of the answer; step it onto the exact one.
+    let estimate = ((log2_x32 * TICKS_PER_LOG2_X32) >> 64) as i32;
+    let mut tick = estimate.clamp(MIN_TICK, MAX_TICK);
+    while tick > MIN_TICK && tick_to_sqrt_price(tick)? > sqrt_price_x64 {
+        tick -= 1;
+    }
+    while tick < MAX_TICK && tick_to_sqrt_price(tick + 1)? <= sqrt_price_x64 {
+        tick += 1;
+    }
+    Ok(tick)
+}
@@ -0,0 +1,165 @@
+use orca_math::*;
+use proptest::prelude::*;
+
+fn tick() -> impl Strategy<Value = i32> {
+    MIN_TICK..=MAX_TICK
+}
+
+fn sqrt_price() -> impl Strategy<Value = u128> {
+    MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64
+}
+
+/// Current price plus a target on the side the swap moves towards, both on ticks.
+fn swap_range() -> impl Strategy<Value = (u128, u128, bool)> {
+    (MIN_TICK + 1..MAX_TICK, any::<bool>(), any::<u32>()).prop_map(|(current, zero_for_one, offset)| {
+        let target = if zero_for_one {
+            current - 1 - (offset as i32).rem_euclid(current - MIN_TICK)
+        } else {
+            current + 1 + (offset as i32).rem_euclid(MAX_TICK - current)
+        };
+        (tick_to_sqrt_price(current).unwrap(), tick_to_sqrt_price(target).unwrap(), zero_for_one)
+    })
+}
+
+fn liquidity() -> impl Strategy<Value = u128> {
+    1u128..1 << 96
+}
+
+#[test]
+fn tick_to_sqrt_price_is_strictly_increasing_over_every_tick() {
+    let mut previous = tick_to_sqrt_price(MIN_TICK).unwrap();
+    for tick in MIN_TICK + 1..=MAX_TICK {
+        let sqrt_price = tick_to_sqrt_price(tick).unwrap();
+        assert!(sqrt_price > previous, "tick {tick}");
+        previous = sqrt_price;
+    }
+}
+
+proptest! {
+    #[test]
+    fn tick_round_trips_through_its_sqrt_price(tick in tick()) {
+        prop_assert_eq!(sqrt_price_to_tick(tick_to_sqrt_price(tick).unwrap()).unwrap(), tick);
+    }
+
+    #[test]
+    fn sqrt_price_to_tick_returns_the_enclosing_tick(sqrt_price in sqrt_price()) {
+        let tick = sqrt_price_to_tick(sqrt_price).unwrap();
+        prop_assert!(tick_to_sqrt_price(tick).unwrap() <= sqrt_price);
+        prop_assert!(sqrt_price < tick_to_sqrt_price(tick + 1).unwrap());
+    }
+
+    #[test]
+    fn sqrt_price_to_tick_is_monotonic(a in sqrt_price(), b in sqrt_price()) {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        prop_assert!(sqrt_price_to_tick(low).unwrap() <= sqrt_price_to_tick(high).unwrap());
+    }
+
+    #[test]
+    fn out_of_range_inputs_are_rejected(offset in 1i32..1_000_000) {
+        prop_assert_eq!(tick_to_sqrt_price(MAX_TICK + offset), Err(MathError::TickOutOfBounds));
+        prop_assert_eq!(tick_to_sqrt_price(MIN_TICK - offset), Err(MathError::TickOutOfBounds));
+        prop_assert_eq!(sqrt_price_to_tick(MIN_SQRT_PRICE_X64 - offset as u128), Err(MathError::PriceOutOfBounds));
+    }
+
+    #[test]
+    fn exact_input_never_spends_more_than_given(
+        (current, target, zero_for_one) in swap_range(),
+        liquidity in liquidity(),
+        amount in any::<u64>(),
+        fee_rate in any::<u16>(),
+    ) {
+        let Ok(step) = compute_swap_step(current, target, liquidity, amount, fee_rate, zero_for_one) else { return Ok(()) };
+        prop_assert!(step.amount_in as u128 + step.fee_amount as u128 <= amount as u128);
+        // The price moves towards the target and never past it
+        if zero_for_one {
+            prop_assert!(target <= step.sqrt_price_next_x64 && step.sqrt_price_next_x64 <= current);
+        } else {
+            prop_assert!(current <= step.sqrt_price_next_x64 && step.sqrt_price_next_x64 <= target);
+        }
+    }
+
+    #[test]
+    fn swapping_there_and_back_never_returns_more_than_was_put_in(
+        (current, target, zero_for_one) in swap_range(),
+        liquidity in liquidity(),
+        amount in any::<u64>(),
+        fee_rate in any::<u16>(),
+    ) {
+        let Ok(there) = compute_swap_step(current, target, liquidity, amount, fee_rate, zero_for_one) else { return Ok(()) };
+        if there.amount_out == 0 {
+            return Ok(());
+        }
+        // Selling the proceeds straight back towards the starting price
+        let Ok(back) = compute_swap_step(there.sqrt_price_next_x64, current, liquidity, there.amount_out, fee_rate, !zero_for_one) else { return Ok(()) };
+        prop_assert!(back.amount_out <= there.amount_in);
+    }
+
+    #[test]
+    fn exact_output_never_pays_out_more_than_asked_or_more_than_is_paid_for(
+        (current, target, zero_for_one) in swap_range(),
+        liquidity in liquidity(),
+        amount in any::<u64>(),
+        fee_rate in any::<u16>(),
+    ) {
+        let Ok(step) = compute_swap_step_exact_out(current, target, liquidity, amount, fee_rate, zero_for_one) else { return Ok(()) };
+        prop_assert!(step.amount_out <= amount);
+        // What the quoted input is worth at the exact price path is at least the output
+        let exact_out = if zero_for_one {
+            get_amount_1_delta(step.sqrt_price_next_x64, current, liquidity, true).unwrap()
+        } else {
+            get_amount_0_delta(current, step.sqrt_price_next_x64, liquidity, true).unwrap()
+        };
+        prop_assert!(step.amount_out as u128 <= exact_out);
+        let exact_in = if zero_for_one {
+            get_amount_0_delta(step.sqrt_price_next_x64, current, liquidity, false).unwrap()
+        } else {
+            get_amount_1_delta(current, step.sqrt_price_next_x64, liquidity, false).unwrap()
+        };
+        prop_assert!(step.amount_in as u128 >= exact_in);
+    }
+
+    #[test]
+    fn fees_round_in_favour_of_the_pool(
+        (current, target, zero_for_one) in swap_range(),
+        liquidity in liquidity(),
+        amount in any::<u64>(),
+        fee_rate in any::<u16>(),
+        exact_input in any::<bool>(),
+    ) {
+        let step = if exact_input {
+            compute_swap_step(current, target, liquidity, amount, fee_rate, zero_for_one)
+        } else {
+            compute_swap_step_exact_out(current, target, liquidity, amount, fee_rate, zero_for_one)
+        };
+        let Ok(step) = step else { return Ok(()) };
+        // The fee is at least `fee_rate` of everything the trader pays
+        let paid = step.amount_in as u128 + step.fee_amount as u128;
+        prop_assert!(step.fee_amount as u128 * FEE_RATE_DENOMINATOR >= paid * fee_rate as u128);
+    }
+
+    #[test]
+    fn liquidity_round_trip_never_returns_more_than_deposited(
+        sqrt_price in sqrt_price(),
+        amount_0 in any::<u64>(),
+        amount_1 in any::<u64>(),
+    ) {
+        let liquidity = liquidity_from_amounts(sqrt_price, amount_0, amount_1).unwrap();
+        let Ok((deposit_0, deposit_1)) = amounts_for_liquidity(sqrt_price, liquidity, true) else { return Ok(()) };
+        let (withdraw_0, withdraw_1) = amounts_for_liquidity(sqrt_price, liquidity, false).unwrap();
+        prop_assert!(withdraw_0 <= deposit_0 && withdraw_1 <= deposit_1);
+        // Re-depositing the withdrawal cannot mint more liquidity than was given up
+        prop_assert!(liquidity_from_amounts(sqrt_price, withdraw_0, withdraw_1).unwrap() <= liquidity);
+    }
+
+    #[test]
+    fn liquidity_never_needs_more_than_the_offered_amounts(
+        sqrt_price in sqrt_price(),
+        amount_0 in any::<u64>(),
+        amount_1 in any::<u64>(),
+    ) {
+        let liquidity = liquidity_from_amounts(sqrt_price, amount_0, amount_1).unwrap();
+        let Ok((deposit_0, deposit_1)) = amounts_for_liquidity(sqrt_price, liquidity, true) else { return Ok(()) };
+        // Rounding the deposit up costs at most one unit beyond the offer
+        prop_assert!(deposit_0 as u128 <= amount_0 as u128 + 1 && deposit_1 as u128 <= amount_1 as u128 + 1);
+    }
+}
@@ -0,0 +1,161 @@
+use orca_math::*;
+
+#[test]
+fn mul_div_rounds_in_the_requested_direction() {
+    assert_eq!(mul_div(7, 3, 2, false), Ok(10));
+    assert_eq!(mul_div(7, 3, 2, true), Ok(11));
+    // Exact quotients are unaffected by the flag
+    assert_eq!(mul_div(8, 3, 2, true), Ok(12));
+    // The 256-bit intermediate lets products above u128::MAX through
+    assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX, true), Ok(u128::MAX));
+    assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 90, false), Ok(1 << 110));
+}
+
+#[test]
+fn mul_div_reports_overflow_and_division_by_zero() {
+    assert_eq!(mul_div(u128::MAX, 2, 1, false), Err(MathError::Overflow));
+    assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX, false).map(|q| q.checked_add(1)), Ok(None));
+    assert_eq!(mul_div(1, 1, 0, false), Err(MathError::DivisionByZero));
+}
+
+#[test]
+fn tick_bounds_match_price_bounds() {
+    assert_eq!(tick_to_sqrt_price(MIN_TICK), Ok(MIN_SQRT_PRICE_X64));
+    assert_eq!(tick_to_sqrt_price(MAX_TICK), Ok(MAX_SQRT_PRICE_X64));
+    assert_eq!(tick_to_sqrt_price(0), Ok(Q64));
+    assert_eq!(tick_to_sqrt_price(MIN_TICK - 1), Err(MathError::TickOutOfBounds));
+    assert_eq!(tick_to_sqrt_price(MAX_TICK + 1), Err(MathError::TickOutOfBounds));
+    assert_eq!(sqrt_price_to_tick(MIN_SQRT_PRICE_X64), Ok(MIN_TICK));
+    assert_eq!(sqrt_price_to_tick(MAX_SQRT_PRICE_X64), Ok(MAX_TICK));
+    assert_eq!(sqrt_price_to_tick(MIN_SQRT_PRICE_X64 - 1), Err(MathError::PriceOutOfBounds));
+    assert_eq!(sqrt_price_to_tick(MAX_SQRT_PRICE_X64 + 1), Err(MathError::PriceOutOfBounds));
+}
+
+#[test]
+fn sqrt_price_to_tick_rounds_down_to_the_enclosing_tick() {
+    for tick in (MIN_TICK + 1..MAX_TICK).step_by(9973).chain([-1, 1, -60, 60]) {
+        let sqrt_price = tick_to_sqrt_price(tick).unwrap();
+        assert_eq!(sqrt_price_to_tick(sqrt_price), Ok(tick));
+        assert_eq!(sqrt_price_to_tick(sqrt_price - 1), Ok(tick - 1));
+        let next = tick_to_sqrt_price(tick + 1).unwrap();
+        assert_eq!(sqrt_price_to_tick(next - 1), Ok(tick));
+    }
+}
+
+#[test]
+fn amount_deltas_round_up_by_at_most_the_rounding_steps() {
+    let (lower, upper) = (tick_to_sqrt_price(-100).unwrap(), tick_to_sqrt_price(250).unwrap());
+    for liquidity in [1u128, 999, 1_000_003, 123_456_789_012_345] {
+        let down = get_amount_0_delta(lower, upper, liquidity, false).unwrap();
+        let up = get_amount_0_delta(lower, upper, liquidity, true).unwrap();
+        assert!(up >= down && up - down <= 2);
+        let down = get_amount_1_delta(lower, upper, liquidity, false).unwrap();
+        let up = get_amount_1_delta(lower, upper, liquidity, true).unwrap();
+        assert!(up >= down && up - down <= 1);
+    }
+}
+
+#[test]
+fn swap_step_never_spends_more_than_given() {
+    let liquidity = 1_000_000_000u128;
+    for (amount, zero_for_one) in [(1u64, true), (77, false), (10_000, true), (5_000_000, false), (u32::MAX as u64, true)] {
+        let target = if zero_for_one { MIN_SQRT_PRICE_X64 } else { MAX_SQRT_PRICE_X64 };
+        let step = compute_swap_step(Q64, target, liquidity, amount, 3000, zero_for_one).unwrap();
+        assert!(step.amount_in + step.fee_amount <= amount);
+        // Short of the target the input is consumed in full, with the remainder as fee
+        assert_eq!(step.amount_in + step.fee_amount, amount);
+        // 0.3% fee rounded up, so at least that much is kept
+        assert!(step.fee_amount as u128 * FEE_RATE_DENOMINATOR >= amount as u128 * 3000);
+    }
+}
+
+#[test]
+fn swap_step_stops_at_the_target_and_rounds_the_fee_up() {
+    let target = tick_to_sqrt_price(-100).unwrap();
+    let step = compute_swap_step(Q64, target, 1_000_000_000, u32::MAX as u64, 3000, true).unwrap();
+    assert_eq!(step.sqrt_price_next_x64, target);
+    assert_eq!(step.amount_in as u128, get_amount_0_delta(target, Q64, 1_000_000_000, true).unwrap());
+    assert_eq!(step.amount_out as u128, get_amount_1_delta(target, Q64, 1_000_000_000, false).unwrap());
+    let exact_fee = step.amount_in as u128 * 3000;
+    let denominator = FEE_RATE_DENOMINATOR - 3000;
+    assert_eq!(step.fee_amount as u128, exact_fee.div_ceil(denominator));
+}
+
+#[test]
+fn selling_token_0_rounds_the_new_price_up_and_token_1_rounds_it_down() {
+    let liquidity = 3_000_000_007u128;
+    let down = get_next_sqrt_price_from_input(Q64, liquidity, 1_000_003, true).unwrap();
+    let exact_down = mul_div(liquidity, Q64, liquidity + 1_000_003, false).unwrap();
+    assert!(down >= exact_down);
+    let up = get_next_sqrt_price_from_input(Q64, liquidity, 1_000_003, false).unwrap();
+    assert_eq!(up, Q64 + mul_div(1_000_003, Q64, liquidity, false).unwrap());
+}
+
+#[test]
+fn liquidity_round_trip_never_returns_more_than_deposited() {
+    for (tick, amount_0, amount_1) in [(0, 1_000_000u64, 1_000_000u64), (-5000, 7, 900), (23_000, 1, u32::MAX as u64)] {
+        let sqrt_price = tick_to_sqrt_price(tick).unwrap();
+        let liquidity = liquidity_from_amounts(sqrt_price, amount_0, amount_1).unwrap();
+        let (deposit_0, deposit_1) = amounts_for_liquidity(sqrt_price, liquidity, true).unwrap();
+        assert!(deposit_0 <= amount_0 && deposit_1 <= amount_1);
+        let (withdraw_0, withdraw_1) = amounts_for_liquidity(sqrt_price, liquidity, false).unwrap();
+        assert!(withdraw_0 <= deposit_0 && withdraw_1 <= deposit_1);
+    }
+}
+
+#[test]
+fn exact_out_step_never_pays_out_more_than_asked_and_charges_at_least_exact_input() {
+    let liquidity = 1_000_000_000u128;
+    for (amount, zero_for_one) in [(1u64, true), (77, false), (10_000, true), (5_000_000, false)] {
+        let target = if zero_for_one { MIN_SQRT_PRICE_X64 } else { MAX_SQRT_PRICE_X64 };
+        let step = compute_swap_step_exact_out(Q64, target, liquidity, amount, 3000, zero_for_one).unwrap();
+        assert_eq!(step.amount_out, amount);
+        // Selling the quoted input exactly must buy at least the requested output
+        let forward = compute_swap_step(Q64, target, liquidity, step.amount_in + step.fee_amount, 3000, zero_for_one).unwrap();
+        assert!(forward.amount_out >= amount);
+    }
+}
+
+#[test]
+fn exact_out_step_stops_at_the_target() {
+    let target = tick_to_sqrt_price(100).unwrap();
+    let step = compute_swap_step_exact_out(Q64, target, 1_000_000_000, u32::MAX as u64, 3000, false).unwrap();
+    assert_eq!(step.sqrt_price_next_x64, target);
+    assert_eq!(step.amount_out as u128, get_amount_0_delta(Q64, target, 1_000_000_000, false).unwrap());
+    assert_eq!(step.amount_in as u128, get_amount_1_delta(Q64, target, 1_000_000_000, true).unwrap());
+}
+
+#[test]
+fn range_liquidity_round_trip_never_returns_more_than_deposited() {
+    for (lower, upper, amount) in [(0, 64, 1_000_000u64), (-6400, -6336, 7), (23_040, 23_104, u32::MAX as u64)] {
+        let (sqrt_lower, sqrt_upper) = (tick_to_sqrt_price(lower).unwrap(), tick_to_sqrt_price(upper).unwrap());
+        // Below the range only token 0 is held, above it only token 1
+        let liquidity_0 = liquidity_from_amount_0(sqrt_lower, sqrt_upper, amount).unwrap();
+        let (deposit_0, deposit_1) = amounts_for_range_liquidity(sqrt_lower, sqrt_lower, sqrt_upper, liquidity_0, true).unwrap();
+        assert!(deposit_0 <= amount && deposit_1 == 0);
+        let liquidity_1 = liquidity_from_amount_1(sqrt_lower, sqrt_upper, amount).unwrap();
+        let (deposit_0, deposit_1) = amounts_for_range_liquidity(sqrt_upper, sqrt_lower, sqrt_upper, liquidity_1, true).unwrap();
+        assert!(deposit_0 == 0 && deposit_1 <= amount);
+        // Inside the range the holdings are a mix that never exceeds the rounded-up deposit
+        let mid = (sqrt_lower + sqrt_upper) / 2;
+        let (inside_0, inside_1) = amounts_for_range_liquidity(mid, sqrt_lower, sqrt_upper, liquidity_1, false).unwrap();
+        assert!(inside_0 > 0 && inside_1 > 0 && inside_1 <= deposit_1);
+    }
+}
+
+#[test]
+fn sqrt_price_from_amounts_inverts_the_deposit_ratio() {
+    assert_eq!(sqrt_price_from_amounts(1_000_000, 1_000_000).unwrap(), Q64);
+    assert_eq!(sqrt_price_from_amounts(1, 4).unwrap(), 2 * Q64);
+    assert_eq!(sqrt_price_from_amounts(0, 1), Err(MathError::DivisionByZero));
+    for (amount_0, amount_1) in [(7u64, 900u64), (3_000_000, 1), (u64::MAX, u64::MAX - 1), (1, u32::MAX as u64)] {
+        let sqrt_price = sqrt_price_from_amounts(amount_0, amount_1).unwrap();
+        // Rounded down: squaring undershoots the ratio, one step up overshoots it
+        let ratio = |sqrt_price: u128| mul_div(mul_div(sqrt_price, sqrt_price, Q64, false).unwrap(), amount_0 as u128, Q64, false).unwrap();
+        assert!(ratio(sqrt_price) <= amount_1 as u128);
+        assert!(mul_div(mul_div(sqrt_price + 1, sqrt_price + 1, Q64, true).unwrap(), amount_0 as u128, Q64, true).unwrap() >= amount_1 as u128);
+        let liquidity = liquidity_from_amounts(sqrt_price, amount_0, amount_1).unwrap();
+        let (deposit_0, deposit_1) = amounts_for_liquidity(sqrt_price, liquidity, true).unwrap();
+        assert!(deposit_0 <= amount_0 && deposit_1 <= amount_1);
+    }
+}
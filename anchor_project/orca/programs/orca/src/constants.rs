@@ -1,12 +1,29 @@
 /// Tick constants
-pub const MIN_TICK: i32 = -887272;
-pub const MAX_TICK: i32 = 887272;
-pub const MIN_SQRT_PRICE: u128 = 4295128739;
-pub const MAX_SQRT_PRICE: u128 = u64::MAX as u128;
+pub const MIN_TICK: i32 = orca_math::MIN_TICK;
+pub const MAX_TICK: i32 = orca_math::MAX_TICK;
+pub const MIN_SQRT_PRICE: u128 = orca_math::MIN_SQRT_PRICE_X64;
+pub const MAX_SQRT_PRICE: u128 = orca_math::MAX_SQRT_PRICE_X64;
 
 /// Fee constants
 pub const MAX_FEE_RATE: u16 = 10000; // 1%
 pub const MAX_PROTOCOL_FEE_RATE: u16 = 10000; // 100% of trading fees
+pub const PROTOCOL_FEE_RATE_DENOMINATOR: u128 = 10000; // protocol_fee_rate is in bps of the trading fee
+pub const FEE_RATE_DENOMINATOR: u128 = orca_math::FEE_RATE_DENOMINATOR; // fee_rate is in hundredths of a bp
+
+/// Seconds over which the dynamic fee's volatility accumulator halves
+pub const VOLATILITY_HALF_LIFE: i64 = 60;
+
+/// Pool status bits; a set bit disables that operation on the pool
+pub const POOL_STATUS_SWAP_DISABLED: u8 = 1 << 0; // also covers quote_swap and flash loans
+pub const POOL_STATUS_DEPOSIT_DISABLED: u8 = 1 << 1;
+pub const POOL_STATUS_WITHDRAW_DISABLED: u8 = 1 << 2;
+pub const POOL_STATUS_OPEN_POSITION_DISABLED: u8 = 1 << 3;
+pub const POOL_STATUS_COLLECT_FEES_DISABLED: u8 = 1 << 4;
+pub const POOL_STATUS_ALL: u8 = (1 << 5) - 1;
+/// Operations emergency mode halts on top of the status bits; only the emergency withdrawals remain
+pub const EMERGENCY_MODE_DISABLED: u8 = POOL_STATUS_SWAP_DISABLED | POOL_STATUS_DEPOSIT_DISABLED | POOL_STATUS_WITHDRAW_DISABLED | POOL_STATUS_COLLECT_FEES_DISABLED;
+/// Seconds between scheduling an exit from emergency mode and being able to exit
+pub const EMERGENCY_EXIT_TIMELOCK: i64 = 3 * 24 * 60 * 60;
 
 /// Position constants
 pub const MAX_TICK_SPACING: u16 = 16384;
@@ -16,8 +33,31 @@ pub const MIN_TICK_SPACING: u16 = 1;
 pub const AMM_CONFIG_SEED: &str = "amm_config";
 pub const POOL_SEED: &str = "pool";
 pub const OBSERVATION_SEED: &str = "observation";
+pub const LIMIT_ORDER_BOOK_SEED: &str = "limit_order_book";
+pub const LIMIT_ORDER_SEED: &str = "limit_order";
+pub const POOL_CREATOR_SEED: &str = "pool_creator";
+pub const TOKEN_BADGE_SEED: &str = "token_badge";
 
 /// Vault authority seed
 pub const POOL_AUTHORITY_SEED: &str = "pool_authority";
 
-pub const Q64: u128 = 1u128 << 64; // added for fee growth scaling
\ No newline at end of file
+/// LP share mint seed and decimals
+pub const LP_MINT_SEED: &str = "lp_mint";
+pub const LP_MINT_DECIMALS: u8 = 9;
+/// Shares withheld from the first depositor and never minted, so the share price cannot be set by a dust deposit
+pub const MINIMUM_LIQUIDITY: u128 = 1000;
+/// The `limit_order_min_liquidity` pools start with. What it is worth in tokens
+/// depends on the tick spacing and the mints, so the config authority tunes it
+/// per pool with `set_limit_order_min_liquidity`.
+pub const DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY: u128 = 1_000_000;
+
+pub const Q64: u128 = orca_math::Q64; // added for fee growth scaling
+
+/// Layout version of every account type, kept in its `version` byte with
+/// `reserved` space for later fields. The types that predate versioning were
+/// stored at the layouts in `state::legacy`; `migrate_*` rewrites those
+/// accounts at this version. Types added since have no legacy layout.
+/// Version 2 gave pools back a `reserved` tail and a record of the fees owed to
+/// positions and a minimum limit-order size, and limit-order ranges their token balances; other types are
+/// laid out the same at versions 1 and 2.
+pub const ACCOUNT_VERSION: u8 = 2;
@@ -1,33 +1,45 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Token, TokenAccount, Mint};
-use crate::state::{AmmConfig, Pool, Position, ObservationState};
+use crate::state::{AmmConfig, Pool, Position, ObservationState, LimitOrderBook, LimitOrder, PoolCreator, TokenBadge};
 use crate::constants::*;
 use crate::errors::ErrorCode as OrcaErrorCode;
 
+/// Each config is its own venue, with its own authority and fee defaults;
+/// pools are created under one and bound to it. Only the program's upgrade
+/// authority creates configs, so a fresh deployment cannot be front-run.
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(index: u16)]
 pub struct InitializeAmm<'info> {
     #[account(
         init,
-        payer = authority,
+        payer = upgrade_authority,
         space = AmmConfig::SIZE,
-        seeds = [AMM_CONFIG_SEED.as_bytes()],
+        seeds = [AMM_CONFIG_SEED.as_bytes(), index.to_le_bytes().as_ref()],
         bump
     )]
     pub amm_config: Account<'info, AmmConfig>,
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub upgrade_authority: Signer<'info>,
+    #[account(
+        address = Pubkey::find_program_address(&[crate::ID.as_ref()], &ProgramData::owner()).0 @ OrcaErrorCode::InvalidProgramData,
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key()) @ OrcaErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    /// CHECK: key only; becomes the config's authority
+    pub authority: UncheckedAccount<'info>,
     /// CHECK: destination key only
     pub protocol_fee_destination: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(sqrt_price_x64: u128, tick_spacing: u16)]
 pub struct CreatePool<'info> {
     #[account(
-        seeds = [AMM_CONFIG_SEED.as_bytes()],
-        bump = amm_config.bump,
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
         constraint = amm_config.create_pool_enabled @ OrcaErrorCode::CreatePoolDisabled
     )]
     pub amm_config: Account<'info, AmmConfig>,
@@ -43,7 +55,7 @@ pub struct CreatePool<'info> {
         ],
         bump
     )]
-    pub pool: Account<'info, Pool>,
+    pub pool: AccountLoader<'info, Pool>,
     pub token_mint_0: Account<'info, Mint>,
     pub token_mint_1: Account<'info, Mint>,
     #[account(
@@ -66,6 +78,15 @@ pub struct CreatePool<'info> {
         bump
     )]
     pub pool_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [LP_MINT_SEED.as_bytes(), pool.key().as_ref()],
+        bump,
+        mint::decimals = LP_MINT_DECIMALS,
+        mint::authority = pool_authority
+    )]
+    pub lp_mint: Account<'info, Mint>,
     #[account(
         init,
         payer = creator,
@@ -73,60 +94,244 @@ pub struct CreatePool<'info> {
         seeds = [OBSERVATION_SEED.as_bytes(), pool.key().as_ref()],
         bump
     )]
-    pub observation_state: Account<'info, ObservationState>,
+    pub observation_state: AccountLoader<'info, ObservationState>,
+    #[account(
+        init,
+        payer = creator,
+        space = LimitOrderBook::SIZE,
+        seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()],
+        bump
+    )]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
     #[account(mut)]
     pub creator: Signer<'info>,
+    /// CHECK: PDA; needs to exist only under a config with `permissioned_pool_creation`
+    #[account(seeds = [POOL_CREATOR_SEED.as_bytes(), amm_config.key().as_ref(), creator.key().as_ref()], bump)]
+    pub pool_creator: UncheckedAccount<'info>,
+    /// CHECK: PDA; needs to exist only under a config with `token_badges_required`
+    #[account(seeds = [TOKEN_BADGE_SEED.as_bytes(), amm_config.key().as_ref(), token_mint_0.key().as_ref()], bump)]
+    pub token_badge_0: UncheckedAccount<'info>,
+    /// CHECK: as `token_badge_0`
+    #[account(seeds = [TOKEN_BADGE_SEED.as_bytes(), amm_config.key().as_ref(), token_mint_1.key().as_ref()], bump)]
+    pub token_badge_1: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+/// `CreatePool` plus the creator's first position and the token accounts that fund it.
+#[event_cpi]
 #[derive(Accounts)]
-pub struct ModifyLiquidity<'info> {
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
-    #[account(mut)]
+#[instruction(sqrt_price_x64: Option<u128>, tick_spacing: u16)]
+pub struct CreatePoolWithLiquidity<'info> {
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        constraint = amm_config.create_pool_enabled @ OrcaErrorCode::CreatePoolDisabled
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(
+        init,
+        payer = creator,
+        space = Pool::SIZE,
+        seeds = [
+            POOL_SEED.as_bytes(),
+            amm_config.key().as_ref(),
+            token_mint_0.key().as_ref(),
+            token_mint_1.key().as_ref()
+        ],
+        bump
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+    pub token_mint_0: Account<'info, Mint>,
+    pub token_mint_1: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = creator,
+        token::mint = token_mint_0,
+        token::authority = pool_authority
+    )]
     pub token_vault_0: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(
+        init,
+        payer = creator,
+        token::mint = token_mint_1,
+        token::authority = pool_authority
+    )]
     pub token_vault_1: Account<'info, TokenAccount>,
+    /// CHECK: PDA for pool authority
+    #[account(
+        seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [LP_MINT_SEED.as_bytes(), pool.key().as_ref()],
+        bump,
+        mint::decimals = LP_MINT_DECIMALS,
+        mint::authority = pool_authority
+    )]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = creator,
+        space = ObservationState::SIZE,
+        seeds = [OBSERVATION_SEED.as_bytes(), pool.key().as_ref()],
+        bump
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+    #[account(
+        init,
+        payer = creator,
+        space = LimitOrderBook::SIZE,
+        seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()],
+        bump
+    )]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
+    #[account(
+        init,
+        payer = creator,
+        space = Position::SIZE,
+        seeds = [b"position", pool.key().as_ref(), creator.key().as_ref()],
+        bump
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(mut, constraint = creator_token_account_0.mint == token_mint_0.key() @ OrcaErrorCode::InvalidMint)]
+    pub creator_token_account_0: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = creator_token_account_1.mint == token_mint_1.key() @ OrcaErrorCode::InvalidMint)]
+    pub creator_token_account_1: Box<Account<'info, TokenAccount>>,
     #[account(mut)]
+    pub creator: Signer<'info>,
+    /// CHECK: PDA; needs to exist only under a config with `permissioned_pool_creation`
+    #[account(seeds = [POOL_CREATOR_SEED.as_bytes(), amm_config.key().as_ref(), creator.key().as_ref()], bump)]
+    pub pool_creator: UncheckedAccount<'info>,
+    /// CHECK: PDA; needs to exist only under a config with `token_badges_required`
+    #[account(seeds = [TOKEN_BADGE_SEED.as_bytes(), amm_config.key().as_ref(), token_mint_0.key().as_ref()], bump)]
+    pub token_badge_0: UncheckedAccount<'info>,
+    /// CHECK: as `token_badge_0`
+    #[account(seeds = [TOKEN_BADGE_SEED.as_bytes(), amm_config.key().as_ref(), token_mint_1.key().as_ref()], bump)]
+    pub token_badge_1: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(mut, constraint = pool.load()?.flash_loan_locked == 0 @ OrcaErrorCode::PoolLocked)]
+    pub pool: AccountLoader<'info, Pool>,
+    #[account(mut, address = pool.load()?.token_vault_0 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_0: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.load()?.token_vault_1 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_1: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_account_0.mint == pool.load()?.token_mint_0 @ OrcaErrorCode::InvalidMint)]
     pub user_token_account_0: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(mut, constraint = user_token_account_1.mint == pool.load()?.token_mint_1 @ OrcaErrorCode::InvalidMint)]
     pub user_token_account_1: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.load()?.lp_mint @ OrcaErrorCode::InvalidMint)]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(mut, constraint = user_lp_token_account.mint == pool.load()?.lp_mint @ OrcaErrorCode::InvalidMint)]
+    pub user_lp_token_account: Account<'info, TokenAccount>,
     /// CHECK: PDA
-    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.auth_bump)]
+    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.load()?.auth_bump)]
     pub pool_authority: UncheckedAccount<'info>,
+    #[account(seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()], bump = limit_order_book.load()?.bump)]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
     pub user: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(amount_in: u64, minimum_amount_out: u64, zero_for_one: bool)]
 pub struct Swap<'info> {
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
-    #[account(constraint = amm_config.swap_enabled @ OrcaErrorCode::SwapDisabled)]
+    #[account(
+        mut,
+        has_one = amm_config @ OrcaErrorCode::InvalidPoolConfig,
+        constraint = pool.load()?.flash_loan_locked == 0 @ OrcaErrorCode::PoolLocked,
+        constraint = pool.load()?.is_enabled(POOL_STATUS_SWAP_DISABLED) @ OrcaErrorCode::PoolOperationDisabled
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        constraint = amm_config.swap_enabled @ OrcaErrorCode::SwapDisabled
+    )]
     pub amm_config: Account<'info, AmmConfig>,
-    #[account(mut)]
+    #[account(mut, constraint = user_token_account_in.mint == token_vault_in.mint @ OrcaErrorCode::InvalidMint)]
     pub user_token_account_in: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(mut, constraint = user_token_account_out.mint == token_vault_out.mint @ OrcaErrorCode::InvalidMint)]
     pub user_token_account_out: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(
+        mut,
+        address = if zero_for_one { pool.load()?.token_vault_0 } else { pool.load()?.token_vault_1 } @ OrcaErrorCode::InvalidTokenAccount
+    )]
     pub token_vault_in: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(
+        mut,
+        address = if zero_for_one { pool.load()?.token_vault_1 } else { pool.load()?.token_vault_0 } @ OrcaErrorCode::InvalidTokenAccount
+    )]
     pub token_vault_out: Account<'info, TokenAccount>,
     /// CHECK: PDA
-    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.auth_bump)]
+    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.load()?.auth_bump)]
     pub pool_authority: UncheckedAccount<'info>,
-    #[account(mut, seeds = [OBSERVATION_SEED.as_bytes(), pool.key().as_ref()], bump = observation_state.bump)]
-    pub observation_state: Account<'info, ObservationState>,
+    #[account(mut, seeds = [OBSERVATION_SEED.as_bytes(), pool.key().as_ref()], bump = observation_state.load()?.bump)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+    #[account(mut, seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()], bump = limit_order_book.load()?.bump)]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
     pub user: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateAmmConfig<'info> {
+    #[account(
+        mut,
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    pub authority: Signer<'info>,
+}
+
+/// Shared by the config authority's per-pool settings: status bits, dynamic
+/// fee parameters and emergency mode.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PoolAdmin<'info> {
+    #[account(mut, has_one = amm_config @ OrcaErrorCode::InvalidPoolConfig)]
+    pub pool: AccountLoader<'info, Pool>,
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    #[account(
+        has_one = amm_config @ OrcaErrorCode::InvalidPoolConfig,
+        constraint = pool.load()?.is_enabled(POOL_STATUS_SWAP_DISABLED) @ OrcaErrorCode::PoolOperationDisabled
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        constraint = amm_config.swap_enabled @ OrcaErrorCode::SwapDisabled
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()], bump = limit_order_book.load()?.bump)]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct OpenPosition<'info> {
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
+    #[account(mut, constraint = pool.load()?.is_enabled(POOL_STATUS_OPEN_POSITION_DISABLED) @ OrcaErrorCode::PoolOperationDisabled)]
+    pub pool: AccountLoader<'info, Pool>,
     #[account(mut)]
     pub owner: Signer<'info>,
     #[account(
@@ -140,23 +345,299 @@ pub struct OpenPosition<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ModifyPositionLiquidity<'info> {
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
+    #[account(mut, constraint = pool.load()?.flash_loan_locked == 0 @ OrcaErrorCode::PoolLocked)]
+    pub pool: AccountLoader<'info, Pool>,
     #[account(mut, has_one = pool, has_one = owner)]
     pub position: Account<'info, Position>,
     pub owner: Signer<'info>,
-    #[account(mut)]
+    #[account(mut, address = pool.load()?.token_vault_0 @ OrcaErrorCode::InvalidTokenAccount)]
     pub token_vault_0: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(mut, address = pool.load()?.token_vault_1 @ OrcaErrorCode::InvalidTokenAccount)]
     pub token_vault_1: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(mut, constraint = user_token_account_0.mint == pool.load()?.token_mint_0 @ OrcaErrorCode::InvalidMint)]
+    pub user_token_account_0: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_account_1.mint == pool.load()?.token_mint_1 @ OrcaErrorCode::InvalidMint)]
+    pub user_token_account_1: Account<'info, TokenAccount>,
+    /// CHECK: PDA
+    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.load()?.auth_bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+    #[account(seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()], bump = limit_order_book.load()?.bump)]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Shared by `flash_borrow` and `flash_repay`; `pool` must stay the first
+/// account, since the borrow finds its repayment by it.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut, has_one = amm_config @ OrcaErrorCode::InvalidPoolConfig)]
+    pub pool: AccountLoader<'info, Pool>,
+    #[account(constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig)]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(mut, address = pool.load()?.token_vault_0 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_0: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.load()?.token_vault_1 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_1: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_account_0.mint == pool.load()?.token_mint_0 @ OrcaErrorCode::InvalidMint)]
     pub user_token_account_0: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_account_1.mint == pool.load()?.token_mint_1 @ OrcaErrorCode::InvalidMint)]
+    pub user_token_account_1: Account<'info, TokenAccount>,
+    /// CHECK: PDA
+    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.load()?.auth_bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: instructions sysvar, read through the introspection helpers
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(tick_lower: i32)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(
+        constraint = pool.load()?.flash_loan_locked == 0 @ OrcaErrorCode::PoolLocked,
+        constraint = pool.load()?.is_enabled(POOL_STATUS_DEPOSIT_DISABLED) @ OrcaErrorCode::PoolOperationDisabled
+    )]
+    pub pool: AccountLoader<'info, Pool>,
+    #[account(mut, seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()], bump = limit_order_book.load()?.bump)]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
+    #[account(
+        init,
+        payer = owner,
+        space = LimitOrder::SIZE,
+        seeds = [LIMIT_ORDER_SEED.as_bytes(), pool.key().as_ref(), owner.key().as_ref(), &tick_lower.to_le_bytes()],
+        bump
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
     #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, address = pool.load()?.token_vault_0 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_0: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.load()?.token_vault_1 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_1: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_account_0.mint == pool.load()?.token_mint_0 @ OrcaErrorCode::InvalidMint)]
+    pub user_token_account_0: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_account_1.mint == pool.load()?.token_mint_1 @ OrcaErrorCode::InvalidMint)]
     pub user_token_account_1: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by `cancel_limit_order` and `claim_limit_order`. Proceeds always go
+/// to the owner's token accounts and the rent back to the owner, so a keeper
+/// can sign a claim on the owner's behalf.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseLimitOrder<'info> {
+    #[account(constraint = pool.load()?.flash_loan_locked == 0 @ OrcaErrorCode::PoolLocked)]
+    pub pool: AccountLoader<'info, Pool>,
+    #[account(mut, seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()], bump = limit_order_book.load()?.bump)]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
+    #[account(mut, has_one = pool, has_one = owner, close = owner)]
+    pub limit_order: Account<'info, LimitOrder>,
+    /// CHECK: the order's owner, enforced by `has_one`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+    #[account(mut, address = pool.load()?.token_vault_0 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_0: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.load()?.token_vault_1 @ OrcaErrorCode::InvalidTokenAccount)]
+    pub token_vault_1: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_token_account_0.mint == pool.load()?.token_mint_0 @ OrcaErrorCode::InvalidMint,
+        constraint = owner_token_account_0.owner == owner.key() @ OrcaErrorCode::InvalidTokenAccount
+    )]
+    pub owner_token_account_0: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_token_account_1.mint == pool.load()?.token_mint_1 @ OrcaErrorCode::InvalidMint,
+        constraint = owner_token_account_1.owner == owner.key() @ OrcaErrorCode::InvalidTokenAccount
+    )]
+    pub owner_token_account_1: Account<'info, TokenAccount>,
     /// CHECK: PDA
-    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.auth_bump)]
+    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump = pool.load()?.auth_bump)]
     pub pool_authority: UncheckedAccount<'info>,
     pub token_program: Program<'info, Token>,
 }
+
+/// A config still at the legacy layout does not deserialize as `AmmConfig`,
+/// so the handler checks its authority against the legacy bytes.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateAmmConfig<'info> {
+    /// CHECK: discriminator, layout and authority checked by the handler
+    #[account(mut, owner = crate::ID)]
+    pub amm_config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Configs have to be migrated first. A config's authority may only migrate
+/// its own pools, and pays the extra rent and the accounts pools lacked.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// CHECK: discriminator, layout and config checked by the handler
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    /// CHECK: PDA for pool authority
+    #[account(seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [LP_MINT_SEED.as_bytes(), pool.key().as_ref()],
+        bump,
+        mint::decimals = LP_MINT_DECIMALS,
+        mint::authority = pool_authority
+    )]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        space = LimitOrderBook::SIZE,
+        seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()],
+        bump
+    )]
+    pub limit_order_book: AccountLoader<'info, LimitOrderBook>,
+    /// The pool's vaults, checked by the handler; what they hold beyond
+    /// everything else sets the fees owed to positions
+    pub token_vault_0: Account<'info, TokenAccount>,
+    pub token_vault_1: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// A version 1 pool is migrated together with its limit order book, whose
+/// balances the fees owed to its positions are set against.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigratePoolV1<'info> {
+    /// CHECK: discriminator, layout and config checked by the handler
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    /// CHECK: discriminator and layout checked by the handler
+    #[account(mut, owner = crate::ID, seeds = [LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.key().as_ref()], bump)]
+    pub limit_order_book: UncheckedAccount<'info>,
+    /// The pool's vaults, checked by the handler
+    pub token_vault_0: Account<'info, TokenAccount>,
+    pub token_vault_1: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by the `migrate_*` instructions for accounts under a pool, which
+/// may be migrated before or after the pool. A config's authority may only migrate the
+/// accounts under its own pools, and pays the extra rent.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: discriminator and layout checked by the handler
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    /// CHECK: the pool `account` belongs to, read at any of its layouts by the handler
+    #[account(owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AddPoolCreator<'info> {
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = PoolCreator::SIZE,
+        seeds = [POOL_CREATOR_SEED.as_bytes(), amm_config.key().as_ref(), creator.key().as_ref()],
+        bump
+    )]
+    pub pool_creator: Account<'info, PoolCreator>,
+    /// CHECK: any key may be allowlisted
+    pub creator: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemovePoolCreator<'info> {
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(mut, has_one = amm_config @ OrcaErrorCode::InvalidPoolConfig, close = authority)]
+    pub pool_creator: Account<'info, PoolCreator>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AddTokenBadge<'info> {
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = TokenBadge::SIZE,
+        seeds = [TOKEN_BADGE_SEED.as_bytes(), amm_config.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_badge: Account<'info, TokenBadge>,
+    pub token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemoveTokenBadge<'info> {
+    #[account(
+        constraint = amm_config.is_address(&amm_config.key()) @ OrcaErrorCode::InvalidPoolConfig,
+        has_one = authority @ OrcaErrorCode::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+    #[account(mut, has_one = amm_config @ OrcaErrorCode::InvalidPoolConfig, close = authority)]
+    pub token_badge: Account<'info, TokenBadge>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use orca_math::MathError;
 
 #[error_code]
 pub enum ErrorCode {
@@ -166,4 +167,57 @@ pub enum ErrorCode {
     
     #[msg("Amount out too low")]
     AmountOutTooLow,
-}
\ No newline at end of file
+
+    #[msg("Pool is locked by an outstanding flash loan")]
+    PoolLocked,
+
+    #[msg("Flash loan is not repaid later in the transaction")]
+    FlashLoanNotRepaid,
+
+    #[msg("No flash loan outstanding")]
+    NoFlashLoanOutstanding,
+
+    #[msg("Operation disabled for this pool")]
+    PoolOperationDisabled,
+
+    #[msg("Invalid pool status")]
+    InvalidPoolStatus,
+
+    #[msg("Pool is not in emergency mode")]
+    NotInEmergencyMode,
+
+    #[msg("Emergency mode exit is not scheduled or its timelock has not passed")]
+    EmergencyExitTimelocked,
+
+    #[msg("Every limit order range of the pool is in use")]
+    LimitOrderBookFull,
+
+    #[msg("Limit order is already filled")]
+    LimitOrderFilled,
+
+    #[msg("Limit order is not filled yet")]
+    LimitOrderNotFilled,
+
+    #[msg("Account already has the current layout")]
+    AccountAlreadyMigrated,
+
+    #[msg("Creator is not allowed to create pools under this config")]
+    PoolCreatorNotAllowed,
+
+    #[msg("Mint has no token badge under this config")]
+    TokenBadgeRequired,
+
+    #[msg("Limit order liquidity is below the pool's minimum")]
+    LimitOrderTooSmall,
+}
+
+impl From<MathError> for ErrorCode {
+    fn from(err: MathError) -> Self {
+        match err {
+            MathError::Overflow => ErrorCode::MathOverflow,
+            MathError::DivisionByZero => ErrorCode::DivisionByZero,
+            MathError::TickOutOfBounds => ErrorCode::TickOutOfBounds,
+            MathError::PriceOutOfBounds => ErrorCode::PriceOutOfBounds,
+        }
+    }
+}
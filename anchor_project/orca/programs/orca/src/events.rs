@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub amm_config: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub lp_mint: Pubkey,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub tick_spacing: u16,
+    pub fee_rate: u16,
+}
+
+/// Pool-level deposits and withdrawals as well as position changes; `position`
+/// is `None` for pool-level liquidity, where `lp_amount` shares were minted or burned.
+#[event]
+pub struct LiquidityChanged {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub position: Option<Pubkey>,
+    pub increase: bool,
+    pub liquidity_delta: u128,
+    pub amount_token_0: u64,
+    pub amount_token_1: u64,
+    pub lp_amount: u64,
+    pub pool_liquidity: u128,
+}
+
+#[event]
+pub struct Swap {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub zero_for_one: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub sqrt_price_before_x64: u128,
+    pub sqrt_price_after_x64: u128,
+    pub tick_before: i32,
+    pub tick_after: i32,
+    pub liquidity: u128,
+    pub fee_rate: u16,
+}
+
+#[event]
+pub struct PositionOpened {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_token_0: u64,
+    pub amount_token_1: u64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub amm_config: Pubkey,
+    pub index: u16,
+    pub authority: Pubkey,
+    pub default_fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    pub protocol_fee_destination: Pubkey,
+    pub create_pool_enabled: bool,
+    pub swap_enabled: bool,
+    pub flash_fee_rate: u16,
+    pub permissioned_pool_creation: bool,
+    pub token_badges_required: bool,
+}
+
+#[event]
+pub struct PoolStatusUpdated {
+    pub pool: Pubkey,
+    pub status: u8,
+}
+
+#[event]
+pub struct EmergencyModeUpdated {
+    pub pool: Pubkey,
+    pub emergency_mode: bool,
+    pub emergency_exit_after: i64,
+}
+
+#[event]
+pub struct DynamicFeeUpdated {
+    pub pool: Pubkey,
+    pub dynamic_fee_max_rate: u16,
+    pub dynamic_fee_rate_per_tick: u16,
+}
+
+#[event]
+pub struct LimitOrderMinLiquidityUpdated {
+    pub pool: Pubkey,
+    pub limit_order_min_liquidity: u128,
+}
+
+/// Emitted when a flash loan is drawn; `FlashLoan` follows on repayment.
+#[event]
+pub struct FlashBorrow {
+    pub pool: Pubkey,
+    pub borrower: Pubkey,
+    pub amount_token_0: u64,
+    pub amount_token_1: u64,
+}
+
+/// Emitted on repayment; the fees are credited to LP fee growth.
+#[event]
+pub struct FlashLoan {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_token_0: u64,
+    pub amount_token_1: u64,
+    pub fee_token_0: u64,
+    pub fee_token_1: u64,
+}
+
+#[event]
+pub struct LimitOrderPlaced {
+    pub pool: Pubkey,
+    pub limit_order: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub sells_token_0: bool,
+    pub liquidity: u128,
+    pub amount: u64,
+}
+
+/// Emitted by the swap that carries the price through the range's far side.
+#[event]
+pub struct LimitOrderRangeFilled {
+    pub pool: Pubkey,
+    pub tick_lower: i32,
+    pub liquidity: u128,
+}
+
+/// Emitted by both cancels and claims; the amounts include the fees.
+#[event]
+pub struct LimitOrderClosed {
+    pub pool: Pubkey,
+    pub limit_order: Pubkey,
+    pub owner: Pubkey,
+    pub filled: bool,
+    pub amount_token_0: u64,
+    pub amount_token_1: u64,
+    pub fee_token_0: u64,
+    pub fee_token_1: u64,
+}
+
+/// Emitted by the `migrate_*` instructions.
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub version: u8,
+    pub size: u32,
+}
+
+#[event]
+pub struct PoolCreatorUpdated {
+    pub amm_config: Pubkey,
+    pub creator: Pubkey,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct TokenBadgeUpdated {
+    pub amm_config: Pubkey,
+    pub token_mint: Pubkey,
+    pub badged: bool,
+}
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode, events::{ConfigUpdated, DynamicFeeUpdated, LimitOrderMinLiquidityUpdated, PoolStatusUpdated}};
+use crate::state::AmmConfig;
+
+/// The event every change to a config emits, carrying all of its settings.
+pub(crate) fn config_updated(amm_config: &Account<AmmConfig>) -> ConfigUpdated {
+    ConfigUpdated {
+        amm_config: amm_config.key(),
+        index: amm_config.index,
+        authority: amm_config.authority,
+        default_fee_rate: amm_config.default_fee_rate,
+        protocol_fee_rate: amm_config.protocol_fee_rate,
+        protocol_fee_destination: amm_config.protocol_fee_destination,
+        create_pool_enabled: amm_config.create_pool_enabled,
+        swap_enabled: amm_config.swap_enabled,
+        flash_fee_rate: amm_config.flash_fee_rate,
+        permissioned_pool_creation: amm_config.permissioned_pool_creation,
+        token_badges_required: amm_config.token_badges_required,
+    }
+}
+
+pub fn set_flash_fee_rate(ctx: Context<UpdateAmmConfig>, flash_fee_rate: u16) -> Result<()> {
+    require!(flash_fee_rate <= MAX_FEE_RATE, OrcaErrorCode::InvalidFeeRate);
+    let amm_config = &mut ctx.accounts.amm_config;
+    amm_config.flash_fee_rate = flash_fee_rate;
+    emit_cpi!(config_updated(amm_config));
+    msg!("Set flash fee rate to {}", flash_fee_rate);
+    Ok(())
+}
+
+/// Turns the creator allowlist and the token badge requirement for new pools
+/// under the config on or off; pools that already exist are unaffected.
+pub fn set_pool_creation_policy(ctx: Context<UpdateAmmConfig>, permissioned_pool_creation: bool, token_badges_required: bool) -> Result<()> {
+    let amm_config = &mut ctx.accounts.amm_config;
+    amm_config.permissioned_pool_creation = permissioned_pool_creation;
+    amm_config.token_badges_required = token_badges_required;
+    emit_cpi!(config_updated(amm_config));
+    msg!("Set pool creation policy: permissioned {}, token badges required {}", permissioned_pool_creation, token_badges_required);
+    Ok(())
+}
+
+/// Replaces the pool's status bits; see `POOL_STATUS_*`.
+pub fn set_pool_status(ctx: Context<PoolAdmin>, status: u8) -> Result<()> {
+    require!(status & !POOL_STATUS_ALL == 0, OrcaErrorCode::InvalidPoolStatus);
+    ctx.accounts.pool.load_mut()?.status = status;
+    let pool_key = ctx.accounts.pool.key();
+    emit_cpi!(PoolStatusUpdated { pool: pool_key, status });
+    msg!("Set pool {} status to {:#07b}", pool_key, status);
+    Ok(())
+}
+
+/// Turns dynamic fees on with `dynamic_fee_rate_per_tick` of extra fee per tick of
+/// recent movement, capped at `dynamic_fee_max_rate`; a rate per tick of 0 turns them off.
+pub fn set_dynamic_fee(ctx: Context<PoolAdmin>, dynamic_fee_max_rate: u16, dynamic_fee_rate_per_tick: u16) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(dynamic_fee_max_rate >= pool.fee_rate && dynamic_fee_max_rate <= MAX_FEE_RATE, OrcaErrorCode::InvalidFeeRate);
+    pool.dynamic_fee_max_rate = dynamic_fee_max_rate;
+    pool.dynamic_fee_rate_per_tick = dynamic_fee_rate_per_tick;
+    emit_cpi!(DynamicFeeUpdated { pool: pool_key, dynamic_fee_max_rate, dynamic_fee_rate_per_tick });
+    msg!("Set pool {} dynamic fee: {} per tick, capped at {}", pool_key, dynamic_fee_rate_per_tick, dynamic_fee_max_rate);
+    Ok(())
+}
+
+/// Orders already in the book keep their slots; only new ones must clear the minimum.
+pub fn set_limit_order_min_liquidity(ctx: Context<PoolAdmin>, limit_order_min_liquidity: u128) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    ctx.accounts.pool.load_mut()?.limit_order_min_liquidity = limit_order_min_liquidity;
+    emit_cpi!(LimitOrderMinLiquidityUpdated { pool: pool_key, limit_order_min_liquidity });
+    msg!("Set pool {} limit order minimum liquidity to {}", pool_key, limit_order_min_liquidity);
+    Ok(())
+}
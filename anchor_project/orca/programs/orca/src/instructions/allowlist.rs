@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::{contexts::*, constants::*, events::{PoolCreatorUpdated, TokenBadgeUpdated}};
+
+/// Lets `creator` create pools under a config with `permissioned_pool_creation`.
+pub fn add_pool_creator(ctx: Context<AddPoolCreator>) -> Result<()> {
+    let pool_creator = &mut ctx.accounts.pool_creator;
+    pool_creator.bump = ctx.bumps.pool_creator;
+    pool_creator.amm_config = ctx.accounts.amm_config.key();
+    pool_creator.creator = ctx.accounts.creator.key();
+    pool_creator.version = ACCOUNT_VERSION;
+    emit_cpi!(PoolCreatorUpdated { amm_config: pool_creator.amm_config, creator: pool_creator.creator, allowed: true });
+    msg!("Added pool creator {}", pool_creator.creator);
+    Ok(())
+}
+
+pub fn remove_pool_creator(ctx: Context<RemovePoolCreator>) -> Result<()> {
+    let pool_creator = &ctx.accounts.pool_creator;
+    emit_cpi!(PoolCreatorUpdated { amm_config: pool_creator.amm_config, creator: pool_creator.creator, allowed: false });
+    msg!("Removed pool creator {}", pool_creator.creator);
+    Ok(())
+}
+
+/// Lets `token_mint` into new pools under a config with `token_badges_required`.
+pub fn add_token_badge(ctx: Context<AddTokenBadge>) -> Result<()> {
+    let token_badge = &mut ctx.accounts.token_badge;
+    token_badge.bump = ctx.bumps.token_badge;
+    token_badge.amm_config = ctx.accounts.amm_config.key();
+    token_badge.token_mint = ctx.accounts.token_mint.key();
+    token_badge.version = ACCOUNT_VERSION;
+    emit_cpi!(TokenBadgeUpdated { amm_config: token_badge.amm_config, token_mint: token_badge.token_mint, badged: true });
+    msg!("Added token badge for mint {}", token_badge.token_mint);
+    Ok(())
+}
+
+/// Pools that already hold the mint are unaffected.
+pub fn remove_token_badge(ctx: Context<RemoveTokenBadge>) -> Result<()> {
+    let token_badge = &ctx.accounts.token_badge;
+    emit_cpi!(TokenBadgeUpdated { amm_config: token_badge.amm_config, token_mint: token_badge.token_mint, badged: false });
+    msg!("Removed token badge for mint {}", token_badge.token_mint);
+    Ok(())
+}
@@ -1,29 +1,160 @@
 use anchor_lang::prelude::*;
-use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode};
+use anchor_spl::token::{self, Mint, TokenAccount, Transfer};
+use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode, events::{LiquidityChanged, PoolCreated, PositionOpened}};
+use crate::state::{AmmConfig, LimitOrderBook, ObservationState, Pool};
+use orca_math::{amounts_for_liquidity, liquidity_from_amounts, sqrt_price_from_amounts};
+use super::liquidity::LiquidityResult;
 
 pub fn handler(
     ctx: Context<CreatePool>,
     sqrt_price_x64: u128,
     tick_spacing: u16,
 ) -> Result<()> {
-    require!(sqrt_price_x64 >= MIN_SQRT_PRICE && sqrt_price_x64 <= MAX_SQRT_PRICE, OrcaErrorCode::InvalidSqrtPrice);
-    require!(tick_spacing >= MIN_TICK_SPACING && tick_spacing <= MAX_TICK_SPACING, OrcaErrorCode::InvalidTickSpacing);
-    require!(ctx.accounts.token_mint_0.key() < ctx.accounts.token_mint_1.key(), OrcaErrorCode::InvalidTokenOrder);
-    let current_tick = 0; // placeholder to avoid pulling math module while resolving build
+    let accounts = &ctx.accounts;
+    let created = initialize(
+        NewPool {
+            amm_config: &accounts.amm_config,
+            pool: &accounts.pool,
+            token_mint_0: &accounts.token_mint_0,
+            token_mint_1: &accounts.token_mint_1,
+            token_vault_0: &accounts.token_vault_0,
+            token_vault_1: &accounts.token_vault_1,
+            lp_mint: &accounts.lp_mint,
+            observation_state: &accounts.observation_state,
+            limit_order_book: &accounts.limit_order_book,
+            pool_creator: &accounts.pool_creator,
+            token_badge_0: &accounts.token_badge_0,
+            token_badge_1: &accounts.token_badge_1,
+        },
+        [ctx.bumps.pool, ctx.bumps.pool_authority, ctx.bumps.observation_state, ctx.bumps.limit_order_book],
+        sqrt_price_x64,
+        tick_spacing,
+    )?;
+    emit_cpi!(created);
+    Ok(())
+}
+
+/// Creates the pool and opens the creator's position with its first deposit in
+/// one transaction, so nobody can trade against the pool before it holds
+/// liquidity. Without an explicit `sqrt_price_x64` the price is the one at
+/// which the two amounts are of equal value; with one, the amounts are
+/// maximums as in `increase_position_liquidity`.
+pub fn create_pool_with_liquidity(
+    ctx: Context<CreatePoolWithLiquidity>,
+    sqrt_price_x64: Option<u128>,
+    tick_spacing: u16,
+    amount_token_0: u64,
+    amount_token_1: u64,
+) -> Result<LiquidityResult> {
+    require!(amount_token_0 > 0 && amount_token_1 > 0, OrcaErrorCode::InvalidAmount);
+    let sqrt_price_x64 = match sqrt_price_x64 {
+        Some(sqrt_price_x64) => sqrt_price_x64,
+        None => sqrt_price_from_amounts(amount_token_0, amount_token_1).map_err(OrcaErrorCode::from)?,
+    };
+    let accounts = &ctx.accounts;
+    let created = initialize(
+        NewPool {
+            amm_config: &accounts.amm_config,
+            pool: &accounts.pool,
+            token_mint_0: &accounts.token_mint_0,
+            token_mint_1: &accounts.token_mint_1,
+            token_vault_0: &accounts.token_vault_0,
+            token_vault_1: &accounts.token_vault_1,
+            lp_mint: &accounts.lp_mint,
+            observation_state: &accounts.observation_state,
+            limit_order_book: &accounts.limit_order_book,
+            pool_creator: &accounts.pool_creator,
+            token_badge_0: &accounts.token_badge_0,
+            token_badge_1: &accounts.token_badge_1,
+        },
+        [ctx.bumps.pool, ctx.bumps.pool_authority, ctx.bumps.observation_state, ctx.bumps.limit_order_book],
+        sqrt_price_x64,
+        tick_spacing,
+    )?;
+    emit_cpi!(created);
+    let liquidity = liquidity_from_amounts(sqrt_price_x64, amount_token_0, amount_token_1).map_err(OrcaErrorCode::from)?;
+    require!(liquidity > 0, OrcaErrorCode::InvalidAmount);
+    let (amount_token_0, amount_token_1) = amounts_for_liquidity(sqrt_price_x64, liquidity, true).map_err(OrcaErrorCode::from)?;
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    token::transfer(CpiContext::new(cpi_program.clone(), Transfer { from: ctx.accounts.creator_token_account_0.to_account_info(), to: ctx.accounts.token_vault_0.to_account_info(), authority: ctx.accounts.creator.to_account_info() }), amount_token_0)?;
+    token::transfer(CpiContext::new(cpi_program, Transfer { from: ctx.accounts.creator_token_account_1.to_account_info(), to: ctx.accounts.token_vault_1.to_account_info(), authority: ctx.accounts.creator.to_account_info() }), amount_token_1)?;
+    // Anchor writes a zero-copy account's discriminator on exit, so until then the new pool only loads through `load_init`.
+    ctx.accounts.pool.load_init()?.liquidity = liquidity;
+    let position = &mut ctx.accounts.position;
+    position.bump = ctx.bumps.position;
+    position.pool = ctx.accounts.pool.key();
+    position.owner = ctx.accounts.creator.key();
+    position.liquidity = liquidity;
+    position.fee_growth_entry_0_x64 = 0;
+    position.fee_growth_entry_1_x64 = 0;
+    position.tokens_owed_0 = 0;
+    position.tokens_owed_1 = 0;
+    position.version = ACCOUNT_VERSION;
+    emit_cpi!(PositionOpened { pool: position.pool, position: position.key(), owner: position.owner });
+    emit_cpi!(LiquidityChanged {
+        pool: position.pool,
+        user: position.owner,
+        position: Some(position.key()),
+        increase: true,
+        liquidity_delta: liquidity,
+        amount_token_0,
+        amount_token_1,
+        lp_amount: 0,
+        pool_liquidity: liquidity,
+    });
+    msg!("Seeded pool with liquidity {} for token0={}, token1={}", liquidity, amount_token_0, amount_token_1);
+    Ok(LiquidityResult { liquidity_delta: liquidity, amount_token_0, amount_token_1, lp_amount: 0 })
+}
+
+/// The accounts `create_pool` and `create_pool_with_liquidity` both initialize.
+struct NewPool<'a, 'info> {
+    amm_config: &'a Account<'info, AmmConfig>,
+    pool: &'a AccountLoader<'info, Pool>,
+    token_mint_0: &'a Account<'info, Mint>,
+    token_mint_1: &'a Account<'info, Mint>,
+    token_vault_0: &'a Account<'info, TokenAccount>,
+    token_vault_1: &'a Account<'info, TokenAccount>,
+    lp_mint: &'a Account<'info, Mint>,
+    observation_state: &'a AccountLoader<'info, ObservationState>,
+    limit_order_book: &'a AccountLoader<'info, LimitOrderBook>,
+    pool_creator: &'a UncheckedAccount<'info>,
+    token_badge_0: &'a UncheckedAccount<'info>,
+    token_badge_1: &'a UncheckedAccount<'info>,
+}
+
+/// Whether the allowlist PDA has been created; the seeds are checked by the context.
+fn exists(account: &AccountInfo) -> bool {
+    account.owner == &crate::ID && !account.data_is_empty()
+}
+
+/// Validates the price and spacing and writes the new pool's initial state.
+/// `bumps` are the pool, pool authority, observation and order book bumps.
+fn initialize(accounts: NewPool, bumps: [u8; 4], sqrt_price_x64: u128, tick_spacing: u16) -> Result<PoolCreated> {
+    require!((MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price_x64), OrcaErrorCode::InvalidSqrtPrice);
+    require!((MIN_TICK_SPACING..=MAX_TICK_SPACING).contains(&tick_spacing), OrcaErrorCode::InvalidTickSpacing);
+    require!(accounts.token_mint_0.key() < accounts.token_mint_1.key(), OrcaErrorCode::InvalidTokenOrder);
+    let config = accounts.amm_config;
+    require!(!config.permissioned_pool_creation || exists(accounts.pool_creator), OrcaErrorCode::PoolCreatorNotAllowed);
+    for badge in [accounts.token_badge_0, accounts.token_badge_1] {
+        require!(!config.token_badges_required || exists(badge), OrcaErrorCode::TokenBadgeRequired);
+    }
+    let current_tick = orca_math::sqrt_price_to_tick(sqrt_price_x64).map_err(OrcaErrorCode::from)?;
     let clock = Clock::get()?;
-    let pool = &mut ctx.accounts.pool;
-    pool.bump = ctx.bumps.pool;
-    pool.amm_config = ctx.accounts.amm_config.key();
-    pool.token_mint_0 = ctx.accounts.token_mint_0.key();
-    pool.token_mint_1 = ctx.accounts.token_mint_1.key();
-    pool.token_vault_0 = ctx.accounts.token_vault_0.key();
-    pool.token_vault_1 = ctx.accounts.token_vault_1.key();
-    pool.auth_bump = ctx.bumps.pool_authority;
+    let [pool_bump, auth_bump, observation_bump, book_bump] = bumps;
+    let pool_key = accounts.pool.key();
+    let mut pool = accounts.pool.load_init()?;
+    pool.bump = pool_bump;
+    pool.amm_config = accounts.amm_config.key();
+    pool.token_mint_0 = accounts.token_mint_0.key();
+    pool.token_mint_1 = accounts.token_mint_1.key();
+    pool.token_vault_0 = accounts.token_vault_0.key();
+    pool.token_vault_1 = accounts.token_vault_1.key();
+    pool.auth_bump = auth_bump;
     pool.sqrt_price_x64 = sqrt_price_x64;
     pool.liquidity = 0;
     pool.tick_current = current_tick;
     pool.tick_spacing = tick_spacing;
-    pool.fee_rate = ctx.accounts.amm_config.default_fee_rate;
+    pool.fee_rate = accounts.amm_config.default_fee_rate;
     pool.protocol_fees_token_0 = 0;
     pool.protocol_fees_token_1 = 0;
     pool.fee_growth_global_0_x64 = 0;
@@ -31,13 +162,51 @@ pub fn handler(
     pool.fee_split_lp_bps = 2500;
     pool.fee_split_protocol_bps = 400;
     pool.fee_split_impact_bps = 100;
-    let obs = &mut ctx.accounts.observation_state;
-    obs.bump = ctx.bumps.observation_state;
-    obs.pool = pool.key();
+    pool.lp_mint = accounts.lp_mint.key();
+    pool.lp_liquidity = 0;
+    pool.flash_loan_locked = 0;
+    pool.flash_loan_amount_0 = 0;
+    pool.flash_loan_amount_1 = 0;
+    pool.status = 0;
+    pool.emergency_mode = 0;
+    pool.emergency_exit_after = 0;
+    pool.dynamic_fee_max_rate = pool.fee_rate;
+    pool.dynamic_fee_rate_per_tick = 0;
+    pool.volatility_accumulator = 0;
+    pool.volatility_updated_at = clock.unix_timestamp;
+    pool.version = ACCOUNT_VERSION;
+    pool.lp_fees_tracked = 1;
+    pool.lp_fee_growth_entry_0_x64 = 0;
+    pool.lp_fee_growth_entry_1_x64 = 0;
+    pool.lp_fees_owed_0 = 0;
+    pool.lp_fees_owed_1 = 0;
+    pool.position_fee_growth_entry_0_x64 = 0;
+    pool.position_fee_growth_entry_1_x64 = 0;
+    pool.position_fees_owed_0 = 0;
+    pool.position_fees_owed_1 = 0;
+    pool.limit_order_min_liquidity = DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY;
+    let mut obs = accounts.observation_state.load_init()?;
+    obs.bump = observation_bump;
+    obs.pool = pool_key;
     obs.index = 0;
     obs.cardinality = 1;
     obs.tick_cumulative = 0;
     obs.last_timestamp = clock.unix_timestamp;
+    obs.version = ACCOUNT_VERSION;
+    let mut book = accounts.limit_order_book.load_init()?;
+    book.bump = book_bump;
+    book.pool = pool_key;
+    book.version = ACCOUNT_VERSION;
     msg!("Created pool with sqrt_price: {}, tick: {:?}, tick_spacing: {}", sqrt_price_x64, current_tick, tick_spacing);
-    Ok(())
+    Ok(PoolCreated {
+        pool: pool_key,
+        amm_config: pool.amm_config,
+        token_mint_0: pool.token_mint_0,
+        token_mint_1: pool.token_mint_1,
+        lp_mint: pool.lp_mint,
+        sqrt_price_x64,
+        tick_current: current_tick,
+        tick_spacing,
+        fee_rate: pool.fee_rate,
+    })
 }
@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Transfer};
+use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode, events::{EmergencyModeUpdated, LiquidityChanged}};
+use crate::state::{LimitOrderBook, Pool};
+use orca_math::mul_div;
+use super::liquidity::{accrue_lp_fees, redeem_lp_fees, total_shares, LiquidityResult};
+use super::position::{accrue_fees, accrue_position_fees, settle_position_fees};
+
+/// Takes effect immediately; entering again cancels a scheduled exit.
+pub fn enter_emergency_mode(ctx: Context<PoolAdmin>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.emergency_mode = 1;
+    pool.emergency_exit_after = 0;
+    emit_cpi!(EmergencyModeUpdated { pool: pool_key, emergency_mode: true, emergency_exit_after: 0 });
+    msg!("Pool {} entered emergency mode", pool_key);
+    Ok(())
+}
+
+pub fn schedule_emergency_exit(ctx: Context<PoolAdmin>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.emergency_mode != 0, OrcaErrorCode::NotInEmergencyMode);
+    let now = Clock::get()?.unix_timestamp;
+    pool.emergency_exit_after = now.checked_add(EMERGENCY_EXIT_TIMELOCK).ok_or(OrcaErrorCode::MathOverflow)?;
+    emit_cpi!(EmergencyModeUpdated { pool: pool_key, emergency_mode: true, emergency_exit_after: pool.emergency_exit_after });
+    msg!("Pool {} may leave emergency mode after {}", pool_key, pool.emergency_exit_after);
+    Ok(())
+}
+
+pub fn exit_emergency_mode(ctx: Context<PoolAdmin>) -> Result<()> {
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.emergency_mode != 0, OrcaErrorCode::NotInEmergencyMode);
+    let now = Clock::get()?.unix_timestamp;
+    require!(pool.emergency_exit_after != 0 && now >= pool.emergency_exit_after, OrcaErrorCode::EmergencyExitTimelocked);
+    pool.emergency_mode = 0;
+    pool.emergency_exit_after = 0;
+    emit_cpi!(EmergencyModeUpdated { pool: pool_key, emergency_mode: false, emergency_exit_after: 0 });
+    msg!("Pool {} left emergency mode", pool_key);
+    Ok(())
+}
+
+/// Burns `lp_amount` shares for their pro-rata part of both vaults.
+pub fn emergency_remove_liquidity(ctx: Context<ModifyLiquidity>, lp_amount: u64) -> Result<LiquidityResult> {
+    require!(lp_amount > 0, OrcaErrorCode::InvalidAmount);
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.emergency_mode != 0, OrcaErrorCode::NotInEmergencyMode);
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    require!(lp_amount <= lp_supply, OrcaErrorCode::InsufficientLiquidity);
+    let delta = mul_div(lp_amount as u128, pool.lp_liquidity, total_shares(lp_supply), false).map_err(OrcaErrorCode::from)?;
+    // The vault shares leave out all fees owed to shares and positions; these shares' part is paid on top.
+    accrue_lp_fees(&mut pool)?;
+    accrue_position_fees(&mut pool)?;
+    let (amount_token_0, amount_token_1) = vault_shares(&pool, &*ctx.accounts.limit_order_book.load()?, ctx.accounts.token_vault_0.amount, ctx.accounts.token_vault_1.amount, delta)?;
+    let (fees_0, fees_1) = redeem_lp_fees(&mut pool, lp_amount, lp_supply)?;
+    let amount_token_0 = amount_token_0.checked_add(fees_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    let amount_token_1 = amount_token_1.checked_add(fees_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    let cpi_accounts_burn = Burn { mint: ctx.accounts.lp_mint.to_account_info(), from: ctx.accounts.user_lp_token_account.to_account_info(), authority: ctx.accounts.user.to_account_info() };
+    token::burn(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts_burn), lp_amount)?;
+    pool.liquidity = pool.liquidity.checked_sub(delta).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
+    pool.lp_liquidity = pool.lp_liquidity.checked_sub(delta).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
+    let seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    token::transfer(CpiContext::new_with_signer(cpi_program.clone(), Transfer { from: ctx.accounts.token_vault_0.to_account_info(), to: ctx.accounts.user_token_account_0.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_0)?;
+    token::transfer(CpiContext::new_with_signer(cpi_program, Transfer { from: ctx.accounts.token_vault_1.to_account_info(), to: ctx.accounts.user_token_account_1.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_1)?;
+    emit_cpi!(LiquidityChanged {
+        pool: pool_key,
+        user: ctx.accounts.user.key(),
+        position: None,
+        increase: false,
+        liquidity_delta: delta,
+        amount_token_0,
+        amount_token_1,
+        lp_amount,
+        pool_liquidity: pool.liquidity,
+    });
+    msg!("Emergency removed liquidity: shares={}, token0={}, token1={}", lp_amount, amount_token_0, amount_token_1);
+    Ok(LiquidityResult { liquidity_delta: delta, amount_token_0, amount_token_1, lp_amount })
+}
+
+/// Closes out the whole position for its pro-rata part of both vaults, plus
+/// the fees it has not collected, which the split leaves out.
+pub fn emergency_withdraw_position(ctx: Context<ModifyPositionLiquidity>) -> Result<LiquidityResult> {
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.emergency_mode != 0, OrcaErrorCode::NotInEmergencyMode);
+    let position = &mut ctx.accounts.position;
+    let delta = position.liquidity;
+    require!(delta > 0, OrcaErrorCode::PositionIsEmpty);
+    accrue_lp_fees(&mut pool)?;
+    accrue_position_fees(&mut pool)?;
+    accrue_fees(position, &pool)?;
+    let (amount_token_0, amount_token_1) = vault_shares(&pool, &*ctx.accounts.limit_order_book.load()?, ctx.accounts.token_vault_0.amount, ctx.accounts.token_vault_1.amount, delta)?;
+    let (fees_0, fees_1) = (position.tokens_owed_0, position.tokens_owed_1);
+    settle_position_fees(&mut pool, fees_0, fees_1)?;
+    let amount_token_0 = amount_token_0.checked_add(fees_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    let amount_token_1 = amount_token_1.checked_add(fees_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    position.liquidity = 0;
+    position.tokens_owed_0 = 0;
+    position.tokens_owed_1 = 0;
+    pool.liquidity = pool.liquidity.checked_sub(delta).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
+    let seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    token::transfer(CpiContext::new_with_signer(cpi_program.clone(), Transfer { from: ctx.accounts.token_vault_0.to_account_info(), to: ctx.accounts.user_token_account_0.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_0)?;
+    token::transfer(CpiContext::new_with_signer(cpi_program, Transfer { from: ctx.accounts.token_vault_1.to_account_info(), to: ctx.accounts.user_token_account_1.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_1)?;
+    emit_cpi!(LiquidityChanged {
+        pool: pool_key,
+        user: position.owner,
+        position: Some(position.key()),
+        increase: false,
+        liquidity_delta: delta,
+        amount_token_0,
+        amount_token_1,
+        lp_amount: 0,
+        pool_liquidity: pool.liquidity,
+    });
+    msg!("Emergency withdrew position: token0={}, token1={}", amount_token_0, amount_token_1);
+    Ok(LiquidityResult { liquidity_delta: delta, amount_token_0, amount_token_1, lp_amount: 0 })
+}
+
+/// `liquidity`'s share of each vault net of protocol fees, of the fees owed to
+/// LP shares and positions and of what limit orders hold, rounded down. Only
+/// stored balances and liquidity are used, never a swap's pricing.
+fn vault_shares(pool: &Pool, book: &LimitOrderBook, vault_0: u64, vault_1: u64, liquidity: u128) -> Result<(u64, u64)> {
+    require!(liquidity <= pool.liquidity, OrcaErrorCode::InsufficientLiquidity);
+    let (orders_0, orders_1) = book.reserved_amounts()?;
+    let share = |vault: u64, claims: [u64; 4]| -> Result<u64> {
+        let available = claims.iter().try_fold(vault, |available, claim| available.checked_sub(*claim)).ok_or(OrcaErrorCode::MathUnderflow)?;
+        let amount = mul_div(available as u128, liquidity, pool.liquidity, false).map_err(OrcaErrorCode::from)?;
+        Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+    };
+    Ok((
+        share(vault_0, [pool.protocol_fees_token_0, pool.lp_fees_owed_0, pool.position_fees_owed_0, orders_0])?,
+        share(vault_1, [pool.protocol_fees_token_1, pool.lp_fees_owed_1, pool.position_fees_owed_1, orders_1])?,
+    ))
+}
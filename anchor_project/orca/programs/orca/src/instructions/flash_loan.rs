@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_spl::token::{self, Transfer};
+use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode, events};
+use orca_math::mul_div;
+
+pub fn flash_borrow(
+    ctx: Context<FlashLoan>,
+    amount_token_0: u64,
+    amount_token_1: u64,
+) -> Result<()> {
+    require!(amount_token_0 > 0 || amount_token_1 > 0, OrcaErrorCode::InvalidAmount);
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.flash_loan_locked == 0, OrcaErrorCode::PoolLocked);
+    require!(pool.is_enabled(POOL_STATUS_SWAP_DISABLED), OrcaErrorCode::PoolOperationDisabled);
+    // The fee is spread over in-range liquidity, so there must be some to pay it to.
+    require!(pool.liquidity > 0, OrcaErrorCode::InsufficientLiquidity);
+    let pool_key = ctx.accounts.pool.key();
+    require_repay_follows(&ctx.accounts.instructions, &pool_key)?;
+    pool.flash_loan_locked = 1;
+    pool.flash_loan_amount_0 = amount_token_0;
+    pool.flash_loan_amount_1 = amount_token_1;
+    let seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    if amount_token_0 > 0 {
+        token::transfer(CpiContext::new_with_signer(cpi_program.clone(), Transfer { from: ctx.accounts.token_vault_0.to_account_info(), to: ctx.accounts.user_token_account_0.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_0)?;
+    }
+    if amount_token_1 > 0 {
+        token::transfer(CpiContext::new_with_signer(cpi_program, Transfer { from: ctx.accounts.token_vault_1.to_account_info(), to: ctx.accounts.user_token_account_1.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_1)?;
+    }
+    emit_cpi!(events::FlashBorrow { pool: pool_key, borrower: ctx.accounts.user.key(), amount_token_0, amount_token_1 });
+    msg!("Flash borrowed token0={}, token1={}", amount_token_0, amount_token_1);
+    Ok(())
+}
+
+pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.flash_loan_locked != 0, OrcaErrorCode::NoFlashLoanOutstanding);
+    let (amount_token_0, amount_token_1) = (pool.flash_loan_amount_0, pool.flash_loan_amount_1);
+    let flash_fee_rate = ctx.accounts.amm_config.flash_fee_rate as u128;
+    // Rounded up, so no loan is free.
+    let fee = |amount: u64| -> Result<u64> {
+        let fee = mul_div(amount as u128, flash_fee_rate, FEE_RATE_DENOMINATOR, true).map_err(OrcaErrorCode::from)?;
+        Ok(u64::try_from(fee).map_err(|_| OrcaErrorCode::MathOverflow)?)
+    };
+    let (fee_token_0, fee_token_1) = (fee(amount_token_0)?, fee(amount_token_1)?);
+    let repay_0 = amount_token_0.checked_add(fee_token_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    let repay_1 = amount_token_1.checked_add(fee_token_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    if repay_0 > 0 {
+        token::transfer(CpiContext::new(cpi_program.clone(), Transfer { from: ctx.accounts.user_token_account_0.to_account_info(), to: ctx.accounts.token_vault_0.to_account_info(), authority: ctx.accounts.user.to_account_info() }), repay_0)?;
+    }
+    if repay_1 > 0 {
+        token::transfer(CpiContext::new(cpi_program, Transfer { from: ctx.accounts.user_token_account_1.to_account_info(), to: ctx.accounts.token_vault_1.to_account_info(), authority: ctx.accounts.user.to_account_info() }), repay_1)?;
+    }
+    // Liquidity cannot change while the pool is locked, so this is the liquidity the loan was drawn against.
+    let growth_0 = mul_div(fee_token_0 as u128, Q64, pool.liquidity, false).map_err(OrcaErrorCode::from)?;
+    let growth_1 = mul_div(fee_token_1 as u128, Q64, pool.liquidity, false).map_err(OrcaErrorCode::from)?;
+    pool.fee_growth_global_0_x64 = pool.fee_growth_global_0_x64.checked_add(growth_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    pool.fee_growth_global_1_x64 = pool.fee_growth_global_1_x64.checked_add(growth_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    pool.flash_loan_locked = 0;
+    pool.flash_loan_amount_0 = 0;
+    pool.flash_loan_amount_1 = 0;
+    emit_cpi!(events::FlashLoan {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.user.key(),
+        amount_token_0,
+        amount_token_1,
+        fee_token_0,
+        fee_token_1,
+    });
+    msg!("Flash repaid token0={}, token1={} with fees token0={}, token1={}", amount_token_0, amount_token_1, fee_token_0, fee_token_1);
+    Ok(())
+}
+
+/// A borrow is only let through if a later top-level instruction in the same
+/// transaction repays this pool; should that repay fail, the borrow reverts with it.
+fn require_repay_follows(instructions: &AccountInfo, pool: &Pubkey) -> Result<()> {
+    let current = load_current_index_checked(instructions)? as usize;
+    let mut index = current + 1;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        let is_repay = instruction.program_id == crate::ID
+            && instruction.data.starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+            && instruction.accounts.first().is_some_and(|meta| meta.pubkey == *pool);
+        if is_repay {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(OrcaErrorCode::FlashLoanNotRepaid)
+}
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
 use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode};
+use super::admin::config_updated;
 
 pub fn handler(
     ctx: Context<InitializeAmm>,
+    index: u16,
     fee_rate: u16,
     protocol_fee_rate: u16,
 ) -> Result<()> {
@@ -16,6 +18,12 @@ pub fn handler(
     amm_config.protocol_fee_destination = ctx.accounts.protocol_fee_destination.key();
     amm_config.create_pool_enabled = true;
     amm_config.swap_enabled = true;
-    msg!("Initialized AMM with fee rate: {} bps, protocol fee rate: {} bps", fee_rate, protocol_fee_rate);
+    amm_config.flash_fee_rate = fee_rate;
+    amm_config.version = ACCOUNT_VERSION;
+    amm_config.index = index;
+    amm_config.permissioned_pool_creation = false;
+    amm_config.token_badges_required = false;
+    emit_cpi!(config_updated(amm_config));
+    msg!("Initialized AMM config {} with fee rate: {} bps, protocol fee rate: {} bps", index, fee_rate, protocol_fee_rate);
     Ok(())
 }
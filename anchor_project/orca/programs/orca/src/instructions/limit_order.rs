@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Transfer};
+use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode, events::{LimitOrderClosed, LimitOrderPlaced}};
+use crate::state::LimitOrderRange;
+use orca_math::{liquidity_from_amount_0, liquidity_from_amount_1, mul_div};
+use super::liquidity::LiquidityResult;
+
+/// Places `amount` of one token as liquidity on `[tick_lower, tick_lower +
+/// tick_spacing]`. Above the price the order sells token 0, below it token 1;
+/// a range the price is inside cannot take new orders, nor the book one below
+/// the pool's minimum liquidity.
+pub fn place_limit_order(ctx: Context<PlaceLimitOrder>, tick_lower: i32, amount: u64) -> Result<LiquidityResult> {
+    require!(amount > 0, OrcaErrorCode::InvalidAmount);
+    let pool = ctx.accounts.pool.load()?;
+    require!(tick_lower % pool.tick_spacing as i32 == 0, OrcaErrorCode::TickNotAligned);
+    let mut book = ctx.accounts.limit_order_book.load_mut()?;
+    let index = match book.find(tick_lower) {
+        Some(index) => index,
+        None => {
+            let index = book.ranges.iter().position(|range| range.open_orders == 0).ok_or(OrcaErrorCode::LimitOrderBookFull)?;
+            book.ranges[index] = LimitOrderRange { tick_lower, ..Default::default() };
+            index
+        }
+    };
+    let range = &mut book.ranges[index];
+    require!(range.filled == 0, OrcaErrorCode::LimitOrderFilled);
+    let (lower, upper) = range.sqrt_prices(pool.tick_spacing)?;
+    let sells_token_0 = if pool.sqrt_price_x64 <= lower {
+        true
+    } else if pool.sqrt_price_x64 >= upper {
+        false
+    } else {
+        return err!(OrcaErrorCode::InvalidPositionRange);
+    };
+    if range.open_orders == 0 {
+        range.sells_token_0 = sells_token_0 as u8;
+    }
+    require!(range.sells_token_0 == sells_token_0 as u8, OrcaErrorCode::InvalidPositionRange);
+    let liquidity = if sells_token_0 { liquidity_from_amount_0(lower, upper, amount) } else { liquidity_from_amount_1(lower, upper, amount) }.map_err(OrcaErrorCode::from)?;
+    require!(liquidity > 0, OrcaErrorCode::InvalidAmount);
+    require!(liquidity >= pool.limit_order_min_liquidity, OrcaErrorCode::LimitOrderTooSmall);
+    let (amount_token_0, amount_token_1) = range.amounts(pool.sqrt_price_x64, pool.tick_spacing, liquidity, true)?;
+    range.liquidity = range.liquidity.checked_add(liquidity).ok_or(OrcaErrorCode::LiquidityOverflow)?;
+    range.amount_0 = range.amount_0.checked_add(amount_token_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    range.amount_1 = range.amount_1.checked_add(amount_token_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    range.open_orders = range.open_orders.checked_add(1).ok_or(OrcaErrorCode::MathOverflow)?;
+    let order = &mut ctx.accounts.limit_order;
+    order.bump = ctx.bumps.limit_order;
+    order.pool = ctx.accounts.pool.key();
+    order.owner = ctx.accounts.owner.key();
+    order.tick_lower = tick_lower;
+    order.liquidity = liquidity;
+    order.fee_growth_entry_0_x64 = range.fee_growth_0_x64;
+    order.fee_growth_entry_1_x64 = range.fee_growth_1_x64;
+    order.version = ACCOUNT_VERSION;
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    if amount_token_0 > 0 {
+        token::transfer(CpiContext::new(cpi_program.clone(), Transfer { from: ctx.accounts.user_token_account_0.to_account_info(), to: ctx.accounts.token_vault_0.to_account_info(), authority: ctx.accounts.owner.to_account_info() }), amount_token_0)?;
+    }
+    if amount_token_1 > 0 {
+        token::transfer(CpiContext::new(cpi_program, Transfer { from: ctx.accounts.user_token_account_1.to_account_info(), to: ctx.accounts.token_vault_1.to_account_info(), authority: ctx.accounts.owner.to_account_info() }), amount_token_1)?;
+    }
+    emit_cpi!(LimitOrderPlaced {
+        pool: order.pool,
+        limit_order: order.key(),
+        owner: order.owner,
+        tick_lower,
+        sells_token_0,
+        liquidity,
+        amount: amount_token_0.max(amount_token_1),
+    });
+    msg!("Placed limit order at tick {} for token0={}, token1={}", tick_lower, amount_token_0, amount_token_1);
+    Ok(LiquidityResult { liquidity_delta: liquidity, amount_token_0, amount_token_1, lp_amount: 0 })
+}
+
+/// Withdraws an unfilled order's part of what its range holds, fees included. Owner only.
+/// Emergency mode freezes the price and holds orders out of the vault split, so
+/// cancelling stays open there whatever the status bits.
+pub fn cancel_limit_order(ctx: Context<CloseLimitOrder>) -> Result<LiquidityResult> {
+    require_keys_eq!(ctx.accounts.authority.key(), ctx.accounts.owner.key(), OrcaErrorCode::Unauthorized);
+    let allowed = {
+        let pool = ctx.accounts.pool.load()?;
+        pool.emergency_mode != 0 || pool.is_enabled(POOL_STATUS_WITHDRAW_DISABLED)
+    };
+    require!(allowed, OrcaErrorCode::PoolOperationDisabled);
+    close_limit_order(ctx, false)
+}
+
+/// Pays out a filled order's proceeds and fees to its owner. Anyone may sign,
+/// and since the payout was fixed at the fill it is allowed in emergency mode.
+pub fn claim_limit_order(ctx: Context<CloseLimitOrder>) -> Result<LiquidityResult> {
+    require!(ctx.accounts.pool.load()?.status & POOL_STATUS_WITHDRAW_DISABLED == 0, OrcaErrorCode::PoolOperationDisabled);
+    close_limit_order(ctx, true)
+}
+
+fn close_limit_order(ctx: Context<CloseLimitOrder>, filled: bool) -> Result<LiquidityResult> {
+    let pool = ctx.accounts.pool.load()?;
+    let order = &ctx.accounts.limit_order;
+    let mut book = ctx.accounts.limit_order_book.load_mut()?;
+    let index = book.find(order.tick_lower).ok_or(OrcaErrorCode::PositionNotFound)?;
+    let range = &mut book.ranges[index];
+    require!(range.filled == filled as u8, if filled { OrcaErrorCode::LimitOrderNotFilled } else { OrcaErrorCode::LimitOrderFilled });
+    // The order's part of the range's balances, whatever swaps have converted of them.
+    let held = |amount: u64| -> Result<u64> {
+        let amount = mul_div(amount as u128, order.liquidity, range.liquidity, false).map_err(OrcaErrorCode::from)?;
+        Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+    };
+    let (amount_token_0, amount_token_1) = (held(range.amount_0)?, held(range.amount_1)?);
+    let fee = |growth: u128, entry: u128| -> Result<u64> {
+        let growth_delta = growth.checked_sub(entry).ok_or(OrcaErrorCode::MathUnderflow)?;
+        let amount = mul_div(order.liquidity, growth_delta, Q64, false).map_err(OrcaErrorCode::from)?;
+        Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+    };
+    let fee_token_0 = fee(range.fee_growth_0_x64, order.fee_growth_entry_0_x64)?;
+    let fee_token_1 = fee(range.fee_growth_1_x64, order.fee_growth_entry_1_x64)?;
+    range.fees_owed_0 = range.fees_owed_0.checked_sub(fee_token_0).ok_or(OrcaErrorCode::MathUnderflow)?;
+    range.fees_owed_1 = range.fees_owed_1.checked_sub(fee_token_1).ok_or(OrcaErrorCode::MathUnderflow)?;
+    range.amount_0 = range.amount_0.checked_sub(amount_token_0).ok_or(OrcaErrorCode::MathUnderflow)?;
+    range.amount_1 = range.amount_1.checked_sub(amount_token_1).ok_or(OrcaErrorCode::MathUnderflow)?;
+    range.liquidity = range.liquidity.checked_sub(order.liquidity).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
+    range.open_orders = range.open_orders.checked_sub(1).ok_or(OrcaErrorCode::MathUnderflow)?;
+    // The last order out frees the slot; rounding dust left in `fees_owed_*` and `amount_*` stays with the pool.
+    if range.open_orders == 0 {
+        *range = LimitOrderRange::default();
+    }
+    let payout_0 = amount_token_0.checked_add(fee_token_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    let payout_1 = amount_token_1.checked_add(fee_token_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    let pool_key = ctx.accounts.pool.key();
+    let seeds = [POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    if payout_0 > 0 {
+        token::transfer(CpiContext::new_with_signer(cpi_program.clone(), Transfer { from: ctx.accounts.token_vault_0.to_account_info(), to: ctx.accounts.owner_token_account_0.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), payout_0)?;
+    }
+    if payout_1 > 0 {
+        token::transfer(CpiContext::new_with_signer(cpi_program, Transfer { from: ctx.accounts.token_vault_1.to_account_info(), to: ctx.accounts.owner_token_account_1.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), payout_1)?;
+    }
+    emit_cpi!(LimitOrderClosed {
+        pool: pool_key,
+        limit_order: order.key(),
+        owner: order.owner,
+        filled,
+        amount_token_0: payout_0,
+        amount_token_1: payout_1,
+        fee_token_0,
+        fee_token_1,
+    });
+    msg!("Closed limit order at tick {}: token0={}, token1={}", order.tick_lower, payout_0, payout_1);
+    Ok(LiquidityResult { liquidity_delta: order.liquidity, amount_token_0: payout_0, amount_token_1: payout_1, lp_amount: 0 })
+}
@@ -1,42 +1,157 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer};
-use crate::{contexts::*, errors::ErrorCode as OrcaErrorCode};
+use anchor_spl::token::{self, Burn, MintTo, Transfer};
+use crate::{contexts::*, constants::{MINIMUM_LIQUIDITY, POOL_STATUS_DEPOSIT_DISABLED, POOL_STATUS_WITHDRAW_DISABLED}, errors::ErrorCode as OrcaErrorCode, events::LiquidityChanged};
+use crate::state::Pool;
+use orca_math::{amounts_for_liquidity, liquidity_from_amounts, mul_div, Q64};
+
+/// What a liquidity or position instruction actually moved; `lp_amount` is the
+/// shares minted or burned and always zero for positions.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidityResult {
+    pub liquidity_delta: u128,
+    pub amount_token_0: u64,
+    pub amount_token_1: u64,
+    pub lp_amount: u64,
+}
 
 pub fn add_liquidity(
     ctx: Context<ModifyLiquidity>,
     amount_token_0: u64,
     amount_token_1: u64,
-) -> Result<()> {
+) -> Result<LiquidityResult> {
     require!(amount_token_0 > 0 && amount_token_1 > 0, OrcaErrorCode::InvalidAmount);
-    let pool = &mut ctx.accounts.pool;
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.is_enabled(POOL_STATUS_DEPOSIT_DISABLED), OrcaErrorCode::PoolOperationDisabled);
+    // The amounts are maximums; only the pair matching the current price is taken.
+    let liquidity_delta = liquidity_from_amounts(pool.sqrt_price_x64, amount_token_0, amount_token_1).map_err(OrcaErrorCode::from)?;
+    require!(liquidity_delta > 0, OrcaErrorCode::InvalidAmount);
+    let (deposit_0, deposit_1) = amounts_for_liquidity(pool.sqrt_price_x64, liquidity_delta, true).map_err(OrcaErrorCode::from)?;
+    accrue_lp_fees(&mut pool)?;
+    // Shares are priced off tracked liquidity rather than vault balances, so tokens donated to a
+    // vault never move the share price; rounding down keeps any remainder with existing holders.
+    let shares = if pool.lp_liquidity == 0 {
+        require!(liquidity_delta > MINIMUM_LIQUIDITY, OrcaErrorCode::InsufficientLiquidity);
+        liquidity_delta - MINIMUM_LIQUIDITY
+    } else {
+        mul_div(liquidity_delta, total_shares(ctx.accounts.lp_mint.supply), pool.lp_liquidity, false).map_err(OrcaErrorCode::from)?
+    };
+    let shares = u64::try_from(shares).map_err(|_| OrcaErrorCode::MathOverflow)?;
+    require!(shares > 0, OrcaErrorCode::InvalidAmount);
+    // New shares also buy into the fees owed to existing ones, rounding up, so a deposit never dilutes them.
+    let buy_in = |fees_owed: u64| fee_share(fees_owed, shares, ctx.accounts.lp_mint.supply, true);
+    let (fees_in_0, fees_in_1) = (buy_in(pool.lp_fees_owed_0)?, buy_in(pool.lp_fees_owed_1)?);
+    pool.lp_fees_owed_0 = pool.lp_fees_owed_0.checked_add(fees_in_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    pool.lp_fees_owed_1 = pool.lp_fees_owed_1.checked_add(fees_in_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    let deposit_0 = deposit_0.checked_add(fees_in_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    let deposit_1 = deposit_1.checked_add(fees_in_1).ok_or(OrcaErrorCode::MathOverflow)?;
     let cpi_accounts_0 = Transfer { from: ctx.accounts.user_token_account_0.to_account_info(), to: ctx.accounts.token_vault_0.to_account_info(), authority: ctx.accounts.user.to_account_info() };
     let cpi_program = ctx.accounts.token_program.to_account_info();
-    token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts_0), amount_token_0)?;
+    token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts_0), deposit_0)?;
     let cpi_accounts_1 = Transfer { from: ctx.accounts.user_token_account_1.to_account_info(), to: ctx.accounts.token_vault_1.to_account_info(), authority: ctx.accounts.user.to_account_info() };
-    token::transfer(CpiContext::new(cpi_program, cpi_accounts_1), amount_token_1)?;
-    pool.liquidity = pool.liquidity.saturating_add((amount_token_0 as u128) + (amount_token_1 as u128));
-    msg!("Added liquidity: token0={}, token1={}, new_liquidity={}", amount_token_0, amount_token_1, pool.liquidity);
-    Ok(())
+    token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts_1), deposit_1)?;
+    let seeds = [crate::constants::POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts_mint = MintTo { mint: ctx.accounts.lp_mint.to_account_info(), to: ctx.accounts.user_lp_token_account.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() };
+    token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts_mint, signer), shares)?;
+    pool.liquidity = pool.liquidity.checked_add(liquidity_delta).ok_or(OrcaErrorCode::LiquidityOverflow)?;
+    pool.lp_liquidity = pool.lp_liquidity.checked_add(liquidity_delta).ok_or(OrcaErrorCode::LiquidityOverflow)?;
+    emit_cpi!(LiquidityChanged {
+        pool: pool_key,
+        user: ctx.accounts.user.key(),
+        position: None,
+        increase: true,
+        liquidity_delta,
+        amount_token_0: deposit_0,
+        amount_token_1: deposit_1,
+        lp_amount: shares,
+        pool_liquidity: pool.liquidity,
+    });
+    msg!("Added liquidity: token0={}, token1={}, shares={}, new_liquidity={}", deposit_0, deposit_1, shares, pool.liquidity);
+    Ok(LiquidityResult { liquidity_delta, amount_token_0: deposit_0, amount_token_1: deposit_1, lp_amount: shares })
 }
 
 pub fn remove_liquidity(
     ctx: Context<ModifyLiquidity>,
-    amount_token_0: u64,
-    amount_token_1: u64,
-) -> Result<()> {
-    require!(amount_token_0 > 0 && amount_token_1 > 0, OrcaErrorCode::InvalidAmount);
-    let pool = &mut ctx.accounts.pool;
-    require!(pool.liquidity > 0, OrcaErrorCode::InsufficientLiquidity);
-    let delta = (amount_token_0 as u128) + (amount_token_1 as u128);
-    require!(delta <= pool.liquidity, OrcaErrorCode::InsufficientLiquidity);
-    pool.liquidity -= delta;
-    let pool_key = pool.key();
+    lp_amount: u64,
+) -> Result<LiquidityResult> {
+    require!(lp_amount > 0, OrcaErrorCode::InvalidAmount);
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.is_enabled(POOL_STATUS_WITHDRAW_DISABLED), OrcaErrorCode::PoolOperationDisabled);
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    require!(lp_amount <= lp_supply, OrcaErrorCode::InsufficientLiquidity);
+    let delta = mul_div(lp_amount as u128, pool.lp_liquidity, total_shares(lp_supply), false).map_err(OrcaErrorCode::from)?;
+    require!(delta <= pool.lp_liquidity, OrcaErrorCode::InsufficientLiquidity);
+    let (amount_token_0, amount_token_1) = amounts_for_liquidity(pool.sqrt_price_x64, delta, false).map_err(OrcaErrorCode::from)?;
+    let (fees_0, fees_1) = redeem_lp_fees(&mut pool, lp_amount, lp_supply)?;
+    let amount_token_0 = amount_token_0.checked_add(fees_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    let amount_token_1 = amount_token_1.checked_add(fees_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    // Burning with the caller as authority is what ties a withdrawal to shares the caller holds.
+    let cpi_accounts_burn = Burn { mint: ctx.accounts.lp_mint.to_account_info(), from: ctx.accounts.user_lp_token_account.to_account_info(), authority: ctx.accounts.user.to_account_info() };
+    token::burn(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts_burn), lp_amount)?;
+    pool.liquidity = pool.liquidity.checked_sub(delta).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
+    pool.lp_liquidity = pool.lp_liquidity.checked_sub(delta).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
     let seeds = [crate::constants::POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
     let signer = &[&seeds[..]];
     let cpi_accounts_0 = Transfer { from: ctx.accounts.token_vault_0.to_account_info(), to: ctx.accounts.user_token_account_0.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() };
     token::transfer(CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts_0, signer), amount_token_0)?;
     let cpi_accounts_1 = Transfer { from: ctx.accounts.token_vault_1.to_account_info(), to: ctx.accounts.user_token_account_1.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() };
     token::transfer(CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts_1, signer), amount_token_1)?;
-    msg!("Removed liquidity: token0={}, token1={}, remaining_liquidity={}", amount_token_0, amount_token_1, pool.liquidity);
+    emit_cpi!(LiquidityChanged {
+        pool: pool_key,
+        user: ctx.accounts.user.key(),
+        position: None,
+        increase: false,
+        liquidity_delta: delta,
+        amount_token_0,
+        amount_token_1,
+        lp_amount,
+        pool_liquidity: pool.liquidity,
+    });
+    msg!("Removed liquidity: shares={}, token0={}, token1={}, remaining_liquidity={}", lp_amount, amount_token_0, amount_token_1, pool.liquidity);
+    Ok(LiquidityResult { liquidity_delta: delta, amount_token_0, amount_token_1, lp_amount })
+}
+
+/// Outstanding shares including the locked ones, which are never minted.
+pub(super) fn total_shares(lp_supply: u64) -> u128 {
+    lp_supply as u128 + MINIMUM_LIQUIDITY
+}
+
+/// Credits the share pool with the fees its liquidity earned since `lp_liquidity`
+/// last changed, as `accrue_fees` does for a position, rounding down. Must run
+/// before `lp_liquidity` changes.
+pub(super) fn accrue_lp_fees(pool: &mut Pool) -> Result<()> {
+    if pool.lp_fees_tracked != 0 {
+        let earned = |growth: u128, entry: u128| -> Result<u64> {
+            // Fee growth only ever increases, so an entry above it is corrupt state rather than a wrap.
+            let growth_delta = growth.checked_sub(entry).ok_or(OrcaErrorCode::MathUnderflow)?;
+            let amount = mul_div(pool.lp_liquidity, growth_delta, Q64, false).map_err(OrcaErrorCode::from)?;
+            Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+        };
+        let earned_0 = earned(pool.fee_growth_global_0_x64, pool.lp_fee_growth_entry_0_x64)?;
+        let earned_1 = earned(pool.fee_growth_global_1_x64, pool.lp_fee_growth_entry_1_x64)?;
+        pool.lp_fees_owed_0 = pool.lp_fees_owed_0.checked_add(earned_0).ok_or(OrcaErrorCode::MathOverflow)?;
+        pool.lp_fees_owed_1 = pool.lp_fees_owed_1.checked_add(earned_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    }
+    pool.lp_fees_tracked = 1;
+    pool.lp_fee_growth_entry_0_x64 = pool.fee_growth_global_0_x64;
+    pool.lp_fee_growth_entry_1_x64 = pool.fee_growth_global_1_x64;
     Ok(())
 }
+
+/// Accrues the share pool's fees and takes `lp_amount` shares' part of them, rounding down.
+pub(super) fn redeem_lp_fees(pool: &mut Pool, lp_amount: u64, lp_supply: u64) -> Result<(u64, u64)> {
+    accrue_lp_fees(pool)?;
+    let fees_0 = fee_share(pool.lp_fees_owed_0, lp_amount, lp_supply, false)?;
+    let fees_1 = fee_share(pool.lp_fees_owed_1, lp_amount, lp_supply, false)?;
+    pool.lp_fees_owed_0 = pool.lp_fees_owed_0.checked_sub(fees_0).ok_or(OrcaErrorCode::MathUnderflow)?;
+    pool.lp_fees_owed_1 = pool.lp_fees_owed_1.checked_sub(fees_1).ok_or(OrcaErrorCode::MathUnderflow)?;
+    Ok((fees_0, fees_1))
+}
+
+/// `shares`' part of `fees_owed` to all outstanding shares.
+fn fee_share(fees_owed: u64, shares: u64, lp_supply: u64, round_up: bool) -> Result<u64> {
+    let amount = mul_div(fees_owed as u128, shares as u128, total_shares(lp_supply), round_up).map_err(OrcaErrorCode::from)?;
+    Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+}
@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::{AccountSerialize, Discriminator};
+use anchor_spl::token::TokenAccount;
+use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode, events::AccountMigrated};
+use crate::state::{legacy, AmmConfig, LimitOrderBook, ObservationState, Pool, Position};
+use orca_math::amounts_for_liquidity;
+use super::liquidity::accrue_lp_fees;
+
+/// Reads `account` at `L`, the legacy layout of `T`, which it must still have.
+fn read_legacy<T: Discriminator, L: AnchorDeserialize>(account: &AccountInfo, legacy_size: usize) -> Result<L> {
+    let data = account.try_borrow_data()?;
+    require!(data.starts_with(T::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+    require!(data.len() == legacy_size, OrcaErrorCode::AccountAlreadyMigrated);
+    legacy::read::<T, L>(&data)
+}
+
+/// Replaces a legacy account's data with `data`, the same state at the current
+/// layout, growing the account and topping its rent up from `payer`.
+fn write_migrated<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    data: &[u8],
+) -> Result<AccountMigrated> {
+    let shortfall = Rent::get()?.minimum_balance(data.len()).saturating_sub(account.lamports());
+    if shortfall > 0 {
+        let cpi_accounts = Transfer { from: payer.to_account_info(), to: account.clone() };
+        system_program::transfer(CpiContext::new(system_program.to_account_info(), cpi_accounts), shortfall)?;
+    }
+    account.resize(data.len())?;
+    account.try_borrow_mut_data()?.copy_from_slice(data);
+    msg!("Migrated {} to version {} ({} bytes)", account.key(), ACCOUNT_VERSION, data.len());
+    Ok(AccountMigrated { account: account.key(), version: ACCOUNT_VERSION, size: data.len() as u32 })
+}
+
+fn serialized<T: AccountSerialize>(state: &T) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    state.try_serialize(&mut data)?;
+    Ok(data)
+}
+
+fn zero_copy_bytes<T: Discriminator + bytemuck::Pod>(state: &T) -> Vec<u8> {
+    [T::DISCRIMINATOR, bytemuck::bytes_of(state)].concat()
+}
+
+pub fn migrate_amm_config(ctx: Context<MigrateAmmConfig>) -> Result<()> {
+    let config = ctx.accounts.amm_config.to_account_info();
+    let legacy = read_legacy::<AmmConfig, legacy::AmmConfig>(&config, legacy::AmmConfig::SIZE)?;
+    require_keys_eq!(legacy.authority, ctx.accounts.authority.key(), OrcaErrorCode::Unauthorized);
+    let mut amm_config = legacy.upgrade();
+    amm_config.version = ACCOUNT_VERSION;
+    let migrated = write_migrated(&config, &ctx.accounts.authority, &ctx.accounts.system_program, &serialized(&amm_config)?)?;
+    emit_cpi!(migrated);
+    Ok(())
+}
+
+/// Starts tracking the fees owed to positions, which no account recorded
+/// before version 2, at what the vaults hold beyond everything else they back:
+/// protocol fees, share fees, limit orders and the curve's liquidity at its
+/// price. That rounds down, so the estimate covers every position's claim.
+fn track_position_fees(pool: &mut Pool, orders: (u64, u64), vault_0: &Account<TokenAccount>, vault_1: &Account<TokenAccount>) -> Result<()> {
+    require_keys_eq!(vault_0.key(), pool.token_vault_0, OrcaErrorCode::InvalidTokenAccount);
+    require_keys_eq!(vault_1.key(), pool.token_vault_1, OrcaErrorCode::InvalidTokenAccount);
+    let (principal_0, principal_1) = amounts_for_liquidity(pool.sqrt_price_x64, pool.liquidity, false).map_err(OrcaErrorCode::from)?;
+    let surplus = |vault: u64, claims: [u64; 4]| claims.iter().fold(vault, |left, claim| left.saturating_sub(*claim));
+    pool.position_fees_owed_0 = surplus(vault_0.amount, [pool.protocol_fees_token_0, pool.lp_fees_owed_0, orders.0, principal_0]);
+    pool.position_fees_owed_1 = surplus(vault_1.amount, [pool.protocol_fees_token_1, pool.lp_fees_owed_1, orders.1, principal_1]);
+    pool.position_fee_growth_entry_0_x64 = pool.fee_growth_global_0_x64;
+    pool.position_fee_growth_entry_1_x64 = pool.fee_growth_global_1_x64;
+    Ok(())
+}
+
+/// Pools had no LP mint or limit order book then; both are created here.
+/// Share and position fees are tracked from here on.
+pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+    let account = ctx.accounts.pool.to_account_info();
+    let legacy = read_legacy::<Pool, legacy::Pool>(&account, legacy::Pool::SIZE)?;
+    require_keys_eq!(legacy.amm_config, ctx.accounts.amm_config.key(), OrcaErrorCode::Unauthorized);
+    let mut pool = legacy.upgrade();
+    pool.lp_mint = ctx.accounts.lp_mint.key();
+    pool.volatility_updated_at = Clock::get()?.unix_timestamp;
+    pool.version = ACCOUNT_VERSION;
+    pool.limit_order_min_liquidity = DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY;
+    accrue_lp_fees(&mut pool)?;
+    track_position_fees(&mut pool, (0, 0), &ctx.accounts.token_vault_0, &ctx.accounts.token_vault_1)?;
+    let migrated = write_migrated(&account, &ctx.accounts.authority, &ctx.accounts.system_program, &zero_copy_bytes(&pool))?;
+    let mut book = ctx.accounts.limit_order_book.load_init()?;
+    book.bump = ctx.bumps.limit_order_book;
+    book.pool = account.key();
+    book.version = ACCOUNT_VERSION;
+    emit_cpi!(migrated);
+    Ok(())
+}
+
+/// Version 1 pools already have their LP mint and book; they regain a
+/// `reserved` tail and start tracking the fees owed to positions, and their
+/// book's ranges store the tokens they hold. Pools from before share fees were
+/// credited start tracking them.
+pub fn migrate_pool_v1(ctx: Context<MigratePoolV1>) -> Result<()> {
+    let account = ctx.accounts.pool.to_account_info();
+    let book_account = ctx.accounts.limit_order_book.to_account_info();
+    let mut pool: Pool = bytemuck::Zeroable::zeroed();
+    {
+        let data = account.try_borrow_data()?;
+        require!(data.starts_with(Pool::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+        require!(data.len() == legacy::POOL_V1_SIZE, OrcaErrorCode::AccountAlreadyMigrated);
+        bytemuck::bytes_of_mut(&mut pool)[..data.len() - Pool::DISCRIMINATOR.len()].copy_from_slice(&data[Pool::DISCRIMINATOR.len()..]);
+    }
+    require_keys_eq!(pool.amm_config, ctx.accounts.amm_config.key(), OrcaErrorCode::Unauthorized);
+    let mut book = {
+        let data = book_account.try_borrow_data()?;
+        require!(data.starts_with(LimitOrderBook::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+        require!(data.len() == legacy::LimitOrderBookV1::SIZE, OrcaErrorCode::AccountAlreadyMigrated);
+        let legacy: legacy::LimitOrderBookV1 = bytemuck::pod_read_unaligned(&data[LimitOrderBook::DISCRIMINATOR.len()..]);
+        legacy.upgrade(pool.sqrt_price_x64, pool.tick_spacing)?
+    };
+    book.version = ACCOUNT_VERSION;
+    pool.version = ACCOUNT_VERSION;
+    pool.limit_order_min_liquidity = DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY;
+    accrue_lp_fees(&mut pool)?;
+    track_position_fees(&mut pool, book.reserved_amounts()?, &ctx.accounts.token_vault_0, &ctx.accounts.token_vault_1)?;
+    let migrated = write_migrated(&account, &ctx.accounts.authority, &ctx.accounts.system_program, &zero_copy_bytes(&pool))?;
+    emit_cpi!(migrated);
+    let migrated = write_migrated(&book_account, &ctx.accounts.authority, &ctx.accounts.system_program, &zero_copy_bytes(&book))?;
+    emit_cpi!(migrated);
+    Ok(())
+}
+
+/// The config `pool` is bound to, at any of its layouts.
+fn pool_config(pool: &AccountInfo) -> Result<Pubkey> {
+    let data = pool.try_borrow_data()?;
+    if data.len() == legacy::Pool::SIZE {
+        return Ok(legacy::read::<Pool, legacy::Pool>(&data)?.amm_config);
+    }
+    require!(data.starts_with(Pool::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+    let offset = Pool::DISCRIMINATOR.len() + std::mem::offset_of!(Pool, amm_config);
+    let key = data.get(offset..offset + 32).ok_or(ErrorCode::AccountDidNotDeserialize)?;
+    Ok(Pubkey::try_from(key).unwrap())
+}
+
+/// Requires `owner`, the pool a legacy account names, to be the `pool` passed
+/// in and to be bound to the signer's config.
+fn require_under_config(ctx: &Context<MigrateAccount>, owner: Pubkey) -> Result<()> {
+    require_keys_eq!(owner, ctx.accounts.pool.key(), OrcaErrorCode::InvalidPoolConfig);
+    require_keys_eq!(pool_config(&ctx.accounts.pool)?, ctx.accounts.amm_config.key(), OrcaErrorCode::Unauthorized);
+    Ok(())
+}
+
+pub fn migrate_position(ctx: Context<MigrateAccount>) -> Result<()> {
+    let account = ctx.accounts.account.to_account_info();
+    let legacy = read_legacy::<Position, legacy::Position>(&account, legacy::Position::SIZE)?;
+    require_under_config(&ctx, legacy.pool)?;
+    let mut position = legacy.upgrade();
+    position.version = ACCOUNT_VERSION;
+    let migrated = write_migrated(&account, &ctx.accounts.authority, &ctx.accounts.system_program, &serialized(&position)?)?;
+    emit_cpi!(migrated);
+    Ok(())
+}
+
+pub fn migrate_observation_state(ctx: Context<MigrateAccount>) -> Result<()> {
+    let account = ctx.accounts.account.to_account_info();
+    let legacy = read_legacy::<ObservationState, legacy::ObservationState>(&account, legacy::ObservationState::SIZE)?;
+    require_under_config(&ctx, legacy.pool)?;
+    let mut observation = legacy.upgrade();
+    observation.version = ACCOUNT_VERSION;
+    let migrated = write_migrated(&account, &ctx.accounts.authority, &ctx.accounts.system_program, &zero_copy_bytes(&observation))?;
+    emit_cpi!(migrated);
+    Ok(())
+}
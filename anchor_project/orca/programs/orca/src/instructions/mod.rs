@@ -3,3 +3,10 @@ pub mod create_pool;
 pub mod liquidity;
 pub mod swap;
 pub mod position;
+pub mod quote;
+pub mod flash_loan;
+pub mod admin;
+pub mod emergency;
+pub mod limit_order;
+pub mod migrate;
+pub mod allowlist;
@@ -1,18 +1,23 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Transfer};
-use crate::{contexts::*, errors::ErrorCode as OrcaErrorCode};
+use crate::{contexts::*, constants::*, errors::ErrorCode as OrcaErrorCode, events::{FeesCollected, LiquidityChanged, PositionOpened}};
+use crate::state::{Pool, Position};
+use orca_math::{amounts_for_liquidity, liquidity_from_amounts, mul_div};
+use super::liquidity::LiquidityResult;
 
 pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
-    let pool = &ctx.accounts.pool;
+    let pool = ctx.accounts.pool.load()?;
     let position = &mut ctx.accounts.position;
     position.bump = ctx.bumps.position;
-    position.pool = pool.key();
+    position.pool = ctx.accounts.pool.key();
     position.owner = ctx.accounts.owner.key();
     position.liquidity = 0;
     position.fee_growth_entry_0_x64 = pool.fee_growth_global_0_x64;
     position.fee_growth_entry_1_x64 = pool.fee_growth_global_1_x64;
     position.tokens_owed_0 = 0;
     position.tokens_owed_1 = 0;
+    position.version = ACCOUNT_VERSION;
+    emit_cpi!(PositionOpened { pool: position.pool, position: position.key(), owner: position.owner });
     msg!("Position opened for owner {} on pool {}", position.owner, position.pool);
     Ok(())
 }
@@ -21,41 +26,143 @@ pub fn increase_position_liquidity(
     ctx: Context<ModifyPositionLiquidity>,
     amount_token_0: u64,
     amount_token_1: u64,
-) -> Result<()> {
+) -> Result<LiquidityResult> {
     require!(amount_token_0 > 0 && amount_token_1 > 0, OrcaErrorCode::InvalidAmount);
-    let pool = &mut ctx.accounts.pool;
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.is_enabled(POOL_STATUS_DEPOSIT_DISABLED), OrcaErrorCode::PoolOperationDisabled);
     let position = &mut ctx.accounts.position;
+    // The amounts are maximums; only the pair matching the current price is taken.
+    let delta_liquidity = liquidity_from_amounts(pool.sqrt_price_x64, amount_token_0, amount_token_1).map_err(OrcaErrorCode::from)?;
+    require!(delta_liquidity > 0, OrcaErrorCode::InvalidAmount);
+    let (amount_token_0, amount_token_1) = amounts_for_liquidity(pool.sqrt_price_x64, delta_liquidity, true).map_err(OrcaErrorCode::from)?;
     let cpi_program = ctx.accounts.token_program.to_account_info();
     token::transfer(CpiContext::new(cpi_program.clone(), Transfer { from: ctx.accounts.user_token_account_0.to_account_info(), to: ctx.accounts.token_vault_0.to_account_info(), authority: ctx.accounts.owner.to_account_info() }), amount_token_0)?;
     token::transfer(CpiContext::new(cpi_program, Transfer { from: ctx.accounts.user_token_account_1.to_account_info(), to: ctx.accounts.token_vault_1.to_account_info(), authority: ctx.accounts.owner.to_account_info() }), amount_token_1)?;
-    let delta_liquidity = (amount_token_0 as u128) + (amount_token_1 as u128);
-    position.liquidity = position.liquidity.saturating_add(delta_liquidity);
-    pool.liquidity = pool.liquidity.saturating_add(delta_liquidity);
-    position.fee_growth_entry_0_x64 = pool.fee_growth_global_0_x64;
-    position.fee_growth_entry_1_x64 = pool.fee_growth_global_1_x64;
-    msg!("Increased position liquidity by {} (tokens0+1)", delta_liquidity);
-    Ok(())
+    accrue_fees(position, &pool)?;
+    accrue_position_fees(&mut pool)?;
+    position.liquidity = position.liquidity.checked_add(delta_liquidity).ok_or(OrcaErrorCode::LiquidityOverflow)?;
+    pool.liquidity = pool.liquidity.checked_add(delta_liquidity).ok_or(OrcaErrorCode::LiquidityOverflow)?;
+    emit_cpi!(LiquidityChanged {
+        pool: pool_key,
+        user: position.owner,
+        position: Some(position.key()),
+        increase: true,
+        liquidity_delta: delta_liquidity,
+        amount_token_0,
+        amount_token_1,
+        lp_amount: 0,
+        pool_liquidity: pool.liquidity,
+    });
+    msg!("Increased position liquidity by {} for token0={}, token1={}", delta_liquidity, amount_token_0, amount_token_1);
+    Ok(LiquidityResult { liquidity_delta: delta_liquidity, amount_token_0, amount_token_1, lp_amount: 0 })
 }
 
 pub fn decrease_position_liquidity(
     ctx: Context<ModifyPositionLiquidity>,
     amount_token_0: u64,
     amount_token_1: u64,
-) -> Result<()> {
+) -> Result<LiquidityResult> {
     require!(amount_token_0 > 0 && amount_token_1 > 0, OrcaErrorCode::InvalidAmount);
-    let pool = &mut ctx.accounts.pool;
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.is_enabled(POOL_STATUS_WITHDRAW_DISABLED), OrcaErrorCode::PoolOperationDisabled);
     let position = &mut ctx.accounts.position;
     require!(position.liquidity > 0, OrcaErrorCode::PositionIsEmpty);
-    let delta_liquidity = (amount_token_0 as u128) + (amount_token_1 as u128);
+    // Withdraws the liquidity the requested amounts correspond to; the payout rounds down.
+    let delta_liquidity = liquidity_from_amounts(pool.sqrt_price_x64, amount_token_0, amount_token_1).map_err(OrcaErrorCode::from)?;
+    require!(delta_liquidity > 0, OrcaErrorCode::InvalidAmount);
     require!(delta_liquidity <= position.liquidity, OrcaErrorCode::InsufficientLiquidity);
-    position.liquidity -= delta_liquidity;
-    pool.liquidity = pool.liquidity.saturating_sub(delta_liquidity);
-    let pool_key = pool.key();
+    let (amount_token_0, amount_token_1) = amounts_for_liquidity(pool.sqrt_price_x64, delta_liquidity, false).map_err(OrcaErrorCode::from)?;
+    accrue_fees(position, &pool)?;
+    accrue_position_fees(&mut pool)?;
+    position.liquidity = position.liquidity.checked_sub(delta_liquidity).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
+    pool.liquidity = pool.liquidity.checked_sub(delta_liquidity).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
     let seeds = [crate::constants::POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
     let signer = &[&seeds[..]];
     let cpi_program = ctx.accounts.token_program.to_account_info();
     token::transfer(CpiContext::new_with_signer(cpi_program.clone(), Transfer { from: ctx.accounts.token_vault_0.to_account_info(), to: ctx.accounts.user_token_account_0.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_0)?;
     token::transfer(CpiContext::new_with_signer(cpi_program, Transfer { from: ctx.accounts.token_vault_1.to_account_info(), to: ctx.accounts.user_token_account_1.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_1)?;
-    msg!("Decreased position liquidity by {} (tokens0+1)", delta_liquidity);
+    emit_cpi!(LiquidityChanged {
+        pool: pool_key,
+        user: position.owner,
+        position: Some(position.key()),
+        increase: false,
+        liquidity_delta: delta_liquidity,
+        amount_token_0,
+        amount_token_1,
+        lp_amount: 0,
+        pool_liquidity: pool.liquidity,
+    });
+    msg!("Decreased position liquidity by {} for token0={}, token1={}", delta_liquidity, amount_token_0, amount_token_1);
+    Ok(LiquidityResult { liquidity_delta: delta_liquidity, amount_token_0, amount_token_1, lp_amount: 0 })
+}
+
+pub fn collect_fees(ctx: Context<ModifyPositionLiquidity>) -> Result<LiquidityResult> {
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(pool.is_enabled(POOL_STATUS_COLLECT_FEES_DISABLED), OrcaErrorCode::PoolOperationDisabled);
+    let position = &mut ctx.accounts.position;
+    accrue_fees(position, &pool)?;
+    let (amount_token_0, amount_token_1) = (position.tokens_owed_0, position.tokens_owed_1);
+    settle_position_fees(&mut pool, amount_token_0, amount_token_1)?;
+    position.tokens_owed_0 = 0;
+    position.tokens_owed_1 = 0;
+    let seeds = [crate::constants::POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    if amount_token_0 > 0 {
+        token::transfer(CpiContext::new_with_signer(cpi_program.clone(), Transfer { from: ctx.accounts.token_vault_0.to_account_info(), to: ctx.accounts.user_token_account_0.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_0)?;
+    }
+    if amount_token_1 > 0 {
+        token::transfer(CpiContext::new_with_signer(cpi_program, Transfer { from: ctx.accounts.token_vault_1.to_account_info(), to: ctx.accounts.user_token_account_1.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() }, signer), amount_token_1)?;
+    }
+    emit_cpi!(FeesCollected { pool: pool_key, position: position.key(), owner: position.owner, amount_token_0, amount_token_1 });
+    msg!("Collected fees: token0={}, token1={}", amount_token_0, amount_token_1);
+    Ok(LiquidityResult { liquidity_delta: 0, amount_token_0, amount_token_1, lp_amount: 0 })
+}
+
+/// Moves fees earned since the last snapshot into `tokens_owed_*`, rounding down,
+/// and re-snapshots fee growth. Must run before the position's liquidity changes.
+pub(super) fn accrue_fees(position: &mut Position, pool: &Pool) -> Result<()> {
+    let owed = |growth: u128, entry: u128| -> Result<u64> {
+        // Fee growth only ever increases, so an entry above it is corrupt state rather than a wrap.
+        let growth_delta = growth.checked_sub(entry).ok_or(OrcaErrorCode::MathUnderflow)?;
+        let amount = mul_div(position.liquidity, growth_delta, Q64, false).map_err(OrcaErrorCode::from)?;
+        Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+    };
+    let owed_0 = owed(pool.fee_growth_global_0_x64, position.fee_growth_entry_0_x64)?;
+    let owed_1 = owed(pool.fee_growth_global_1_x64, position.fee_growth_entry_1_x64)?;
+    position.tokens_owed_0 = position.tokens_owed_0.checked_add(owed_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    position.tokens_owed_1 = position.tokens_owed_1.checked_add(owed_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    position.fee_growth_entry_0_x64 = pool.fee_growth_global_0_x64;
+    position.fee_growth_entry_1_x64 = pool.fee_growth_global_1_x64;
+    Ok(())
+}
+
+/// Moves fees all positions together earned since the pool's last snapshot into
+/// `position_fees_owed_*`, rounding up, and re-snapshots fee growth. Must run
+/// before position liquidity changes.
+pub(super) fn accrue_position_fees(pool: &mut Pool) -> Result<()> {
+    let liquidity = pool.liquidity.checked_sub(pool.lp_liquidity).ok_or(OrcaErrorCode::LiquidityUnderflow)?;
+    let earned = |growth: u128, entry: u128| -> Result<u64> {
+        let growth_delta = growth.checked_sub(entry).ok_or(OrcaErrorCode::MathUnderflow)?;
+        let amount = mul_div(liquidity, growth_delta, Q64, true).map_err(OrcaErrorCode::from)?;
+        Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+    };
+    let earned_0 = earned(pool.fee_growth_global_0_x64, pool.position_fee_growth_entry_0_x64)?;
+    let earned_1 = earned(pool.fee_growth_global_1_x64, pool.position_fee_growth_entry_1_x64)?;
+    pool.position_fees_owed_0 = pool.position_fees_owed_0.checked_add(earned_0).ok_or(OrcaErrorCode::MathOverflow)?;
+    pool.position_fees_owed_1 = pool.position_fees_owed_1.checked_add(earned_1).ok_or(OrcaErrorCode::MathOverflow)?;
+    pool.position_fee_growth_entry_0_x64 = pool.fee_growth_global_0_x64;
+    pool.position_fee_growth_entry_1_x64 = pool.fee_growth_global_1_x64;
+    Ok(())
+}
+
+/// Accrues the positions' fees and takes the `amount_*` paid to one of them out.
+pub(super) fn settle_position_fees(pool: &mut Pool, amount_0: u64, amount_1: u64) -> Result<()> {
+    accrue_position_fees(pool)?;
+    pool.position_fees_owed_0 = pool.position_fees_owed_0.checked_sub(amount_0).ok_or(OrcaErrorCode::MathUnderflow)?;
+    pool.position_fees_owed_1 = pool.position_fees_owed_1.checked_sub(amount_1).ok_or(OrcaErrorCode::MathUnderflow)?;
     Ok(())
 }
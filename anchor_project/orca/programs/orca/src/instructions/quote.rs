@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use crate::{contexts::*, errors::ErrorCode as OrcaErrorCode};
+use super::swap::{self, SwapQuote};
+
+/// Runs the same pricing as `swap` without moving tokens or touching the pool.
+/// `other_amount_threshold` is the minimum output for exact-input quotes and
+/// the maximum input for exact-output ones, as a `swap` would enforce.
+pub fn quote_swap(
+    ctx: Context<QuoteSwap>,
+    amount: u64,
+    other_amount_threshold: u64,
+    zero_for_one: bool,
+    sqrt_price_limit_x64: u128,
+    exact_input: bool,
+) -> Result<SwapQuote> {
+    let quote = swap::quote(&*ctx.accounts.pool.load()?, &*ctx.accounts.limit_order_book.load()?, amount, zero_for_one, sqrt_price_limit_x64, exact_input)?;
+    if exact_input {
+        require!(quote.amount_out >= other_amount_threshold, OrcaErrorCode::AmountOutTooLow);
+    } else {
+        require!(quote.amount_in <= other_amount_threshold, OrcaErrorCode::AmountInAboveMaximum);
+    }
+    msg!("Quote: {} in ({} fee), {} out, sqrt_price: {}", quote.amount_in, quote.fee_amount, quote.amount_out, quote.sqrt_price_after_x64);
+    Ok(quote)
+}
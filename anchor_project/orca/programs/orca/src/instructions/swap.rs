@@ -1,35 +1,240 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Transfer};
-use crate::{contexts::*, errors::ErrorCode as OrcaErrorCode};
-use crate::constants::POOL_AUTHORITY_SEED;
+use crate::{contexts::*, errors::ErrorCode as OrcaErrorCode, events, state::{LimitOrderBook, Pool}};
+use crate::constants::{MAX_SQRT_PRICE, MIN_SQRT_PRICE, POOL_AUTHORITY_SEED, PROTOCOL_FEE_RATE_DENOMINATOR, Q64};
+use orca_math::{compute_swap_step, compute_swap_step_exact_out, mul_div, sqrt_price_to_tick};
+
+/// Priced outcome of a swap, returned by both `swap` and `quote_swap`;
+/// `amount_in` includes `fee_amount`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub sqrt_price_after_x64: u128,
+    pub tick_after: i32,
+    pub fee_rate: u16,
+}
+
+/// Part of a swap at constant liquidity: the pool's, plus that of the
+/// limit-order range at `range` while the price is inside it. `amount_in`
+/// excludes `fee_amount`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapSegment {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub liquidity: u128,
+    pub range: Option<usize>,
+    pub fills_range: bool,
+}
 
 pub fn handler(
     ctx: Context<Swap>,
     amount_in: u64,
     minimum_amount_out: u64,
     zero_for_one: bool,
-) -> Result<()> {
-    require!(amount_in > 0, OrcaErrorCode::InvalidAmount);
-    let pool = &mut ctx.accounts.pool;
-    let fee_amount = (amount_in as u128 * pool.fee_rate as u128 / 1_000_000u128) as u64;
-    let amount_in_after_fee = amount_in.saturating_sub(fee_amount);
-    let amount_out = amount_in_after_fee / 2;
+    sqrt_price_limit_x64: u128,
+) -> Result<SwapQuote> {
+    let pool_key = ctx.accounts.pool.key();
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    let mut book = ctx.accounts.limit_order_book.load_mut()?;
+    let sqrt_price_before_x64 = pool.sqrt_price_x64;
+    let tick_before = pool.tick_current;
+    let (quote, segments) = route(&pool, &book, amount_in, zero_for_one, sqrt_price_limit_x64, true)?;
+    // Stopping at the limit leaves part of `amount_in` unspent; only the filled part is pulled from the user.
+    let amount_in_filled = quote.amount_in;
+    let amount_out = quote.amount_out;
+    let fee_amount = quote.fee_amount;
     require!(amount_out >= minimum_amount_out, OrcaErrorCode::AmountOutTooLow);
-    if zero_for_one { pool.protocol_fees_token_0 = pool.protocol_fees_token_0.saturating_add(fee_amount); } else { pool.protocol_fees_token_1 = pool.protocol_fees_token_1.saturating_add(fee_amount); }
-    if pool.liquidity > 0 { let fee_growth_increment = ((fee_amount as u128) << 64) / pool.liquidity; if zero_for_one { pool.fee_growth_global_0_x64 = pool.fee_growth_global_0_x64.saturating_add(fee_growth_increment); } else { pool.fee_growth_global_1_x64 = pool.fee_growth_global_1_x64.saturating_add(fee_growth_increment); } }
+    for segment in &segments {
+        credit_fees(&mut pool, &mut book, segment, ctx.accounts.amm_config.protocol_fee_rate, zero_for_one)?;
+        convert_range(&mut book, segment, zero_for_one)?;
+    }
+    pool.sqrt_price_x64 = quote.sqrt_price_after_x64;
+    pool.tick_current = quote.tick_after;
+    // Ticks moved feed the dynamic fee; movement in ticks means the same thing at every price.
+    let clock_now = Clock::get()?;
+    let tick_movement = (quote.tick_after as i64 - tick_before as i64).unsigned_abs();
+    pool.volatility_accumulator = pool.volatility_at(clock_now.unix_timestamp).checked_add(tick_movement).ok_or(OrcaErrorCode::MathOverflow)?;
+    pool.volatility_updated_at = clock_now.unix_timestamp;
     let cpi_accounts_in = Transfer { from: ctx.accounts.user_token_account_in.to_account_info(), to: ctx.accounts.token_vault_in.to_account_info(), authority: ctx.accounts.user.to_account_info() };
     let cpi_program_in = ctx.accounts.token_program.to_account_info();
-    token::transfer(CpiContext::new(cpi_program_in, cpi_accounts_in), amount_in)?;
-    let pool_key = pool.key();
+    token::transfer(CpiContext::new(cpi_program_in, cpi_accounts_in), amount_in_filled)?;
     let pool_seeds = &[POOL_AUTHORITY_SEED.as_bytes(), pool_key.as_ref(), &[pool.auth_bump]];
     let signer_seeds = &[&pool_seeds[..]];
     let cpi_accounts_out = Transfer { from: ctx.accounts.token_vault_out.to_account_info(), to: ctx.accounts.user_token_account_out.to_account_info(), authority: ctx.accounts.pool_authority.to_account_info() };
     let cpi_program_out = ctx.accounts.token_program.to_account_info();
     token::transfer(CpiContext::new_with_signer(cpi_program_out, cpi_accounts_out, signer_seeds), amount_out)?;
-    let clock_now = Clock::get()?;
-    let obs = &mut ctx.accounts.observation_state;
-    let elapsed = clock_now.unix_timestamp - obs.last_timestamp;
-    if elapsed > 0 { obs.tick_cumulative = obs.tick_cumulative.saturating_add((pool.tick_current as i128) * (elapsed as i128)); obs.last_timestamp = clock_now.unix_timestamp; }
-    msg!("Swap completed: {} in, {} out", amount_in, amount_out);
+    let mut obs = ctx.accounts.observation_state.load_mut()?;
+    let elapsed = clock_now.unix_timestamp.checked_sub(obs.last_timestamp).ok_or(OrcaErrorCode::MathUnderflow)?;
+    // The tick in force since the last observation is the pre-swap one.
+    if elapsed > 0 {
+        let tick_delta = (tick_before as i128).checked_mul(elapsed as i128).ok_or(OrcaErrorCode::MathOverflow)?;
+        obs.tick_cumulative = obs.tick_cumulative.checked_add(tick_delta).ok_or(OrcaErrorCode::MathOverflow)?;
+        obs.last_timestamp = clock_now.unix_timestamp;
+    }
+    for index in segments.iter().filter(|segment| segment.fills_range).filter_map(|segment| segment.range) {
+        let range = &book.ranges[index];
+        emit_cpi!(events::LimitOrderRangeFilled { pool: pool_key, tick_lower: range.tick_lower, liquidity: range.liquidity });
+    }
+    emit_cpi!(events::Swap {
+        pool: pool_key,
+        user: ctx.accounts.user.key(),
+        zero_for_one,
+        amount_in: amount_in_filled,
+        amount_out,
+        fee_amount,
+        sqrt_price_before_x64,
+        sqrt_price_after_x64: pool.sqrt_price_x64,
+        tick_before,
+        tick_after: pool.tick_current,
+        liquidity: pool.liquidity,
+        fee_rate: quote.fee_rate,
+    });
+    msg!("Swap completed: {} in ({} fee) of {} requested, {} out, sqrt_price: {}", amount_in_filled, fee_amount, amount_in, amount_out, pool.sqrt_price_x64);
+    Ok(quote)
+}
+
+/// Splits a segment's fee between the protocol and the liquidity it was paid
+/// to. Fee growth is per unit of all liquidity in range, so the pool's and the
+/// limit-order range's growth move by the same increment; freezes the range
+/// if the segment filled it.
+fn credit_fees(pool: &mut Pool, book: &mut LimitOrderBook, segment: &SwapSegment, protocol_fee_rate: u16, zero_for_one: bool) -> Result<()> {
+    let protocol_fee = mul_div(segment.fee_amount as u128, protocol_fee_rate as u128, PROTOCOL_FEE_RATE_DENOMINATOR, false).map_err(OrcaErrorCode::from)?;
+    let protocol_fee = u64::try_from(protocol_fee).map_err(|_| OrcaErrorCode::MathOverflow)?;
+    let lp_fee = segment.fee_amount.checked_sub(protocol_fee).ok_or(OrcaErrorCode::MathUnderflow)?;
+    let fee_growth_increment = mul_div(lp_fee as u128, Q64, segment.liquidity, false).map_err(OrcaErrorCode::from)?;
+    let (protocol_fees, fee_growth) = if zero_for_one {
+        (&mut pool.protocol_fees_token_0, &mut pool.fee_growth_global_0_x64)
+    } else {
+        (&mut pool.protocol_fees_token_1, &mut pool.fee_growth_global_1_x64)
+    };
+    *protocol_fees = protocol_fees.checked_add(protocol_fee).ok_or(OrcaErrorCode::MathOverflow)?;
+    *fee_growth = fee_growth.checked_add(fee_growth_increment).ok_or(OrcaErrorCode::MathOverflow)?;
+    let Some(index) = segment.range else { return Ok(()) };
+    let range = &mut book.ranges[index];
+    // Rounded up so the range never owes its orders less than they can claim.
+    let owed = mul_div(fee_growth_increment, range.liquidity, Q64, true).map_err(OrcaErrorCode::from)?;
+    let owed = u64::try_from(owed).map_err(|_| OrcaErrorCode::MathOverflow)?;
+    let (range_fee_growth, fees_owed) = if zero_for_one {
+        (&mut range.fee_growth_0_x64, &mut range.fees_owed_0)
+    } else {
+        (&mut range.fee_growth_1_x64, &mut range.fees_owed_1)
+    };
+    *range_fee_growth = range_fee_growth.checked_add(fee_growth_increment).ok_or(OrcaErrorCode::MathOverflow)?;
+    *fees_owed = fees_owed.checked_add(owed).ok_or(OrcaErrorCode::MathOverflow)?;
+    range.filled |= segment.fills_range as u8;
     Ok(())
 }
+
+/// Moves the range's part of a segment's trade into its stored balances: its
+/// share of the input rounded down and of the output rounded up, so the range
+/// never holds more than the vaults received for it.
+fn convert_range(book: &mut LimitOrderBook, segment: &SwapSegment, zero_for_one: bool) -> Result<()> {
+    let Some(index) = segment.range else { return Ok(()) };
+    let range = &mut book.ranges[index];
+    let share = |amount: u64, round_up: bool| -> Result<u64> {
+        let amount = mul_div(amount as u128, range.liquidity, segment.liquidity, round_up).map_err(OrcaErrorCode::from)?;
+        Ok(u64::try_from(amount).map_err(|_| OrcaErrorCode::MathOverflow)?)
+    };
+    let (amount_in, amount_out) = (share(segment.amount_in, false)?, share(segment.amount_out, true)?);
+    let (balance_in, balance_out) = if zero_for_one { (&mut range.amount_0, &mut range.amount_1) } else { (&mut range.amount_1, &mut range.amount_0) };
+    *balance_in = balance_in.checked_add(amount_in).ok_or(OrcaErrorCode::MathOverflow)?;
+    // Rounding up can ask a range crossed to its far side for a unit more than it holds.
+    *balance_out = balance_out.saturating_sub(amount_out);
+    Ok(())
+}
+
+/// Prices a swap against the pool's current state without modifying it. This
+/// is the only pricing path: `swap` applies its result and `quote_swap`
+/// returns it. `amount` is the input for exact-input swaps and the desired
+/// output otherwise; reaching `sqrt_price_limit_x64` first fills only part of it.
+pub fn quote(
+    pool: &Pool,
+    book: &LimitOrderBook,
+    amount: u64,
+    zero_for_one: bool,
+    sqrt_price_limit_x64: u128,
+    exact_input: bool,
+) -> Result<SwapQuote> {
+    Ok(route(pool, book, amount, zero_for_one, sqrt_price_limit_x64, exact_input)?.0)
+}
+
+/// `quote` together with the segments the price crossed, one per stretch of
+/// constant liquidity between limit-order range boundaries.
+pub fn route(
+    pool: &Pool,
+    book: &LimitOrderBook,
+    amount: u64,
+    zero_for_one: bool,
+    sqrt_price_limit_x64: u128,
+    exact_input: bool,
+) -> Result<(SwapQuote, Vec<SwapSegment>)> {
+    require!(amount > 0, OrcaErrorCode::InvalidAmount);
+    require!(pool.liquidity > 0, OrcaErrorCode::InsufficientLiquidity);
+    // The limit has to sit strictly on the side of the current price the trade moves towards.
+    if zero_for_one {
+        require!(sqrt_price_limit_x64 >= MIN_SQRT_PRICE && sqrt_price_limit_x64 < pool.sqrt_price_x64, OrcaErrorCode::InvalidSqrtPriceLimit);
+    } else {
+        require!(sqrt_price_limit_x64 > pool.sqrt_price_x64 && sqrt_price_limit_x64 <= MAX_SQRT_PRICE, OrcaErrorCode::InvalidSqrtPriceLimit);
+    }
+    let fee_rate = pool.effective_fee_rate(Clock::get()?.unix_timestamp);
+    // Bounds of the ranges in the curve; a range filled along the way drops out for the rest of the swap.
+    let mut ranges = book
+        .ranges
+        .iter()
+        .map(|range| if range.is_active() { range.sqrt_prices(pool.tick_spacing).map(Some) } else { Ok(None) })
+        .collect::<Result<Vec<Option<(u128, u128)>>>>()?;
+    let mut sqrt_price = pool.sqrt_price_x64;
+    let mut remaining = amount;
+    let (mut amount_in, mut amount_out, mut fee_amount) = (0u64, 0u64, 0u64);
+    let mut segments = Vec::new();
+    while remaining > 0 && sqrt_price != sqrt_price_limit_x64 {
+        // A range includes the bound the price enters it through, so moving down its upper bound is inside it.
+        let range = ranges.iter().position(|bounds| {
+            bounds.is_some_and(|(lower, upper)| if zero_for_one { lower < sqrt_price && sqrt_price <= upper } else { lower <= sqrt_price && sqrt_price < upper })
+        });
+        let boundaries = ranges.iter().flatten().flat_map(|&(lower, upper)| [lower, upper]);
+        let target = if zero_for_one {
+            boundaries.filter(|bound| *bound < sqrt_price).max().map_or(sqrt_price_limit_x64, |bound| bound.max(sqrt_price_limit_x64))
+        } else {
+            boundaries.filter(|bound| *bound > sqrt_price).min().map_or(sqrt_price_limit_x64, |bound| bound.min(sqrt_price_limit_x64))
+        };
+        let order_liquidity = range.map_or(0, |index| book.ranges[index].liquidity);
+        let liquidity = pool.liquidity.checked_add(order_liquidity).ok_or(OrcaErrorCode::LiquidityOverflow)?;
+        let step = if exact_input {
+            compute_swap_step(sqrt_price, target, liquidity, remaining, fee_rate, zero_for_one)
+        } else {
+            compute_swap_step_exact_out(sqrt_price, target, liquidity, remaining, fee_rate, zero_for_one)
+        }
+        .map_err(OrcaErrorCode::from)?;
+        let spent = step.amount_in.checked_add(step.fee_amount).ok_or(OrcaErrorCode::MathOverflow)?;
+        remaining = remaining.checked_sub(if exact_input { spent } else { step.amount_out }).ok_or(OrcaErrorCode::MathUnderflow)?;
+        amount_in = amount_in.checked_add(spent).ok_or(OrcaErrorCode::MathOverflow)?;
+        amount_out = amount_out.checked_add(step.amount_out).ok_or(OrcaErrorCode::MathOverflow)?;
+        fee_amount = fee_amount.checked_add(step.fee_amount).ok_or(OrcaErrorCode::MathOverflow)?;
+        sqrt_price = step.sqrt_price_next_x64;
+        // Reaching the far side means the range is fully converted; it is frozen there instead of swapping back.
+        let fills_range = range.is_some_and(|index| {
+            let (lower, upper) = ranges[index].unwrap_or_default();
+            if book.ranges[index].sells_token_0 != 0 { !zero_for_one && sqrt_price >= upper } else { zero_for_one && sqrt_price <= lower }
+        });
+        if let (true, Some(index)) = (fills_range, range) {
+            ranges[index] = None;
+        }
+        segments.push(SwapSegment { amount_in: step.amount_in, amount_out: step.amount_out, fee_amount: step.fee_amount, liquidity, range, fills_range });
+        if sqrt_price != target {
+            break;
+        }
+    }
+    let quote = SwapQuote {
+        amount_in,
+        amount_out,
+        fee_amount,
+        sqrt_price_after_x64: sqrt_price,
+        tick_after: sqrt_price_to_tick(sqrt_price).map_err(OrcaErrorCode::from)?,
+        fee_rate,
+    };
+    Ok((quote, segments))
+}
@@ -1,14 +1,18 @@
+// The `#[program]` expansion calls `AccountInfo::realloc`, deprecated in newer solana-program.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 
 pub mod constants;
 pub mod errors;
+pub mod events;
 pub mod state;
-// pub mod math; // removed to avoid conflict with math directory
 pub mod contexts;
 pub mod instructions;
 
 use contexts::*;
-use instructions::{initialize_amm, create_pool, liquidity, swap, position};
+use instructions::{initialize_amm, create_pool, liquidity, swap, position, quote, flash_loan, admin, emergency, limit_order, migrate, allowlist};
+use instructions::{liquidity::LiquidityResult, swap::SwapQuote};
 
 declare_id!("9P6cZJHLnLu77ur6CdpNEgTZPnVVHweozgo4ykc9LMVZ");
 
@@ -18,10 +22,11 @@ pub mod orca {
 
     pub fn initialize_amm(
         ctx: Context<InitializeAmm>,
+        index: u16,
         fee_rate: u16,
         protocol_fee_rate: u16,
     ) -> Result<()> {
-        initialize_amm::handler(ctx, fee_rate, protocol_fee_rate)
+        initialize_amm::handler(ctx, index, fee_rate, protocol_fee_rate)
     }
 
     pub fn create_pool(
@@ -32,20 +37,29 @@ pub mod orca {
         create_pool::handler(ctx, sqrt_price_x64, tick_spacing)
     }
 
+    pub fn create_pool_with_liquidity(
+        ctx: Context<CreatePoolWithLiquidity>,
+        sqrt_price_x64: Option<u128>,
+        tick_spacing: u16,
+        amount_token_0: u64,
+        amount_token_1: u64,
+    ) -> Result<LiquidityResult> {
+        create_pool::create_pool_with_liquidity(ctx, sqrt_price_x64, tick_spacing, amount_token_0, amount_token_1)
+    }
+
     pub fn add_liquidity(
         ctx: Context<ModifyLiquidity>,
         amount_token_0: u64,
         amount_token_1: u64,
-    ) -> Result<()> {
+    ) -> Result<LiquidityResult> {
         liquidity::add_liquidity(ctx, amount_token_0, amount_token_1)
     }
 
     pub fn remove_liquidity(
         ctx: Context<ModifyLiquidity>,
-        amount_token_0: u64,
-        amount_token_1: u64,
-    ) -> Result<()> {
-        liquidity::remove_liquidity(ctx, amount_token_0, amount_token_1)
+        lp_amount: u64,
+    ) -> Result<LiquidityResult> {
+        liquidity::remove_liquidity(ctx, lp_amount)
     }
 
     pub fn swap(
@@ -53,8 +67,20 @@ pub mod orca {
         amount_in: u64,
         minimum_amount_out: u64,
         zero_for_one: bool,
-    ) -> Result<()> {
-        swap::handler(ctx, amount_in, minimum_amount_out, zero_for_one)
+        sqrt_price_limit_x64: u128,
+    ) -> Result<SwapQuote> {
+        swap::handler(ctx, amount_in, minimum_amount_out, zero_for_one, sqrt_price_limit_x64)
+    }
+
+    pub fn quote_swap(
+        ctx: Context<QuoteSwap>,
+        amount: u64,
+        other_amount_threshold: u64,
+        zero_for_one: bool,
+        sqrt_price_limit_x64: u128,
+        exact_input: bool,
+    ) -> Result<SwapQuote> {
+        quote::quote_swap(ctx, amount, other_amount_threshold, zero_for_one, sqrt_price_limit_x64, exact_input)
     }
 
     pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
@@ -65,7 +91,7 @@ pub mod orca {
         ctx: Context<ModifyPositionLiquidity>,
         amount_token_0: u64,
         amount_token_1: u64,
-    ) -> Result<()> {
+    ) -> Result<LiquidityResult> {
         position::increase_position_liquidity(ctx, amount_token_0, amount_token_1)
     }
 
@@ -73,7 +99,128 @@ pub mod orca {
         ctx: Context<ModifyPositionLiquidity>,
         amount_token_0: u64,
         amount_token_1: u64,
-    ) -> Result<()> {
+    ) -> Result<LiquidityResult> {
         position::decrease_position_liquidity(ctx, amount_token_0, amount_token_1)
     }
+
+    pub fn collect_fees(ctx: Context<ModifyPositionLiquidity>) -> Result<LiquidityResult> {
+        position::collect_fees(ctx)
+    }
+
+    pub fn flash_borrow(
+        ctx: Context<FlashLoan>,
+        amount_token_0: u64,
+        amount_token_1: u64,
+    ) -> Result<()> {
+        flash_loan::flash_borrow(ctx, amount_token_0, amount_token_1)
+    }
+
+    pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+        flash_loan::flash_repay(ctx)
+    }
+
+    pub fn set_flash_fee_rate(ctx: Context<UpdateAmmConfig>, flash_fee_rate: u16) -> Result<()> {
+        admin::set_flash_fee_rate(ctx, flash_fee_rate)
+    }
+
+    pub fn set_pool_creation_policy(
+        ctx: Context<UpdateAmmConfig>,
+        permissioned_pool_creation: bool,
+        token_badges_required: bool,
+    ) -> Result<()> {
+        admin::set_pool_creation_policy(ctx, permissioned_pool_creation, token_badges_required)
+    }
+
+    pub fn set_pool_status(ctx: Context<PoolAdmin>, status: u8) -> Result<()> {
+        admin::set_pool_status(ctx, status)
+    }
+
+    pub fn enter_emergency_mode(ctx: Context<PoolAdmin>) -> Result<()> {
+        emergency::enter_emergency_mode(ctx)
+    }
+
+    pub fn schedule_emergency_exit(ctx: Context<PoolAdmin>) -> Result<()> {
+        emergency::schedule_emergency_exit(ctx)
+    }
+
+    pub fn exit_emergency_mode(ctx: Context<PoolAdmin>) -> Result<()> {
+        emergency::exit_emergency_mode(ctx)
+    }
+
+    pub fn emergency_remove_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        lp_amount: u64,
+    ) -> Result<LiquidityResult> {
+        emergency::emergency_remove_liquidity(ctx, lp_amount)
+    }
+
+    pub fn emergency_withdraw_position(ctx: Context<ModifyPositionLiquidity>) -> Result<LiquidityResult> {
+        emergency::emergency_withdraw_position(ctx)
+    }
+
+    pub fn set_dynamic_fee(
+        ctx: Context<PoolAdmin>,
+        dynamic_fee_max_rate: u16,
+        dynamic_fee_rate_per_tick: u16,
+    ) -> Result<()> {
+        admin::set_dynamic_fee(ctx, dynamic_fee_max_rate, dynamic_fee_rate_per_tick)
+    }
+
+    pub fn set_limit_order_min_liquidity(
+        ctx: Context<PoolAdmin>,
+        limit_order_min_liquidity: u128,
+    ) -> Result<()> {
+        admin::set_limit_order_min_liquidity(ctx, limit_order_min_liquidity)
+    }
+
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        tick_lower: i32,
+        amount: u64,
+    ) -> Result<LiquidityResult> {
+        limit_order::place_limit_order(ctx, tick_lower, amount)
+    }
+
+    pub fn cancel_limit_order(ctx: Context<CloseLimitOrder>) -> Result<LiquidityResult> {
+        limit_order::cancel_limit_order(ctx)
+    }
+
+    pub fn claim_limit_order(ctx: Context<CloseLimitOrder>) -> Result<LiquidityResult> {
+        limit_order::claim_limit_order(ctx)
+    }
+    pub fn migrate_amm_config(ctx: Context<MigrateAmmConfig>) -> Result<()> {
+        migrate::migrate_amm_config(ctx)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        migrate::migrate_pool(ctx)
+    }
+
+    pub fn migrate_pool_v1(ctx: Context<MigratePoolV1>) -> Result<()> {
+        migrate::migrate_pool_v1(ctx)
+    }
+
+    pub fn migrate_position(ctx: Context<MigrateAccount>) -> Result<()> {
+        migrate::migrate_position(ctx)
+    }
+
+    pub fn migrate_observation_state(ctx: Context<MigrateAccount>) -> Result<()> {
+        migrate::migrate_observation_state(ctx)
+    }
+
+    pub fn add_pool_creator(ctx: Context<AddPoolCreator>) -> Result<()> {
+        allowlist::add_pool_creator(ctx)
+    }
+
+    pub fn remove_pool_creator(ctx: Context<RemovePoolCreator>) -> Result<()> {
+        allowlist::remove_pool_creator(ctx)
+    }
+
+    pub fn add_token_badge(ctx: Context<AddTokenBadge>) -> Result<()> {
+        allowlist::add_token_badge(ctx)
+    }
+
+    pub fn remove_token_badge(ctx: Context<RemoveTokenBadge>) -> Result<()> {
+        allowlist::remove_token_badge(ctx)
+    }
 }
@@ -1,4 +1,7 @@
 use anchor_lang::prelude::*;
+use crate::constants::AMM_CONFIG_SEED;
+use crate::errors::ErrorCode as OrcaErrorCode;
+use orca_math::{amounts_for_range_liquidity, tick_to_sqrt_price};
 
 #[account]
 pub struct AmmConfig {
@@ -9,43 +12,132 @@ pub struct AmmConfig {
     pub protocol_fee_destination: Pubkey,
     pub create_pool_enabled: bool,
     pub swap_enabled: bool,
+    pub flash_fee_rate: u16, // same units as fee_rate
+    /// `ACCOUNT_VERSION`; the SDK reads a legacy account as version 0
+    pub version: u8,
+    pub index: u16, // the config's PDA seed, next to AMM_CONFIG_SEED
+    // only creators with a PoolCreator account under this config may create pools
+    pub permissioned_pool_creation: bool,
+    // both mints of a new pool need a TokenBadge under this config
+    pub token_badges_required: bool,
+    pub reserved: [u8; 59],
 }
 
 impl AmmConfig {
     pub const SEED: &'static str = "amm_config";
-    pub const SIZE: usize = 8 + 1 + 32 + 2 + 2 + 32 + 1 + 1;
+    pub const SIZE: usize = 8 + 1 + 32 + 2 + 2 + 32 + 1 + 1 + 2 + 1 + 2 + 1 + 1 + 59;
+
+    /// Whether `key` is this config's PDA. Config 0 may also be the config made
+    /// before configs were indexed, at `AMM_CONFIG_SEED` alone, so that the
+    /// pools bound to it keep working once it is migrated.
+    pub fn is_address(&self, key: &Pubkey) -> bool {
+        let derives = |seeds: &[&[u8]]| Pubkey::create_program_address(seeds, &crate::ID).is_ok_and(|address| address == *key);
+        derives(&[AMM_CONFIG_SEED.as_bytes(), &self.index.to_le_bytes(), &[self.bump]])
+            || (self.index == 0 && derives(&[AMM_CONFIG_SEED.as_bytes(), &[self.bump]]))
+    }
 }
 
-#[account]
+/// Zero-copy: swaps read and write the pool in place instead of deserializing
+/// it. Fields are ordered by alignment so the `repr(C)` layout has no padding
+/// on any target; booleans are stored as `u8`.
+#[account(zero_copy)]
+#[repr(C)]
 pub struct Pool {
-    pub bump: u8,
+    pub liquidity: u128, // added global liquidity
+    pub sqrt_price_x64: u128,
+    // fee growth accumulators (per unit liquidity) for later per-position accounting
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+    // the part of `liquidity` the LP share supply (plus MINIMUM_LIQUIDITY locked shares) is backed by
+    pub lp_liquidity: u128,
     pub amm_config: Pubkey,
     pub token_mint_0: Pubkey,
     pub token_mint_1: Pubkey,
     pub token_vault_0: Pubkey,
     pub token_vault_1: Pubkey,
-    pub auth_bump: u8,
-    pub liquidity: u128, // added global liquidity
-    pub sqrt_price_x64: u128,
+    pub lp_mint: Pubkey,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    // outstanding while flash_loan_locked is set
+    pub flash_loan_amount_0: u64,
+    pub flash_loan_amount_1: u64,
+    // when emergency mode may be left, 0 if no exit is scheduled
+    pub emergency_exit_after: i64,
+    pub volatility_accumulator: u64,
+    pub volatility_updated_at: i64,
     pub tick_current: i32,
     pub tick_spacing: u16,
     pub fee_rate: u16,
-    pub protocol_fees_token_0: u64,
-    pub protocol_fees_token_1: u64,
-    // fee growth accumulators (per unit liquidity) for later per-position accounting
-    pub fee_growth_global_0_x64: u128,
-    pub fee_growth_global_1_x64: u128,
     // simple split percentages (bps) for lp, protocol, impact funds
     pub fee_split_lp_bps: u16,
     pub fee_split_protocol_bps: u16,
     pub fee_split_impact_bps: u16,
+    // dynamic fees: fee_rate plus dynamic_fee_rate_per_tick for each tick of recent movement, up to
+    // dynamic_fee_max_rate; off while dynamic_fee_rate_per_tick is 0. The accumulator is tracked regardless.
+    pub dynamic_fee_max_rate: u16,
+    pub dynamic_fee_rate_per_tick: u16,
+    pub bump: u8,
+    pub auth_bump: u8,
+    // set between flash_borrow and flash_repay; swaps and liquidity changes are refused meanwhile
+    pub flash_loan_locked: u8,
+    // POOL_STATUS_* bits set by the config authority
+    pub status: u8,
+    // withdraw-only mode
+    pub emergency_mode: u8,
+    pub padding: [u8; 1],
+    /// `ACCOUNT_VERSION`; the SDK reads a legacy account as version 0
+    pub version: u8,
+    // set once lp_fee_growth_entry_* is tracked, at creation, migration or the next share change;
+    // share fees from before that stay in the vaults
+    pub lp_fees_tracked: u8,
+    pub padding_1: [u8; 14],
+    // fee growth the share pool has been credited up to, like a position's entry
+    pub lp_fee_growth_entry_0_x64: u128,
+    pub lp_fee_growth_entry_1_x64: u128,
+    // fees owed to all shares together, paid out pro rata as shares are redeemed
+    pub lp_fees_owed_0: u64,
+    pub lp_fees_owed_1: u64,
+    // the same for all position liquidity (`liquidity - lp_liquidity`), rounded up so it covers what
+    // positions collect one by one; the emergency split leaves these fees out
+    pub position_fee_growth_entry_0_x64: u128,
+    pub position_fee_growth_entry_1_x64: u128,
+    pub position_fees_owed_0: u64,
+    pub position_fees_owed_1: u64,
+    // the least liquidity a limit order may place, so dust orders cannot fill the book's slots
+    pub limit_order_min_liquidity: u128,
+    pub reserved: [u8; 48],
 }
 
 impl Pool {
     pub const SEED: &'static str = "pool";
-    pub const SIZE: usize = 8 + 1 + (32 * 6) + 1 + 16 + 16 + 16 + 4 + 2 + 2 + 8 + 8 + 16 + 16 + 2 + 2 + 2; // updated for new fields
+
+    /// Whether the operation behind a `POOL_STATUS_*` bit is allowed.
+    pub fn is_enabled(&self, status_bit: u8) -> bool {
+        let disabled = if self.emergency_mode != 0 { self.status | crate::constants::EMERGENCY_MODE_DISABLED } else { self.status };
+        disabled & status_bit == 0
+    }
+
+    /// Ticks moved by recent swaps, halved for every `VOLATILITY_HALF_LIFE` since the last one.
+    pub fn volatility_at(&self, now: i64) -> u64 {
+        let half_lives = (now - self.volatility_updated_at).max(0) / crate::constants::VOLATILITY_HALF_LIFE;
+        self.volatility_accumulator.checked_shr(half_lives.try_into().unwrap_or(u32::MAX)).unwrap_or(0)
+    }
+
+    /// The fee rate a swap at `now` pays; the base `fee_rate` unless dynamic fees are on.
+    pub fn effective_fee_rate(&self, now: i64) -> u16 {
+        if self.dynamic_fee_rate_per_tick == 0 {
+            return self.fee_rate;
+        }
+        // Bounded by the cap, so saturating here cannot misstate anything
+        let variable = self.volatility_at(now).saturating_mul(self.dynamic_fee_rate_per_tick as u64);
+        (self.fee_rate as u64).saturating_add(variable).min(self.dynamic_fee_max_rate as u64) as u16
+    }
+
+    pub const SIZE: usize = 8 + (16 * 5) + (32 * 6) + (8 * 7) + 4 + (2 * 7) + 5 + 1 + 1 + 1 + 14 + (16 * 2) + (8 * 2) + (16 * 2) + (8 * 2) + 16 + 48;
 }
 
+const _: () = assert!(Pool::SIZE == 8 + std::mem::size_of::<Pool>());
+
 #[account]
 pub struct Position {
     pub bump: u8,
@@ -56,24 +148,394 @@ pub struct Position {
     pub fee_growth_entry_1_x64: u128,
     pub tokens_owed_0: u64,
     pub tokens_owed_1: u64,
+    /// `ACCOUNT_VERSION`; the SDK reads a legacy account as version 0
+    pub version: u8,
+    pub reserved: [u8; 63],
 }
 
 impl Position {
     pub const SEED: &'static str = "position";
-    pub const SIZE: usize = 8 + 1 + 32 + 32 + 16 + 16 + 16 + 8 + 8;
+    pub const SIZE: usize = 8 + 1 + 32 + 32 + 16 + 16 + 16 + 8 + 8 + 1 + 63;
 }
 
-#[account]
+#[account(zero_copy)]
+#[repr(C)]
 pub struct ObservationState {
-    pub bump: u8,
+    pub tick_cumulative: i128,
+    pub last_timestamp: i64,
     pub pool: Pubkey,
+    pub bump: u8,
     pub index: u8,
     pub cardinality: u8,
-    pub tick_cumulative: i128,
-    pub last_timestamp: i64,
+    pub padding: [u8; 5],
+    /// `ACCOUNT_VERSION`; the SDK reads a legacy account as version 0
+    pub version: u8,
+    pub reserved: [u8; 63],
 }
 
 impl ObservationState {
     pub const SEED: &'static str = "observation";
-    pub const SIZE: usize = 8 + 1 + 32 + 1 + 1 + 16 + 8;
+    pub const SIZE: usize = 8 + 16 + 8 + 32 + 1 + 1 + 1 + 5 + 1 + 63;
+}
+
+const _: () = assert!(ObservationState::SIZE == 8 + std::mem::size_of::<ObservationState>());
+
+/// Limit-order liquidity on one `tick_spacing`-wide range starting at
+/// `tick_lower`. It is part of the curve only while the price is inside the
+/// range; once a swap carries the price out through the far side it is
+/// `filled`, leaves the curve and stays fully converted until claimed.
+#[zero_copy]
+#[repr(C)]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LimitOrderRange {
+    pub liquidity: u128,
+    // fee growth per unit of the range's liquidity, and what is still owed to its orders (rounded up)
+    pub fee_growth_0_x64: u128,
+    pub fee_growth_1_x64: u128,
+    pub fees_owed_0: u64,
+    pub fees_owed_1: u64,
+    // tokens the range's liquidity holds, deposited by its orders and converted by swaps through it
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub tick_lower: i32,
+    pub open_orders: u32, // the slot is free when this is 0
+    pub sells_token_0: u8, // placed above the price, converts to token 1 as the price rises through it
+    pub filled: u8,
+    pub padding: [u8; 6],
+}
+
+impl LimitOrderRange {
+    pub const SIZE: usize = 16 + 16 + 16 + 8 + 8 + 8 + 8 + 4 + 4 + 1 + 1 + 6;
+
+    /// Whether the range's liquidity is currently part of the curve.
+    pub fn is_active(&self) -> bool {
+        self.open_orders > 0 && self.filled == 0
+    }
+
+    pub fn sqrt_prices(&self, tick_spacing: u16) -> Result<(u128, u128)> {
+        let tick_upper = self.tick_lower.checked_add(tick_spacing as i32).ok_or(OrcaErrorCode::TickOutOfBounds)?;
+        let lower = tick_to_sqrt_price(self.tick_lower).map_err(OrcaErrorCode::from)?;
+        let upper = tick_to_sqrt_price(tick_upper).map_err(OrcaErrorCode::from)?;
+        Ok((lower, upper))
+    }
+
+    /// Tokens backing `liquidity` of this range at `sqrt_price_x64`; a filled
+    /// range is held at the far side it was filled at.
+    pub fn amounts(&self, sqrt_price_x64: u128, tick_spacing: u16, liquidity: u128, round_up: bool) -> Result<(u64, u64)> {
+        let (lower, upper) = self.sqrt_prices(tick_spacing)?;
+        let sqrt_price_x64 = match (self.filled != 0, self.sells_token_0 != 0) {
+            (true, true) => upper,
+            (true, false) => lower,
+            (false, _) => sqrt_price_x64,
+        };
+        Ok(amounts_for_range_liquidity(sqrt_price_x64, lower, upper, liquidity, round_up).map_err(OrcaErrorCode::from)?)
+    }
+}
+
+#[account(zero_copy)]
+#[repr(C)]
+pub struct LimitOrderBook {
+    pub ranges: [LimitOrderRange; 16],
+    pub pool: Pubkey,
+    pub bump: u8,
+    pub padding: [u8; 15],
+    /// `ACCOUNT_VERSION`; the SDK reads a legacy account as version 0
+    pub version: u8,
+    pub reserved: [u8; 63],
+}
+
+impl LimitOrderBook {
+    pub const SEED: &'static str = "limit_order_book";
+    pub const CAPACITY: usize = 16;
+    pub const SIZE: usize = 8 + LimitOrderRange::SIZE * Self::CAPACITY + 32 + 1 + 15 + 1 + 63;
+
+    pub fn find(&self, tick_lower: i32) -> Option<usize> {
+        self.ranges.iter().position(|range| range.open_orders > 0 && range.tick_lower == tick_lower)
+    }
+
+    /// Tokens the vaults hold for limit orders, fees included.
+    pub fn reserved_amounts(&self) -> Result<(u64, u64)> {
+        let (mut amount_0, mut amount_1) = (0u64, 0u64);
+        for range in self.ranges.iter().filter(|range| range.open_orders > 0) {
+            amount_0 = amount_0.checked_add(range.amount_0).and_then(|a| a.checked_add(range.fees_owed_0)).ok_or(OrcaErrorCode::MathOverflow)?;
+            amount_1 = amount_1.checked_add(range.amount_1).and_then(|a| a.checked_add(range.fees_owed_1)).ok_or(OrcaErrorCode::MathOverflow)?;
+        }
+        Ok((amount_0, amount_1))
+    }
+}
+
+const _: () = assert!(LimitOrderBook::SIZE == 8 + std::mem::size_of::<LimitOrderBook>());
+
+#[account]
+pub struct LimitOrder {
+    pub bump: u8,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub liquidity: u128,
+    pub fee_growth_entry_0_x64: u128,
+    pub fee_growth_entry_1_x64: u128,
+    /// `ACCOUNT_VERSION`; the SDK reads a legacy account as version 0
+    pub version: u8,
+    pub reserved: [u8; 63],
+}
+
+impl LimitOrder {
+    pub const SEED: &'static str = "limit_order";
+    pub const SIZE: usize = 8 + 1 + 32 + 32 + 4 + 16 + 16 + 16 + 1 + 63;
+}
+
+/// A creator allowlisted to create pools under a config with
+/// `permissioned_pool_creation`; removing it closes the account.
+#[account]
+pub struct PoolCreator {
+    pub bump: u8,
+    pub amm_config: Pubkey,
+    pub creator: Pubkey,
+    pub version: u8,
+    pub reserved: [u8; 63],
+}
+
+impl PoolCreator {
+    pub const SEED: &'static str = "pool_creator";
+    pub const SIZE: usize = 8 + 1 + 32 + 32 + 1 + 63;
+}
+
+/// A mint the config's authority has vetted for listing.
+#[account]
+pub struct TokenBadge {
+    pub bump: u8,
+    pub amm_config: Pubkey,
+    pub token_mint: Pubkey,
+    pub version: u8,
+    pub reserved: [u8; 63],
+}
+
+impl TokenBadge {
+    pub const SEED: &'static str = "token_badge";
+    pub const SIZE: usize = 8 + 1 + 32 + 32 + 1 + 63;
+}
+
+/// The accounts the program stored before they carried a `version`, as it
+/// serialized them. Only the types that existed then have one; each shares its
+/// current type's name, and so its discriminator. `migrate_*` translates them
+/// to the current layouts, and the SDK decodes them as version 0.
+pub mod legacy {
+    use anchor_lang::prelude::*;
+    use anchor_lang::Discriminator;
+
+    /// Version 1 pools spent their `reserved` space on the share-fee fields and
+    /// had none left; they are the current layout up to the position-fee fields.
+    pub const POOL_V1_SIZE: usize = 8 + std::mem::offset_of!(super::Pool, position_fee_growth_entry_0_x64);
+
+    /// Reads `data`, discriminator included, at `L`, the legacy layout of `T`.
+    pub fn read<T: Discriminator, L: AnchorDeserialize>(data: &[u8]) -> Result<L> {
+        require!(data.starts_with(T::DISCRIMINATOR), ErrorCode::AccountDiscriminatorMismatch);
+        L::deserialize(&mut &data[T::DISCRIMINATOR.len()..]).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+    pub struct AmmConfig {
+        pub bump: u8,
+        pub authority: Pubkey,
+        pub default_fee_rate: u16,
+        pub protocol_fee_rate: u16,
+        pub protocol_fee_destination: Pubkey,
+        pub create_pool_enabled: bool,
+        pub swap_enabled: bool,
+    }
+
+    impl AmmConfig {
+        pub const SIZE: usize = 8 + 1 + 32 + 2 + 2 + 32 + 1 + 1;
+
+        /// Flash loans are charged the swap fee, as `initialize_amm` sets them up.
+        pub fn upgrade(&self) -> super::AmmConfig {
+            super::AmmConfig {
+                bump: self.bump,
+                authority: self.authority,
+                default_fee_rate: self.default_fee_rate,
+                protocol_fee_rate: self.protocol_fee_rate,
+                protocol_fee_destination: self.protocol_fee_destination,
+                create_pool_enabled: self.create_pool_enabled,
+                swap_enabled: self.swap_enabled,
+                flash_fee_rate: self.default_fee_rate,
+                version: 0,
+                index: 0,
+                permissioned_pool_creation: false,
+                token_badges_required: false,
+                reserved: [0; 59],
+            }
+        }
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+    pub struct Pool {
+        pub bump: u8,
+        pub amm_config: Pubkey,
+        pub token_mint_0: Pubkey,
+        pub token_mint_1: Pubkey,
+        pub token_vault_0: Pubkey,
+        pub token_vault_1: Pubkey,
+        pub auth_bump: u8,
+        pub liquidity: u128,
+        pub sqrt_price_x64: u128,
+        pub tick_current: i32,
+        pub tick_spacing: u16,
+        pub fee_rate: u16,
+        pub protocol_fees_token_0: u64,
+        pub protocol_fees_token_1: u64,
+        pub fee_growth_global_0_x64: u128,
+        pub fee_growth_global_1_x64: u128,
+        pub fee_split_lp_bps: u16,
+        pub fee_split_protocol_bps: u16,
+        pub fee_split_impact_bps: u16,
+    }
+
+    impl Pool {
+        /// What pools were allocated. It counts a sixth key and a third `u128`
+        /// the struct never had, so 48 zero bytes follow the 264 serialized ones.
+        pub const SIZE: usize = 8 + 1 + (32 * 6) + 1 + 16 + 16 + 16 + 4 + 2 + 2 + 8 + 8 + 16 + 16 + 2 + 2 + 2;
+
+        /// Pools had no LP mint then, so `lp_mint` is left for `migrate_pool`
+        /// to fill in; none of their liquidity is backed by shares.
+        pub fn upgrade(&self) -> super::Pool {
+            super::Pool {
+                liquidity: self.liquidity,
+                sqrt_price_x64: self.sqrt_price_x64,
+                fee_growth_global_0_x64: self.fee_growth_global_0_x64,
+                fee_growth_global_1_x64: self.fee_growth_global_1_x64,
+                amm_config: self.amm_config,
+                token_mint_0: self.token_mint_0,
+                token_mint_1: self.token_mint_1,
+                token_vault_0: self.token_vault_0,
+                token_vault_1: self.token_vault_1,
+                protocol_fees_token_0: self.protocol_fees_token_0,
+                protocol_fees_token_1: self.protocol_fees_token_1,
+                tick_current: self.tick_current,
+                tick_spacing: self.tick_spacing,
+                fee_rate: self.fee_rate,
+                fee_split_lp_bps: self.fee_split_lp_bps,
+                fee_split_protocol_bps: self.fee_split_protocol_bps,
+                fee_split_impact_bps: self.fee_split_impact_bps,
+                dynamic_fee_max_rate: self.fee_rate,
+                bump: self.bump,
+                auth_bump: self.auth_bump,
+                ..bytemuck::Zeroable::zeroed()
+            }
+        }
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+    pub struct Position {
+        pub bump: u8,
+        pub pool: Pubkey,
+        pub owner: Pubkey,
+        pub liquidity: u128,
+        pub fee_growth_entry_0_x64: u128,
+        pub fee_growth_entry_1_x64: u128,
+        pub tokens_owed_0: u64,
+        pub tokens_owed_1: u64,
+    }
+
+    impl Position {
+        pub const SIZE: usize = 8 + 1 + 32 + 32 + 16 + 16 + 16 + 8 + 8;
+
+        pub fn upgrade(&self) -> super::Position {
+            super::Position {
+                bump: self.bump,
+                pool: self.pool,
+                owner: self.owner,
+                liquidity: self.liquidity,
+                fee_growth_entry_0_x64: self.fee_growth_entry_0_x64,
+                fee_growth_entry_1_x64: self.fee_growth_entry_1_x64,
+                tokens_owed_0: self.tokens_owed_0,
+                tokens_owed_1: self.tokens_owed_1,
+                version: 0,
+                reserved: [0; 63],
+            }
+        }
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+    pub struct ObservationState {
+        pub bump: u8,
+        pub pool: Pubkey,
+        pub index: u8,
+        pub cardinality: u8,
+        pub tick_cumulative: i128,
+        pub last_timestamp: i64,
+    }
+
+    impl ObservationState {
+        pub const SIZE: usize = 8 + 1 + 32 + 1 + 1 + 16 + 8;
+
+        pub fn upgrade(&self) -> super::ObservationState {
+            super::ObservationState {
+                tick_cumulative: self.tick_cumulative,
+                last_timestamp: self.last_timestamp,
+                pool: self.pool,
+                bump: self.bump,
+                index: self.index,
+                cardinality: self.cardinality,
+                ..bytemuck::Zeroable::zeroed()
+            }
+        }
+    }
+
+    /// A version 1 limit-order range, which priced the tokens it held from its
+    /// liquidity instead of storing them.
+    #[zero_copy]
+    #[repr(C)]
+    #[derive(Debug, Default, PartialEq, Eq)]
+    pub struct LimitOrderRangeV1 {
+        pub liquidity: u128,
+        pub fee_growth_0_x64: u128,
+        pub fee_growth_1_x64: u128,
+        pub fees_owed_0: u64,
+        pub fees_owed_1: u64,
+        pub tick_lower: i32,
+        pub open_orders: u32,
+        pub sells_token_0: u8,
+        pub filled: u8,
+        pub padding: [u8; 6],
+    }
+
+    #[zero_copy]
+    #[repr(C)]
+    pub struct LimitOrderBookV1 {
+        pub ranges: [LimitOrderRangeV1; 16],
+        pub pool: Pubkey,
+        pub bump: u8,
+        pub padding: [u8; 15],
+        pub version: u8,
+        pub reserved: [u8; 63],
+    }
+
+    impl LimitOrderBookV1 {
+        pub const SIZE: usize = 8 + 80 * super::LimitOrderBook::CAPACITY + 32 + 1 + 15 + 1 + 63;
+
+        /// Stores each range's tokens as the liquidity priced them at
+        /// `sqrt_price_x64`, rounded down like the orders' payouts were.
+        pub fn upgrade(&self, sqrt_price_x64: u128, tick_spacing: u16) -> Result<super::LimitOrderBook> {
+            let mut book = super::LimitOrderBook { pool: self.pool, bump: self.bump, version: self.version, ..bytemuck::Zeroable::zeroed() };
+            for (range, legacy) in book.ranges.iter_mut().zip(&self.ranges).filter(|(_, legacy)| legacy.open_orders > 0) {
+                *range = super::LimitOrderRange {
+                    liquidity: legacy.liquidity,
+                    fee_growth_0_x64: legacy.fee_growth_0_x64,
+                    fee_growth_1_x64: legacy.fee_growth_1_x64,
+                    fees_owed_0: legacy.fees_owed_0,
+                    fees_owed_1: legacy.fees_owed_1,
+                    tick_lower: legacy.tick_lower,
+                    open_orders: legacy.open_orders,
+                    sells_token_0: legacy.sells_token_0,
+                    filled: legacy.filled,
+                    ..Default::default()
+                };
+                (range.amount_0, range.amount_1) = range.amounts(sqrt_price_x64, tick_spacing, range.liquidity, false)?;
+            }
+            Ok(book)
+        }
+    }
+
+    const _: () = assert!(LimitOrderBookV1::SIZE == 8 + std::mem::size_of::<LimitOrderBookV1>());
 }
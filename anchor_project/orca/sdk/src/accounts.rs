@@ -0,0 +1,68 @@
+use anchor_lang::{require, AccountDeserialize, AnchorDeserialize, Discriminator, Result};
+use orca::state::{legacy, AmmConfig, LimitOrder, LimitOrderBook, ObservationState, Pool, PoolCreator, Position, TokenBadge};
+
+/// Decodes raw account data, discriminator included, as fetched from RPC.
+/// Zero-copy accounts are cast in place, which needs the data after the
+/// discriminator aligned for `u128`; an RPC buffer makes no such promise, so
+/// the data is first copied into one that does.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    let mut aligned = vec![0u128; (data.len() + 8).div_ceil(16)];
+    let buffer = &mut bytemuck::cast_slice_mut::<u128, u8>(&mut aligned)[8..8 + data.len()];
+    buffer.copy_from_slice(data);
+    T::try_deserialize(&mut &buffer[..])
+}
+
+/// `decode`, or for an account not yet migrated, its legacy layout `L` read
+/// into the current one by `upgrade`, at version 0.
+fn decode_or_legacy<T: AccountDeserialize + Discriminator, L: AnchorDeserialize>(data: &[u8], legacy_size: usize, upgrade: fn(&L) -> T) -> Result<T> {
+    if data.len() == legacy_size {
+        return Ok(upgrade(&legacy::read::<T, L>(data)?));
+    }
+    decode(data)
+}
+
+pub fn decode_amm_config(data: &[u8]) -> Result<AmmConfig> {
+    decode_or_legacy(data, legacy::AmmConfig::SIZE, legacy::AmmConfig::upgrade)
+}
+
+/// A legacy pool has no LP mint yet; `lp_mint` reads as the default key. A
+/// version 1 pool reads with its missing `reserved` tail zeroed.
+pub fn decode_pool(data: &[u8]) -> Result<Pool> {
+    if data.len() == legacy::POOL_V1_SIZE {
+        let mut padded = data.to_vec();
+        padded.resize(Pool::SIZE, 0);
+        return decode(&padded);
+    }
+    decode_or_legacy(data, legacy::Pool::SIZE, legacy::Pool::upgrade)
+}
+
+pub fn decode_position(data: &[u8]) -> Result<Position> {
+    decode_or_legacy(data, legacy::Position::SIZE, legacy::Position::upgrade)
+}
+
+pub fn decode_observation_state(data: &[u8]) -> Result<ObservationState> {
+    decode_or_legacy(data, legacy::ObservationState::SIZE, legacy::ObservationState::upgrade)
+}
+
+/// A version 1 book has no stored balances; its ranges read with the tokens
+/// their liquidity holds at `pool`'s price, as `migrate_pool_v1` stores them.
+pub fn decode_limit_order_book(data: &[u8], pool: &Pool) -> Result<LimitOrderBook> {
+    if data.len() == legacy::LimitOrderBookV1::SIZE {
+        require!(data.starts_with(LimitOrderBook::DISCRIMINATOR), anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch);
+        let legacy: legacy::LimitOrderBookV1 = bytemuck::pod_read_unaligned(&data[LimitOrderBook::DISCRIMINATOR.len()..]);
+        return legacy.upgrade(pool.sqrt_price_x64, pool.tick_spacing);
+    }
+    decode(data)
+}
+
+pub fn decode_limit_order(data: &[u8]) -> Result<LimitOrder> {
+    decode(data)
+}
+
+pub fn decode_pool_creator(data: &[u8]) -> Result<PoolCreator> {
+    decode(data)
+}
+
+pub fn decode_token_badge(data: &[u8]) -> Result<TokenBadge> {
+    decode(data)
+}
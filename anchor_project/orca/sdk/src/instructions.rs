@@ -0,0 +1,607 @@
+//! One builder per `#[program]` entrypoint. Arguments are the program's own
+//! `orca::instruction::*` structs, so a signature change on-chain is a compile
+//! error here; accounts the program derives are derived the same way.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use orca::{accounts, instruction};
+use orca::state::Pool;
+
+use crate::pda;
+
+/// Addresses shared by every instruction that operates on an existing pool.
+#[derive(Clone, Copy, Debug)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub amm_config: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub lp_mint: Pubkey,
+    pub pool_authority: Pubkey,
+    pub observation_state: Pubkey,
+    pub limit_order_book: Pubkey,
+}
+
+impl PoolKeys {
+    pub fn new(pool: Pubkey, state: &Pool) -> Self {
+        Self {
+            pool,
+            amm_config: state.amm_config,
+            token_mint_0: state.token_mint_0,
+            token_mint_1: state.token_mint_1,
+            token_vault_0: state.token_vault_0,
+            token_vault_1: state.token_vault_1,
+            lp_mint: state.lp_mint,
+            pool_authority: pda::pool_authority(&pool).0,
+            observation_state: pda::observation(&pool).0,
+            limit_order_book: pda::limit_order_book(&pool).0,
+        }
+    }
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: orca::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Signed by the program's upgrade authority, which pays for the config;
+/// `authority` is the key that will administer it.
+pub fn initialize_amm(
+    upgrade_authority: Pubkey,
+    authority: Pubkey,
+    protocol_fee_destination: Pubkey,
+    args: instruction::InitializeAmm,
+) -> Instruction {
+    build(
+        accounts::InitializeAmm {
+            amm_config: pda::amm_config(args.index).0,
+            upgrade_authority,
+            program_data: pda::program_data().0,
+            authority,
+            protocol_fee_destination,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        args,
+    )
+}
+
+/// Creates the pool under `amm_config`. The vaults are fresh keypairs that
+/// must also sign the transaction.
+pub fn create_pool(
+    creator: Pubkey,
+    amm_config: Pubkey,
+    token_mint_0: Pubkey,
+    token_mint_1: Pubkey,
+    token_vault_0: Pubkey,
+    token_vault_1: Pubkey,
+    args: instruction::CreatePool,
+) -> Instruction {
+    let pool = pda::pool(&amm_config, &token_mint_0, &token_mint_1).0;
+    build(
+        accounts::CreatePool {
+            amm_config,
+            pool,
+            token_mint_0,
+            token_mint_1,
+            token_vault_0,
+            token_vault_1,
+            pool_authority: pda::pool_authority(&pool).0,
+            lp_mint: pda::lp_mint(&pool).0,
+            observation_state: pda::observation(&pool).0,
+            limit_order_book: pda::limit_order_book(&pool).0,
+            creator,
+            pool_creator: pda::pool_creator(&amm_config, &creator).0,
+            token_badge_0: pda::token_badge(&amm_config, &token_mint_0).0,
+            token_badge_1: pda::token_badge(&amm_config, &token_mint_1).0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        args,
+    )
+}
+
+/// Token accounts a liquidity provider deposits from and withdraws to.
+#[derive(Clone, Copy, Debug)]
+pub struct UserTokenAccounts {
+    pub token_account_0: Pubkey,
+    pub token_account_1: Pubkey,
+}
+
+/// `create_pool` that also opens `creator`'s position with the first deposit,
+/// funded from `creator_accounts`. The vaults must sign as in `create_pool`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_pool_with_liquidity(
+    creator: Pubkey,
+    amm_config: Pubkey,
+    token_mint_0: Pubkey,
+    token_mint_1: Pubkey,
+    token_vault_0: Pubkey,
+    token_vault_1: Pubkey,
+    creator_accounts: UserTokenAccounts,
+    args: instruction::CreatePoolWithLiquidity,
+) -> Instruction {
+    let pool = pda::pool(&amm_config, &token_mint_0, &token_mint_1).0;
+    build(
+        accounts::CreatePoolWithLiquidity {
+            amm_config,
+            pool,
+            token_mint_0,
+            token_mint_1,
+            token_vault_0,
+            token_vault_1,
+            pool_authority: pda::pool_authority(&pool).0,
+            lp_mint: pda::lp_mint(&pool).0,
+            observation_state: pda::observation(&pool).0,
+            limit_order_book: pda::limit_order_book(&pool).0,
+            position: pda::position(&pool, &creator).0,
+            creator_token_account_0: creator_accounts.token_account_0,
+            creator_token_account_1: creator_accounts.token_account_1,
+            creator,
+            pool_creator: pda::pool_creator(&amm_config, &creator).0,
+            token_badge_0: pda::token_badge(&amm_config, &token_mint_0).0,
+            token_badge_1: pda::token_badge(&amm_config, &token_mint_1).0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        args,
+    )
+}
+
+fn modify_liquidity_accounts(
+    keys: &PoolKeys,
+    user: Pubkey,
+    user_accounts: UserTokenAccounts,
+    user_lp_token_account: Pubkey,
+) -> accounts::ModifyLiquidity {
+    accounts::ModifyLiquidity {
+        pool: keys.pool,
+        token_vault_0: keys.token_vault_0,
+        token_vault_1: keys.token_vault_1,
+        user_token_account_0: user_accounts.token_account_0,
+        user_token_account_1: user_accounts.token_account_1,
+        lp_mint: keys.lp_mint,
+        user_lp_token_account,
+        pool_authority: keys.pool_authority,
+        limit_order_book: keys.limit_order_book,
+        user,
+        token_program: anchor_spl::token::ID,
+        event_authority: pda::event_authority().0,
+        program: orca::ID,
+    }
+}
+
+pub fn add_liquidity(
+    keys: &PoolKeys,
+    user: Pubkey,
+    user_accounts: UserTokenAccounts,
+    user_lp_token_account: Pubkey,
+    args: instruction::AddLiquidity,
+) -> Instruction {
+    build(modify_liquidity_accounts(keys, user, user_accounts, user_lp_token_account), args)
+}
+
+pub fn remove_liquidity(
+    keys: &PoolKeys,
+    user: Pubkey,
+    user_accounts: UserTokenAccounts,
+    user_lp_token_account: Pubkey,
+    args: instruction::RemoveLiquidity,
+) -> Instruction {
+    build(modify_liquidity_accounts(keys, user, user_accounts, user_lp_token_account), args)
+}
+
+/// Picks the input and output vaults from `args.zero_for_one`; the user
+/// accounts are given in swap order.
+pub fn swap(
+    keys: &PoolKeys,
+    user: Pubkey,
+    user_token_account_in: Pubkey,
+    user_token_account_out: Pubkey,
+    args: instruction::Swap,
+) -> Instruction {
+    let (token_vault_in, token_vault_out) = if args.zero_for_one {
+        (keys.token_vault_0, keys.token_vault_1)
+    } else {
+        (keys.token_vault_1, keys.token_vault_0)
+    };
+    build(
+        accounts::Swap {
+            pool: keys.pool,
+            amm_config: keys.amm_config,
+            user_token_account_in,
+            user_token_account_out,
+            token_vault_in,
+            token_vault_out,
+            pool_authority: keys.pool_authority,
+            observation_state: keys.observation_state,
+            limit_order_book: keys.limit_order_book,
+            user,
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        args,
+    )
+}
+
+/// Read-only; simulate it and decode `orca::instructions::swap::SwapQuote`
+/// from the transaction's return data.
+pub fn quote_swap(keys: &PoolKeys, args: instruction::QuoteSwap) -> Instruction {
+    build(accounts::QuoteSwap { pool: keys.pool, amm_config: keys.amm_config, limit_order_book: keys.limit_order_book }, args)
+}
+
+pub fn open_position(pool: Pubkey, owner: Pubkey) -> Instruction {
+    build(
+        accounts::OpenPosition {
+            pool,
+            owner,
+            position: pda::position(&pool, &owner).0,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::OpenPosition {},
+    )
+}
+
+fn modify_position_accounts(
+    keys: &PoolKeys,
+    owner: Pubkey,
+    user_accounts: UserTokenAccounts,
+) -> accounts::ModifyPositionLiquidity {
+    accounts::ModifyPositionLiquidity {
+        pool: keys.pool,
+        position: pda::position(&keys.pool, &owner).0,
+        owner,
+        token_vault_0: keys.token_vault_0,
+        token_vault_1: keys.token_vault_1,
+        user_token_account_0: user_accounts.token_account_0,
+        user_token_account_1: user_accounts.token_account_1,
+        pool_authority: keys.pool_authority,
+        limit_order_book: keys.limit_order_book,
+        token_program: anchor_spl::token::ID,
+        event_authority: pda::event_authority().0,
+        program: orca::ID,
+    }
+}
+
+pub fn increase_position_liquidity(
+    keys: &PoolKeys,
+    owner: Pubkey,
+    user_accounts: UserTokenAccounts,
+    args: instruction::IncreasePositionLiquidity,
+) -> Instruction {
+    build(modify_position_accounts(keys, owner, user_accounts), args)
+}
+
+pub fn decrease_position_liquidity(
+    keys: &PoolKeys,
+    owner: Pubkey,
+    user_accounts: UserTokenAccounts,
+    args: instruction::DecreasePositionLiquidity,
+) -> Instruction {
+    build(modify_position_accounts(keys, owner, user_accounts), args)
+}
+
+pub fn collect_fees(keys: &PoolKeys, owner: Pubkey, user_accounts: UserTokenAccounts) -> Instruction {
+    build(modify_position_accounts(keys, owner, user_accounts), instruction::CollectFees {})
+}
+
+fn flash_loan_accounts(keys: &PoolKeys, user: Pubkey, user_accounts: UserTokenAccounts) -> accounts::FlashLoan {
+    accounts::FlashLoan {
+        pool: keys.pool,
+        amm_config: keys.amm_config,
+        token_vault_0: keys.token_vault_0,
+        token_vault_1: keys.token_vault_1,
+        user_token_account_0: user_accounts.token_account_0,
+        user_token_account_1: user_accounts.token_account_1,
+        pool_authority: keys.pool_authority,
+        user,
+        token_program: anchor_spl::token::ID,
+        instructions: sysvar::instructions::ID,
+        event_authority: pda::event_authority().0,
+        program: orca::ID,
+    }
+}
+
+/// Must be followed later in the same transaction by `flash_repay` on the same pool.
+pub fn flash_borrow(
+    keys: &PoolKeys,
+    user: Pubkey,
+    user_accounts: UserTokenAccounts,
+    args: instruction::FlashBorrow,
+) -> Instruction {
+    build(flash_loan_accounts(keys, user, user_accounts), args)
+}
+
+/// Repays the outstanding loan plus the flash fee from `user_accounts`.
+pub fn flash_repay(keys: &PoolKeys, user: Pubkey, user_accounts: UserTokenAccounts) -> Instruction {
+    build(flash_loan_accounts(keys, user, user_accounts), instruction::FlashRepay {})
+}
+
+pub fn set_flash_fee_rate(amm_config: Pubkey, authority: Pubkey, args: instruction::SetFlashFeeRate) -> Instruction {
+    build(
+        accounts::UpdateAmmConfig {
+            amm_config,
+            authority,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        args,
+    )
+}
+
+pub fn set_pool_creation_policy(amm_config: Pubkey, authority: Pubkey, args: instruction::SetPoolCreationPolicy) -> Instruction {
+    build(
+        accounts::UpdateAmmConfig {
+            amm_config,
+            authority,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        args,
+    )
+}
+
+fn pool_admin_accounts(keys: &PoolKeys, authority: Pubkey) -> accounts::PoolAdmin {
+    accounts::PoolAdmin {
+        pool: keys.pool,
+        amm_config: keys.amm_config,
+        authority,
+        event_authority: pda::event_authority().0,
+        program: orca::ID,
+    }
+}
+
+/// `status` is a combination of `orca::constants::POOL_STATUS_*` bits.
+pub fn set_pool_status(keys: &PoolKeys, authority: Pubkey, args: instruction::SetPoolStatus) -> Instruction {
+    build(pool_admin_accounts(keys, authority), args)
+}
+
+pub fn set_dynamic_fee(keys: &PoolKeys, authority: Pubkey, args: instruction::SetDynamicFee) -> Instruction {
+    build(pool_admin_accounts(keys, authority), args)
+}
+
+pub fn set_limit_order_min_liquidity(keys: &PoolKeys, authority: Pubkey, args: instruction::SetLimitOrderMinLiquidity) -> Instruction {
+    build(pool_admin_accounts(keys, authority), args)
+}
+
+pub fn enter_emergency_mode(keys: &PoolKeys, authority: Pubkey) -> Instruction {
+    build(pool_admin_accounts(keys, authority), instruction::EnterEmergencyMode {})
+}
+
+pub fn schedule_emergency_exit(keys: &PoolKeys, authority: Pubkey) -> Instruction {
+    build(pool_admin_accounts(keys, authority), instruction::ScheduleEmergencyExit {})
+}
+
+/// Only succeeds once `orca::constants::EMERGENCY_EXIT_TIMELOCK` has passed since the exit was scheduled.
+pub fn exit_emergency_mode(keys: &PoolKeys, authority: Pubkey) -> Instruction {
+    build(pool_admin_accounts(keys, authority), instruction::ExitEmergencyMode {})
+}
+
+pub fn emergency_remove_liquidity(
+    keys: &PoolKeys,
+    user: Pubkey,
+    user_accounts: UserTokenAccounts,
+    user_lp_token_account: Pubkey,
+    args: instruction::EmergencyRemoveLiquidity,
+) -> Instruction {
+    build(modify_liquidity_accounts(keys, user, user_accounts, user_lp_token_account), args)
+}
+
+pub fn emergency_withdraw_position(keys: &PoolKeys, owner: Pubkey, user_accounts: UserTokenAccounts) -> Instruction {
+    build(modify_position_accounts(keys, owner, user_accounts), instruction::EmergencyWithdrawPosition {})
+}
+
+/// `args.tick_lower` must be a multiple of the pool's tick spacing, on
+/// either side of the price but not around it.
+pub fn place_limit_order(
+    keys: &PoolKeys,
+    owner: Pubkey,
+    user_accounts: UserTokenAccounts,
+    args: instruction::PlaceLimitOrder,
+) -> Instruction {
+    build(
+        accounts::PlaceLimitOrder {
+            pool: keys.pool,
+            limit_order_book: keys.limit_order_book,
+            limit_order: pda::limit_order(&keys.pool, &owner, args.tick_lower).0,
+            owner,
+            token_vault_0: keys.token_vault_0,
+            token_vault_1: keys.token_vault_1,
+            user_token_account_0: user_accounts.token_account_0,
+            user_token_account_1: user_accounts.token_account_1,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        args,
+    )
+}
+
+fn close_limit_order_accounts(
+    keys: &PoolKeys,
+    authority: Pubkey,
+    owner: Pubkey,
+    owner_accounts: UserTokenAccounts,
+    tick_lower: i32,
+) -> accounts::CloseLimitOrder {
+    accounts::CloseLimitOrder {
+        pool: keys.pool,
+        limit_order_book: keys.limit_order_book,
+        limit_order: pda::limit_order(&keys.pool, &owner, tick_lower).0,
+        owner,
+        authority,
+        token_vault_0: keys.token_vault_0,
+        token_vault_1: keys.token_vault_1,
+        owner_token_account_0: owner_accounts.token_account_0,
+        owner_token_account_1: owner_accounts.token_account_1,
+        pool_authority: keys.pool_authority,
+        token_program: anchor_spl::token::ID,
+        event_authority: pda::event_authority().0,
+        program: orca::ID,
+    }
+}
+
+pub fn cancel_limit_order(keys: &PoolKeys, owner: Pubkey, owner_accounts: UserTokenAccounts, tick_lower: i32) -> Instruction {
+    build(close_limit_order_accounts(keys, owner, owner, owner_accounts, tick_lower), instruction::CancelLimitOrder {})
+}
+
+/// Any `authority` may claim a filled order; the proceeds go to `owner_accounts`.
+pub fn claim_limit_order(
+    keys: &PoolKeys,
+    authority: Pubkey,
+    owner: Pubkey,
+    owner_accounts: UserTokenAccounts,
+    tick_lower: i32,
+) -> Instruction {
+    build(close_limit_order_accounts(keys, authority, owner, owner_accounts, tick_lower), instruction::ClaimLimitOrder {})
+}
+
+/// Run first: every other `migrate_*` loads `amm_config` at its current layout.
+pub fn migrate_amm_config(amm_config: Pubkey, authority: Pubkey) -> Instruction {
+    build(
+        accounts::MigrateAmmConfig {
+            amm_config,
+            authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::MigrateAmmConfig {},
+    )
+}
+
+/// Also creates the pool's LP mint and limit order book, at `authority`'s expense.
+pub fn migrate_pool(pool: Pubkey, amm_config: Pubkey, token_vault_0: Pubkey, token_vault_1: Pubkey, authority: Pubkey) -> Instruction {
+    build(
+        accounts::MigratePool {
+            pool,
+            amm_config,
+            pool_authority: pda::pool_authority(&pool).0,
+            lp_mint: pda::lp_mint(&pool).0,
+            limit_order_book: pda::limit_order_book(&pool).0,
+            token_vault_0,
+            token_vault_1,
+            authority,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::MigratePool {},
+    )
+}
+
+fn migrate_accounts(account: Pubkey, pool: Pubkey, amm_config: Pubkey, authority: Pubkey) -> accounts::MigrateAccount {
+    accounts::MigrateAccount {
+        account,
+        pool,
+        amm_config,
+        authority,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority().0,
+        program: orca::ID,
+    }
+}
+
+/// For a pool at version 1; its limit order book is migrated with it.
+pub fn migrate_pool_v1(pool: Pubkey, amm_config: Pubkey, token_vault_0: Pubkey, token_vault_1: Pubkey, authority: Pubkey) -> Instruction {
+    build(
+        accounts::MigratePoolV1 {
+            pool,
+            amm_config,
+            limit_order_book: pda::limit_order_book(&pool).0,
+            token_vault_0,
+            token_vault_1,
+            authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::MigratePoolV1 {},
+    )
+}
+
+pub fn migrate_position(position: Pubkey, pool: Pubkey, amm_config: Pubkey, authority: Pubkey) -> Instruction {
+    build(migrate_accounts(position, pool, amm_config, authority), instruction::MigratePosition {})
+}
+
+pub fn migrate_observation_state(observation_state: Pubkey, pool: Pubkey, amm_config: Pubkey, authority: Pubkey) -> Instruction {
+    build(migrate_accounts(observation_state, pool, amm_config, authority), instruction::MigrateObservationState {})
+}
+
+pub fn add_pool_creator(amm_config: Pubkey, authority: Pubkey, creator: Pubkey) -> Instruction {
+    build(
+        accounts::AddPoolCreator {
+            amm_config,
+            pool_creator: pda::pool_creator(&amm_config, &creator).0,
+            creator,
+            authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::AddPoolCreator {},
+    )
+}
+
+/// Closes the `PoolCreator` account, refunding its rent to `authority`.
+pub fn remove_pool_creator(amm_config: Pubkey, authority: Pubkey, creator: Pubkey) -> Instruction {
+    build(
+        accounts::RemovePoolCreator {
+            amm_config,
+            pool_creator: pda::pool_creator(&amm_config, &creator).0,
+            authority,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::RemovePoolCreator {},
+    )
+}
+
+pub fn add_token_badge(amm_config: Pubkey, authority: Pubkey, token_mint: Pubkey) -> Instruction {
+    build(
+        accounts::AddTokenBadge {
+            amm_config,
+            token_badge: pda::token_badge(&amm_config, &token_mint).0,
+            token_mint,
+            authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::AddTokenBadge {},
+    )
+}
+
+/// Closes the `TokenBadge` account, refunding its rent to `authority`.
+pub fn remove_token_badge(amm_config: Pubkey, authority: Pubkey, token_mint: Pubkey) -> Instruction {
+    build(
+        accounts::RemoveTokenBadge {
+            amm_config,
+            token_badge: pda::token_badge(&amm_config, &token_mint).0,
+            authority,
+            event_authority: pda::event_authority().0,
+            program: orca::ID,
+        },
+        instruction::RemoveTokenBadge {},
+    )
+}
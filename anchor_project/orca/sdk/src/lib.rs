@@ -0,0 +1,9 @@
+//! Off-chain client for the orca program: typed instruction builders, PDA
+//! helpers that share the program's seed constants, and account decoders.
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+
+pub use orca::ID as PROGRAM_ID;
+pub use orca::state::{AmmConfig, LimitOrder, LimitOrderBook, LimitOrderRange, ObservationState, Pool, PoolCreator, Position, TokenBadge};
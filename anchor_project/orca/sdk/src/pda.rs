@@ -0,0 +1,64 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::prelude::ProgramData;
+use anchor_lang::Owner;
+use orca::constants::*;
+use orca::state::{LimitOrder, Position};
+
+pub fn amm_config(index: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AMM_CONFIG_SEED.as_bytes(), &index.to_le_bytes()], &orca::ID)
+}
+
+/// The single config made before configs were indexed. Once migrated it is
+/// config 0 of those deployments, and its pools stay bound to this address.
+pub fn legacy_amm_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AMM_CONFIG_SEED.as_bytes()], &orca::ID)
+}
+
+pub fn pool(amm_config: &Pubkey, token_mint_0: &Pubkey, token_mint_1: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POOL_SEED.as_bytes(), amm_config.as_ref(), token_mint_0.as_ref(), token_mint_1.as_ref()],
+        &orca::ID,
+    )
+}
+
+pub fn pool_authority(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POOL_AUTHORITY_SEED.as_bytes(), pool.as_ref()], &orca::ID)
+}
+
+pub fn lp_mint(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LP_MINT_SEED.as_bytes(), pool.as_ref()], &orca::ID)
+}
+
+pub fn observation(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[OBSERVATION_SEED.as_bytes(), pool.as_ref()], &orca::ID)
+}
+
+pub fn position(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[Position::SEED.as_bytes(), pool.as_ref(), owner.as_ref()], &orca::ID)
+}
+
+pub fn limit_order_book(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LIMIT_ORDER_BOOK_SEED.as_bytes(), pool.as_ref()], &orca::ID)
+}
+
+pub fn limit_order(pool: &Pubkey, owner: &Pubkey, tick_lower: i32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LimitOrder::SEED.as_bytes(), pool.as_ref(), owner.as_ref(), &tick_lower.to_le_bytes()], &orca::ID)
+}
+
+pub fn pool_creator(amm_config: &Pubkey, creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POOL_CREATOR_SEED.as_bytes(), amm_config.as_ref(), creator.as_ref()], &orca::ID)
+}
+
+pub fn token_badge(amm_config: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TOKEN_BADGE_SEED.as_bytes(), amm_config.as_ref(), token_mint.as_ref()], &orca::ID)
+}
+
+/// Signer the program uses for `emit_cpi!` self-invocations.
+pub fn event_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], &orca::ID)
+}
+
+/// The program's `ProgramData`, which records its upgrade authority.
+pub fn program_data() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[orca::ID.as_ref()], &ProgramData::owner())
+}
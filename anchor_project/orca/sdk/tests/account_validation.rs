@@ -0,0 +1,127 @@
+//! Instructions fed accounts that are not the pool's own, or signed by the
+//! wrong party, must fail before moving any tokens.
+
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::instruction::AccountMeta;
+use common::*;
+use orca::errors::ErrorCode;
+use orca::instruction;
+use orca_sdk::instructions::{self as ix, UserTokenAccounts};
+
+fn funded_pool() -> (PoolFixture, User) {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&provider, 1_000_000, 1_000_000).unwrap();
+    (fixture, provider)
+}
+
+#[test]
+fn add_liquidity_rejects_a_vault_not_owned_by_the_pool() {
+    let (mut fixture, _) = funded_pool();
+    let attacker = fixture.user(1_000_000, 1_000_000);
+    // An account of the right mint, but controlled by the attacker instead of the pool
+    let mut keys = fixture.keys;
+    keys.token_vault_0 = fixture.svm.create_token_account(&keys.token_mint_0, &attacker.key, 0);
+    let args = instruction::AddLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 };
+    let add = ix::add_liquidity(&keys, attacker.key, attacker.tokens, attacker.lp_token_account, args);
+    assert_eq!(fixture.svm.process(&[add], &[attacker.key]).unwrap_err(), orca_error(ErrorCode::InvalidTokenAccount));
+}
+
+#[test]
+fn add_liquidity_rejects_a_user_account_with_the_wrong_mint() {
+    let (mut fixture, _) = funded_pool();
+    let user = fixture.user(1_000_000, 1_000_000);
+    let tokens = UserTokenAccounts { token_account_0: user.tokens.token_account_1, token_account_1: user.tokens.token_account_0 };
+    let args = instruction::AddLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 };
+    let add = ix::add_liquidity(&fixture.keys, user.key, tokens, user.lp_token_account, args);
+    assert_eq!(fixture.svm.process(&[add], &[user.key]).unwrap_err(), orca_error(ErrorCode::InvalidMint));
+}
+
+#[test]
+fn add_liquidity_rejects_an_lp_mint_other_than_the_pools() {
+    let (mut fixture, _) = funded_pool();
+    let attacker = fixture.user(1_000_000, 1_000_000);
+    let mut keys = fixture.keys;
+    keys.lp_mint = fixture.svm.create_mint(&attacker.key, 9);
+    let lp_token_account = fixture.svm.create_token_account(&keys.lp_mint, &attacker.key, 0);
+    let args = instruction::AddLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 };
+    let add = ix::add_liquidity(&keys, attacker.key, attacker.tokens, lp_token_account, args);
+    assert_eq!(fixture.svm.process(&[add], &[attacker.key]).unwrap_err(), orca_error(ErrorCode::InvalidMint));
+}
+
+#[test]
+fn remove_liquidity_cannot_burn_someone_elses_shares() {
+    let (mut fixture, provider) = funded_pool();
+    let attacker = fixture.user(0, 0);
+    let shares = fixture.svm.token_balance(&provider.lp_token_account);
+    // The attacker signs, but points the burn at the provider's share account
+    let args = instruction::RemoveLiquidity { lp_amount: shares };
+    let remove = ix::remove_liquidity(&fixture.keys, attacker.key, attacker.tokens, provider.lp_token_account, args);
+    assert!(fixture.svm.process(&[remove], &[attacker.key]).is_err());
+    assert_eq!(fixture.svm.token_balance(&provider.lp_token_account), shares);
+}
+
+#[test]
+fn unsigned_instructions_are_rejected() {
+    let (mut fixture, provider) = funded_pool();
+    let args = instruction::RemoveLiquidity { lp_amount: 1 };
+    let mut remove = ix::remove_liquidity(&fixture.keys, provider.key, provider.tokens, provider.lp_token_account, args);
+    let user = remove.accounts.iter_mut().find(|meta| meta.pubkey == provider.key).unwrap();
+    *user = AccountMeta::new_readonly(provider.key, false);
+    assert_eq!(fixture.svm.process(&[remove], &[]).unwrap_err(), orca_error_anchor(anchor_lang::error::ErrorCode::AccountNotSigner));
+}
+
+#[test]
+fn swap_rejects_vaults_reversed_for_the_direction() {
+    let (mut fixture, _) = funded_pool();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    let mut keys = fixture.keys;
+    std::mem::swap(&mut keys.token_vault_0, &mut keys.token_vault_1);
+    // User accounts follow the reversed vaults so only the vault addresses are wrong
+    let args = instruction::Swap { amount_in: 1_000, minimum_amount_out: 0, zero_for_one: true, sqrt_price_limit_x64: orca::constants::MIN_SQRT_PRICE };
+    let swap = ix::swap(&keys, trader.key, trader.tokens.token_account_1, trader.tokens.token_account_0, args);
+    assert_eq!(fixture.svm.process(&[swap], &[trader.key]).unwrap_err(), orca_error(ErrorCode::InvalidTokenAccount));
+}
+
+#[test]
+fn swap_rejects_an_observation_account_from_another_pool() {
+    let (mut fixture, _) = funded_pool();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    let mut keys = fixture.keys;
+    keys.observation_state = Pubkey::new_unique();
+    let args = instruction::Swap { amount_in: 1_000, minimum_amount_out: 0, zero_for_one: true, sqrt_price_limit_x64: orca::constants::MIN_SQRT_PRICE };
+    let swap = ix::swap(&keys, trader.key, trader.tokens.token_account_0, trader.tokens.token_account_1, args);
+    assert!(fixture.svm.process(&[swap], &[trader.key]).is_err());
+}
+
+#[test]
+fn position_liquidity_cannot_be_withdrawn_by_a_non_owner() {
+    let (mut fixture, _) = funded_pool();
+    let owner = fixture.user(1_000_000, 1_000_000);
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, owner.key)], &[owner.key]).unwrap();
+    let increase = ix::increase_position_liquidity(&fixture.keys, owner.key, owner.tokens, instruction::IncreasePositionLiquidity { amount_token_0: 10_000, amount_token_1: 10_000 });
+    fixture.svm.process(&[increase], &[owner.key]).unwrap();
+    let attacker = fixture.user(0, 0);
+    let mut decrease = ix::decrease_position_liquidity(&fixture.keys, attacker.key, attacker.tokens, instruction::DecreasePositionLiquidity { amount_token_0: 10_000, amount_token_1: 10_000 });
+    // Point at the owner's position while signing as the attacker
+    decrease.accounts[1] = AccountMeta::new(orca_sdk::pda::position(&fixture.keys.pool, &owner.key).0, false);
+    assert_eq!(fixture.svm.process(&[decrease], &[attacker.key]).unwrap_err(), orca_error_anchor(anchor_lang::error::ErrorCode::ConstraintHasOne));
+}
+
+#[test]
+fn failed_transactions_leave_no_changes() {
+    let (mut fixture, provider) = funded_pool();
+    let before = fixture.pool();
+    let balance_before = fixture.svm.token_balance(&provider.tokens.token_account_0);
+    let args = instruction::Swap { amount_in: 1_000, minimum_amount_out: u64::MAX, zero_for_one: true, sqrt_price_limit_x64: orca::constants::MIN_SQRT_PRICE };
+    let swap = ix::swap(&fixture.keys, provider.key, provider.tokens.token_account_0, provider.tokens.token_account_1, args);
+    assert_eq!(fixture.svm.process(&[swap], &[provider.key]).unwrap_err(), orca_error(ErrorCode::AmountOutTooLow));
+    assert_eq!(fixture.pool().sqrt_price_x64, before.sqrt_price_x64);
+    assert_eq!(fixture.svm.token_balance(&provider.tokens.token_account_0), balance_before);
+}
+
+fn orca_error_anchor(code: anchor_lang::error::ErrorCode) -> ProgramError {
+    anchor_lang::error::Error::from(code).into()
+}
@@ -0,0 +1,141 @@
+//! Configs are derived from a `u16` index, each its own venue with its own
+//! authority and fee defaults. A pool is created under one config and stays
+//! bound to it.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use common::*;
+use orca::errors::ErrorCode;
+use orca::events::{ConfigUpdated, PoolCreated};
+use orca::instruction;
+use orca_sdk::instructions::{self as ix, PoolKeys};
+use orca_sdk::{pda, AmmConfig, Pool};
+
+const SECOND_FEE_RATE: u16 = 500;
+
+/// The fixture's config 0 plus config 1, run by another authority at another fee rate.
+fn second_config(fixture: &mut PoolFixture) -> Pubkey {
+    let authority = Pubkey::new_unique();
+    let args = instruction::InitializeAmm { index: 1, fee_rate: SECOND_FEE_RATE, protocol_fee_rate: 0 };
+    let outcome = fixture.svm.process(&[ix::initialize_amm(fixture.admin, authority, authority, args)], &[fixture.admin]).unwrap();
+    let event = outcome.events::<ConfigUpdated>().pop().unwrap();
+    assert_eq!((event.amm_config, event.index), (pda::amm_config(1).0, 1));
+    authority
+}
+
+#[test]
+fn each_index_is_its_own_config() {
+    let mut fixture = PoolFixture::new();
+    let authority = second_config(&mut fixture);
+    let (first, second) = (fixture.svm.account::<AmmConfig>(&pda::amm_config(0).0), fixture.svm.account::<AmmConfig>(&pda::amm_config(1).0));
+    assert_eq!((first.index, first.authority, first.default_fee_rate), (0, fixture.admin, FEE_RATE));
+    assert_eq!((second.index, second.authority, second.default_fee_rate), (1, authority, SECOND_FEE_RATE));
+    // An index is taken once
+    let again = ix::initialize_amm(fixture.admin, fixture.admin, fixture.admin, instruction::InitializeAmm { index: 1, fee_rate: FEE_RATE, protocol_fee_rate: 0 });
+    assert!(fixture.svm.process(&[again], &[fixture.admin]).is_err());
+    // Each config's authority only governs that config
+    let set = ix::set_flash_fee_rate(pda::amm_config(0).0, authority, instruction::SetFlashFeeRate { flash_fee_rate: 1 });
+    assert_eq!(fixture.svm.process(&[set], &[authority]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+}
+
+#[test]
+fn the_same_pair_gets_a_pool_per_config_with_that_configs_fee() {
+    let mut fixture = PoolFixture::new();
+    let authority = second_config(&mut fixture);
+    let config = pda::amm_config(1).0;
+    let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let args = instruction::CreatePool { sqrt_price_x64: INITIAL_SQRT_PRICE_X64, tick_spacing: 64 };
+    let create = ix::create_pool(fixture.admin, config, fixture.keys.token_mint_0, fixture.keys.token_mint_1, vault_0, vault_1, args);
+    let created = fixture.svm.process(&[create], &[fixture.admin, vault_0, vault_1]).unwrap().events::<PoolCreated>().pop().unwrap();
+    let pool = pda::pool(&config, &fixture.keys.token_mint_0, &fixture.keys.token_mint_1).0;
+    assert_ne!(pool, fixture.keys.pool);
+    assert_eq!((created.pool, created.amm_config, created.fee_rate), (pool, config, SECOND_FEE_RATE));
+    let state = fixture.svm.account::<Pool>(&pool);
+    assert_eq!((state.amm_config, state.fee_rate), (config, SECOND_FEE_RATE));
+    assert_eq!(fixture.pool().fee_rate, FEE_RATE);
+
+    // Config 1's authority runs its pool, but not config 0's
+    let second = PoolKeys::new(pool, &state);
+    let pause = |keys: &PoolKeys| ix::set_pool_status(keys, authority, instruction::SetPoolStatus { status: orca::constants::POOL_STATUS_SWAP_DISABLED });
+    fixture.svm.process(&[pause(&second)], &[authority]).unwrap();
+    let mut borrowed = fixture.keys;
+    borrowed.amm_config = config;
+    assert_eq!(fixture.svm.process(&[pause(&borrowed)], &[authority]).unwrap_err(), orca_error(ErrorCode::InvalidPoolConfig));
+}
+
+#[test]
+fn a_pool_swaps_only_against_its_own_config() {
+    let mut fixture = PoolFixture::new();
+    second_config(&mut fixture);
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&provider, 1_000_000, 1_000_000).unwrap();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    let own = fixture.keys;
+    // Config 1 charges less, but a pool under config 0 cannot be traded through it
+    fixture.keys.amm_config = pda::amm_config(1).0;
+    assert_eq!(fixture.swap(&trader, 10_000, true).unwrap_err(), orca_error(ErrorCode::InvalidPoolConfig));
+    fixture.keys = own;
+    fixture.swap(&trader, 10_000, true).unwrap();
+}
+
+#[test]
+fn a_config_with_swaps_off_neither_swaps_nor_quotes() {
+    let mut fixture = PoolFixture::new();
+    second_config(&mut fixture);
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&provider, 1_000_000, 1_000_000).unwrap();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    let quote_args = || instruction::QuoteSwap { amount: 10_000, other_amount_threshold: 0, zero_for_one: true, sqrt_price_limit_x64: orca::constants::MIN_SQRT_PRICE, exact_input: true };
+    fixture.svm.process(&[ix::quote_swap(&fixture.keys, quote_args())], &[]).unwrap();
+    // A quote is priced against the pool's own config too
+    let mut borrowed = fixture.keys;
+    borrowed.amm_config = pda::amm_config(1).0;
+    assert_eq!(fixture.svm.process(&[ix::quote_swap(&borrowed, quote_args())], &[]).unwrap_err(), orca_error(ErrorCode::InvalidPoolConfig));
+
+    let config_key = pda::amm_config(0).0;
+    let mut config = fixture.svm.account::<AmmConfig>(&config_key);
+    config.swap_enabled = false;
+    let mut data = Vec::new();
+    anchor_lang::AccountSerialize::try_serialize(&config, &mut data).unwrap();
+    data.resize(fixture.svm.data(&config_key).len(), 0);
+    fixture.svm.set_data(&config_key, data);
+    let disabled = orca_error(ErrorCode::SwapDisabled);
+    assert_eq!(fixture.swap(&trader, 10_000, true).unwrap_err(), disabled);
+    assert_eq!(fixture.svm.process(&[ix::quote_swap(&fixture.keys, quote_args())], &[]).unwrap_err(), disabled);
+}
+
+#[test]
+fn the_config_from_before_indexing_keeps_running_its_pools() {
+    let mut fixture = PoolFixture::with_legacy_config();
+    let legacy = pda::legacy_amm_config().0;
+    assert_eq!(fixture.keys.amm_config, legacy);
+    let config = fixture.svm.account::<AmmConfig>(&legacy);
+    assert_eq!((config.index, config.version, config.authority, config.default_fee_rate), (0, orca::constants::ACCOUNT_VERSION, fixture.admin, FEE_RATE));
+    // Its pools take liquidity and trade through the legacy address
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&provider, 1_000_000, 1_000_000).unwrap();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    fixture.swap(&trader, 10_000, true).unwrap();
+
+    // Config 0 at its indexed address is another config, which the pool is not bound to
+    let args = instruction::InitializeAmm { index: 0, fee_rate: SECOND_FEE_RATE, protocol_fee_rate: 0 };
+    fixture.svm.process(&[ix::initialize_amm(fixture.admin, fixture.admin, fixture.admin, args)], &[fixture.admin]).unwrap();
+    let own = fixture.keys;
+    fixture.keys.amm_config = pda::amm_config(0).0;
+    assert_eq!(fixture.swap(&trader, 10_000, true).unwrap_err(), orca_error(ErrorCode::InvalidPoolConfig));
+    fixture.keys = own;
+
+    // The legacy address's authority administers its pools
+    let pause = ix::set_pool_status(&fixture.keys, fixture.admin, instruction::SetPoolStatus { status: orca::constants::POOL_STATUS_SWAP_DISABLED });
+    fixture.svm.process(std::slice::from_ref(&pause), &[fixture.admin]).unwrap();
+    assert_eq!(fixture.swap(&trader, 10_000, true).unwrap_err(), orca_error(ErrorCode::PoolOperationDisabled));
+    // Only index 0 answers to the legacy address
+    let mut config = fixture.svm.account::<AmmConfig>(&legacy);
+    config.index = 1;
+    let mut data = Vec::new();
+    config.try_serialize(&mut data).unwrap();
+    fixture.svm.set_data(&legacy, data);
+    assert_eq!(fixture.svm.process(&[pause], &[fixture.admin]).unwrap_err(), orca_error(ErrorCode::InvalidPoolConfig));
+}
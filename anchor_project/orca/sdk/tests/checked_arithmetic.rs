@@ -0,0 +1,72 @@
+//! Accounting that would overflow must fail the instruction with a specific
+//! error instead of saturating into a silently wrong pool state.
+
+mod common;
+
+use common::*;
+use orca::errors::ErrorCode;
+use orca::instruction;
+use orca_sdk::instructions as ix;
+
+fn funded_pool() -> (PoolFixture, User) {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&provider, 1_000_000, 1_000_000).unwrap();
+    (fixture, provider)
+}
+
+#[test]
+fn adding_liquidity_past_u128_fails_with_liquidity_overflow() {
+    let (mut fixture, provider) = funded_pool();
+    let mut pool = fixture.pool();
+    pool.liquidity = u128::MAX;
+    fixture.svm.set_account(&fixture.keys.pool, &pool);
+    assert_eq!(fixture.add_liquidity(&provider, 1_000, 1_000).unwrap_err(), orca_error(ErrorCode::LiquidityOverflow));
+    // Position liquidity is added to the same total
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, provider.key)], &[provider.key]).unwrap();
+    let increase = ix::increase_position_liquidity(&fixture.keys, provider.key, provider.tokens, instruction::IncreasePositionLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 });
+    assert_eq!(fixture.svm.process(&[increase], &[provider.key]).unwrap_err(), orca_error(ErrorCode::LiquidityOverflow));
+}
+
+#[test]
+fn withdrawing_more_than_the_pool_tracks_fails_with_liquidity_underflow() {
+    let (mut fixture, provider) = funded_pool();
+    let mut pool = fixture.pool();
+    pool.liquidity = 1;
+    fixture.svm.set_account(&fixture.keys.pool, &pool);
+    let lp_amount = fixture.svm.token_balance(&provider.lp_token_account);
+    let remove = ix::remove_liquidity(&fixture.keys, provider.key, provider.tokens, provider.lp_token_account, instruction::RemoveLiquidity { lp_amount });
+    assert_eq!(fixture.svm.process(&[remove], &[provider.key]).unwrap_err(), orca_error(ErrorCode::LiquidityUnderflow));
+}
+
+#[test]
+fn swap_fails_when_protocol_fees_or_fee_growth_would_overflow() {
+    let (mut fixture, _) = funded_pool();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    let before = fixture.pool();
+
+    let mut pool = before;
+    pool.protocol_fees_token_0 = u64::MAX;
+    fixture.svm.set_account(&fixture.keys.pool, &pool);
+    assert_eq!(fixture.swap(&trader, 100_000, true).unwrap_err(), orca_error(ErrorCode::MathOverflow));
+
+    let mut pool = before;
+    pool.fee_growth_global_1_x64 = u128::MAX;
+    fixture.svm.set_account(&fixture.keys.pool, &pool);
+    assert_eq!(fixture.swap(&trader, 100_000, false).unwrap_err(), orca_error(ErrorCode::MathOverflow));
+
+    // With sane accounting the same swap goes through
+    fixture.svm.set_account(&fixture.keys.pool, &before);
+    fixture.swap(&trader, 100_000, true).unwrap();
+}
+
+#[test]
+fn a_share_fee_entry_above_fee_growth_fails_with_math_underflow() {
+    let (mut fixture, provider) = funded_pool();
+    let mut pool = fixture.pool();
+    pool.lp_fee_growth_entry_0_x64 = pool.fee_growth_global_0_x64 + 1;
+    fixture.svm.set_account(&fixture.keys.pool, &pool);
+    assert_eq!(fixture.add_liquidity(&provider, 1_000, 1_000).unwrap_err(), orca_error(ErrorCode::MathUnderflow));
+    let remove = ix::remove_liquidity(&fixture.keys, provider.key, provider.tokens, provider.lp_token_account, instruction::RemoveLiquidity { lp_amount: 1_000 });
+    assert_eq!(fixture.svm.process(&[remove], &[provider.key]).unwrap_err(), orca_error(ErrorCode::MathUnderflow));
+}
@@ -0,0 +1,442 @@
+//! Execution of the built orca program for `cargo test`.
+//!
+//! Transactions run in LiteSVM against the SBF binary `anchor build` writes
+//! to `target/deploy/orca.so` (`ORCA_PROGRAM` points elsewhere), next to the
+//! SPL Token program LiteSVM ships with. Signatures are not verified, so tests
+//! name their signers by key and a harness wallet pays the fees. Every
+//! transaction is metered and must fit the budgets in `COMPUTE_BUDGETS`.
+//! Program logs are printed only for a failed transaction and carried in the
+//! panic of an over-budget one; `cargo test` shows both for failing tests.
+
+#![allow(dead_code)]
+
+pub mod sequence;
+
+use std::sync::OnceLock;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::{Clock, ProgramData, ProgramError, Pubkey};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::message::Message;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator, Owner, ZeroCopy};
+use anchor_spl::token::spl_token;
+use litesvm::LiteSVM;
+use orca::instruction;
+use orca_sdk::instructions::{self as ix, PoolKeys, UserTokenAccounts};
+use orca_sdk::{pda, Pool};
+use solana_account::Account;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+pub const STARTING_TIMESTAMP: i64 = 1_700_000_000;
+pub const INITIAL_SQRT_PRICE_X64: u128 = 1 << 64;
+pub const FEE_RATE: u16 = 3000;
+/// A fifth of every trading fee goes to the protocol.
+pub const PROTOCOL_FEE_RATE: u16 = 2000;
+
+macro_rules! compute_budgets {
+    ($($instruction:ident => $units:expr,)*) => {
+        &[$((stringify!($instruction), instruction::$instruction::DISCRIMINATOR, $units),)*]
+    };
+}
+
+/// The compute units each instruction may use at most, CPIs included. A
+/// transaction may use the sum of its instructions' budgets; one that uses more
+/// fails its test, so a change that makes an instruction costlier shows up
+/// here and its budget is raised on purpose.
+///
+/// These are unmeasured ceilings: no SBF build of the program has been metered
+/// against them yet. The first run against a built `orca.so` should tighten
+/// each one to a margin over what it reports.
+const COMPUTE_BUDGETS: &[(&str, &[u8], u64)] = compute_budgets! {
+    InitializeAmm => 40_000,
+    CreatePool => 120_000,
+    CreatePoolWithLiquidity => 180_000,
+    AddLiquidity => 60_000,
+    RemoveLiquidity => 60_000,
+    Swap => 120_000,
+    QuoteSwap => 80_000,
+    OpenPosition => 40_000,
+    IncreasePositionLiquidity => 60_000,
+    DecreasePositionLiquidity => 60_000,
+    CollectFees => 50_000,
+    FlashBorrow => 50_000,
+    FlashRepay => 50_000,
+    SetFlashFeeRate => 20_000,
+    SetPoolCreationPolicy => 20_000,
+    SetPoolStatus => 20_000,
+    EnterEmergencyMode => 20_000,
+    ScheduleEmergencyExit => 20_000,
+    ExitEmergencyMode => 20_000,
+    EmergencyRemoveLiquidity => 60_000,
+    EmergencyWithdrawPosition => 60_000,
+    SetDynamicFee => 20_000,
+    SetLimitOrderMinLiquidity => 20_000,
+    PlaceLimitOrder => 60_000,
+    CancelLimitOrder => 60_000,
+    ClaimLimitOrder => 60_000,
+    MigrateAmmConfig => 30_000,
+    MigratePool => 60_000,
+    MigratePoolV1 => 30_000,
+    MigratePosition => 30_000,
+    MigrateObservationState => 30_000,
+    AddPoolCreator => 25_000,
+    RemovePoolCreator => 25_000,
+    AddTokenBadge => 25_000,
+    RemoveTokenBadge => 25_000,
+};
+
+/// The budget of one orca instruction, and its name for failure messages.
+fn compute_budget(instruction: &Instruction) -> (&'static str, u64) {
+    assert_eq!(instruction.program_id, orca::ID, "tests only send orca instructions");
+    let (name, _, units) = COMPUTE_BUDGETS
+        .iter()
+        .find(|(_, discriminator, _)| instruction.data.starts_with(discriminator))
+        .expect("every instruction has a compute budget");
+    (name, *units)
+}
+
+/// The built program, read once for all the tests in a binary.
+fn program() -> &'static [u8] {
+    static PROGRAM: OnceLock<Vec<u8>> = OnceLock::new();
+    PROGRAM.get_or_init(|| {
+        let path = std::env::var("ORCA_PROGRAM").unwrap_or_else(|_| format!("{}/../target/deploy/orca.so", env!("CARGO_MANIFEST_DIR")));
+        std::fs::read(&path).unwrap_or_else(|error| panic!("reading {path}: {error}; build the program with `anchor build` first"))
+    })
+}
+
+/// What a successful transaction left behind besides account changes.
+#[derive(Debug, Default)]
+pub struct Outcome {
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+    pub inner_instructions: Vec<Instruction>,
+    pub compute_units: u64,
+}
+
+impl Outcome {
+    /// Events emitted through `emit_cpi!`, decoded from the self-invocations.
+    pub fn events<E: AnchorDeserialize + Discriminator>(&self) -> Vec<E> {
+        self.inner_instructions
+            .iter()
+            .filter(|ix| ix.program_id == orca::ID && ix.data.starts_with(EVENT_IX_TAG_LE))
+            .map(|ix| &ix.data[EVENT_IX_TAG_LE.len()..])
+            .filter(|data| data.starts_with(E::DISCRIMINATOR))
+            .map(|data| E::deserialize(&mut &data[E::DISCRIMINATOR.len()..]).unwrap())
+            .collect()
+    }
+
+    pub fn return_value<T: AnchorDeserialize>(&self) -> T {
+        let (program_id, data) = self.return_data.as_ref().expect("no return data");
+        assert_eq!(*program_id, orca::ID);
+        T::deserialize(&mut &data[..]).unwrap()
+    }
+}
+
+/// A LiteSVM instance with orca deployed, plus the wallet paying for transactions.
+pub struct Svm {
+    svm: LiteSVM,
+    payer: Pubkey,
+}
+
+impl Svm {
+    pub fn new() -> Self {
+        let mut svm = LiteSVM::new().with_sigverify(false).with_blockhash_check(false).with_transaction_history(0);
+        svm.add_program(orca::ID, program()).unwrap();
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = STARTING_TIMESTAMP;
+        svm.set_sysvar(&clock);
+        let payer = Pubkey::new_unique();
+        let mut svm = Self { svm, payer };
+        svm.airdrop(&payer, 1_000_000_000_000);
+        svm
+    }
+
+    /// Runs `instructions` as one transaction; on failure no account changes are kept.
+    pub fn process(&mut self, instructions: &[Instruction], signers: &[Pubkey]) -> Result<Outcome, ProgramError> {
+        for meta in instructions.iter().flat_map(|instruction| &instruction.accounts) {
+            if meta.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+        let message = Message::new(instructions, Some(&self.payer));
+        let keys = message.account_keys.clone();
+        let meta = match self.svm.send_transaction(Transaction::new_unsigned(message.clone())) {
+            Ok(meta) => meta,
+            Err(failed) => {
+                println!("{}", failed.meta.logs.join("\n"));
+                return match failed.err {
+                    TransactionError::InstructionError(_, error) => Err(ProgramError::try_from(error.clone())
+                        .unwrap_or_else(|_| panic!("transaction failed outside the program error space: {error:?}"))),
+                    error => panic!("transaction rejected: {error:?}"),
+                };
+            }
+        };
+        let (names, budget) = instructions.iter().map(compute_budget).fold((Vec::new(), 0), |(mut names, total), (name, units)| {
+            names.push(name);
+            (names, total + units)
+        });
+        assert!(
+            meta.compute_units_consumed <= budget,
+            "{names:?} used {} compute units, over the {budget} budgeted\n{}",
+            meta.compute_units_consumed,
+            meta.logs.join("\n")
+        );
+        let inner_instructions = meta
+            .inner_instructions
+            .iter()
+            .flatten()
+            .map(|inner| {
+                let compiled = &inner.instruction;
+                let accounts = compiled
+                    .accounts
+                    .iter()
+                    .map(|&index| AccountMeta {
+                        pubkey: keys[index as usize],
+                        is_signer: message.is_signer(index as usize),
+                        is_writable: message.is_maybe_writable(index as usize, None),
+                    })
+                    .collect();
+                Instruction { program_id: keys[compiled.program_id_index as usize], accounts, data: compiled.data.clone() }
+            })
+            .collect();
+        let return_data = (!meta.return_data.data.is_empty()).then_some((meta.return_data.program_id, meta.return_data.data));
+        Ok(Outcome { return_data, inner_instructions, compute_units: meta.compute_units_consumed })
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.svm.get_sysvar()
+    }
+
+    pub fn warp_to_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock();
+        clock.slot += 1;
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    fn raw_account(&self, key: &Pubkey) -> Option<Account> {
+        self.svm.get_account(key).filter(|account| account.lamports > 0)
+    }
+
+    fn store(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len());
+        self.svm.set_account(key, Account { lamports, data, owner, executable: false, rent_epoch: 0 }).unwrap();
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        let mut account = self.raw_account(key).unwrap_or_default();
+        account.lamports += lamports;
+        self.svm.set_account(*key, account).unwrap();
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.raw_account(key).map_or(0, |account| account.lamports)
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        self.try_account(key).unwrap()
+    }
+
+    pub fn try_account<T: AccountDeserialize>(&self, key: &Pubkey) -> Option<T> {
+        orca_sdk::accounts::decode(&self.raw_account(key)?.data).ok()
+    }
+
+    /// Overwrites an existing zero-copy account's state, keeping its owner and lamports.
+    pub fn set_account<T: ZeroCopy>(&mut self, key: &Pubkey, state: &T) {
+        let mut account = self.raw_account(key).unwrap();
+        account.data[T::DISCRIMINATOR.len()..T::DISCRIMINATOR.len() + size_of::<T>()].copy_from_slice(bytemuck::bytes_of(state));
+        self.svm.set_account(*key, account).unwrap();
+    }
+
+    /// Writes a `ProgramData` account, as the upgradeable loader keeps at
+    /// `pda::program_data()` for the deployed program, recording
+    /// `upgrade_authority`, or an immutable program for `None`.
+    pub fn set_program_data(&mut self, key: &Pubkey, upgrade_authority: Option<Pubkey>) {
+        // `UpgradeableLoaderState::ProgramData` as bincode lays it out, with no program bytes after it
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        match upgrade_authority {
+            Some(key) => {
+                data.push(1);
+                data.extend_from_slice(key.as_ref());
+            }
+            None => data.extend_from_slice(&[0; 33]),
+        }
+        self.store(*key, ProgramData::owner(), data);
+    }
+
+    /// Writes an account owned by the program, as an earlier version of it
+    /// would have left `data` at `key`.
+    pub fn set_program_account(&mut self, key: &Pubkey, data: Vec<u8>) {
+        self.store(*key, orca::ID, data);
+    }
+
+    /// Raw account data, discriminator included.
+    pub fn data(&self, key: &Pubkey) -> Vec<u8> {
+        self.raw_account(key).unwrap().data
+    }
+
+    /// Replaces an existing account's data, keeping its owner, with the rent
+    /// an account of the new size would have been created with.
+    pub fn set_data(&mut self, key: &Pubkey, data: Vec<u8>) {
+        let owner = self.raw_account(key).unwrap().owner;
+        self.store(*key, owner, data);
+    }
+
+    fn set_packed<T: Pack>(&mut self, key: Pubkey, state: T) {
+        let mut data = vec![0; T::LEN];
+        T::pack(state, &mut data).unwrap();
+        self.store(key, spl_token::ID, data);
+    }
+
+    pub fn create_mint(&mut self, authority: &Pubkey, decimals: u8) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let state = spl_token::state::Mint {
+            mint_authority: Some(*authority).into(),
+            decimals,
+            is_initialized: true,
+            ..Default::default()
+        };
+        self.set_packed(mint, state);
+        mint
+    }
+
+    /// Creates a token account holding `amount`, minted out of thin air.
+    pub fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let account = Pubkey::new_unique();
+        let state = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        self.set_packed(account, state);
+        if amount > 0 {
+            let mut mint_state = spl_token::state::Mint::unpack(&self.data(mint)).unwrap();
+            mint_state.supply += amount;
+            self.set_packed(*mint, mint_state);
+        }
+        account
+    }
+
+    pub fn token_balance(&self, account: &Pubkey) -> u64 {
+        spl_token::state::Account::unpack(&self.data(account)).unwrap().amount
+    }
+
+    pub fn mint_supply(&self, mint: &Pubkey) -> u64 {
+        spl_token::state::Mint::unpack(&self.data(mint)).unwrap().supply
+    }
+}
+
+/// A config as the program stored it before `version`, with the fixture's
+/// authority and fees.
+pub fn legacy_amm_config(bump: u8, authority: Pubkey) -> Vec<u8> {
+    legacy_account::<orca::state::AmmConfig>(&orca::state::legacy::AmmConfig {
+        bump,
+        authority,
+        default_fee_rate: FEE_RATE,
+        protocol_fee_rate: PROTOCOL_FEE_RATE,
+        protocol_fee_destination: authority,
+        create_pool_enabled: true,
+        swap_enabled: true,
+    })
+}
+
+/// `state`, a legacy layout of `T`, serialized as the program stored it.
+pub fn legacy_account<T: Discriminator>(state: &impl AnchorSerialize) -> Vec<u8> {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    state.serialize(&mut data).unwrap();
+    data
+}
+
+/// Anchor's encoding of a program error code.
+pub fn orca_error(code: orca::errors::ErrorCode) -> ProgramError {
+    anchor_lang::error::Error::from(code).into()
+}
+
+/// A funded wallet with token accounts for both pool mints and the LP mint.
+#[derive(Clone, Copy, Debug)]
+pub struct User {
+    pub key: Pubkey,
+    pub tokens: UserTokenAccounts,
+    pub lp_token_account: Pubkey,
+}
+
+/// An initialized AMM with one pool at a 1:1 price and no liquidity.
+pub struct PoolFixture {
+    pub svm: Svm,
+    pub admin: Pubkey,
+    pub keys: PoolKeys,
+}
+
+impl PoolFixture {
+    pub fn new() -> Self {
+        let (mut svm, admin) = Self::deployment();
+        svm.process(&[ix::initialize_amm(admin, admin, admin, instruction::InitializeAmm { index: 0, fee_rate: FEE_RATE, protocol_fee_rate: PROTOCOL_FEE_RATE })], &[admin]).unwrap();
+        Self::with_pool(svm, admin, pda::amm_config(0).0)
+    }
+
+    /// Like `new`, but the pool is under the config made before configs were
+    /// indexed: written at `pda::legacy_amm_config()` at its legacy layout,
+    /// then migrated.
+    pub fn with_legacy_config() -> Self {
+        let (mut svm, admin) = Self::deployment();
+        let (config, bump) = pda::legacy_amm_config();
+        svm.set_program_account(&config, legacy_amm_config(bump, admin));
+        svm.process(&[ix::migrate_amm_config(config, admin)], &[admin]).unwrap();
+        Self::with_pool(svm, admin, config)
+    }
+
+    fn deployment() -> (Svm, Pubkey) {
+        let mut svm = Svm::new();
+        let admin = Pubkey::new_unique();
+        svm.airdrop(&admin, 100_000_000_000);
+        svm.set_program_data(&pda::program_data().0, Some(admin));
+        (svm, admin)
+    }
+
+    fn with_pool(mut svm: Svm, admin: Pubkey, amm_config: Pubkey) -> Self {
+        let mut mints = [svm.create_mint(&admin, 6), svm.create_mint(&admin, 6)];
+        mints.sort();
+        let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let create = ix::create_pool(admin, amm_config, mints[0], mints[1], vault_0, vault_1, instruction::CreatePool { sqrt_price_x64: INITIAL_SQRT_PRICE_X64, tick_spacing: 64 });
+        svm.process(&[create], &[admin, vault_0, vault_1]).unwrap();
+        let pool = pda::pool(&amm_config, &mints[0], &mints[1]).0;
+        let keys = PoolKeys::new(pool, &svm.account::<Pool>(&pool));
+        Self { svm, admin, keys }
+    }
+
+    pub fn pool(&self) -> Pool {
+        self.svm.account(&self.keys.pool)
+    }
+
+    pub fn user(&mut self, amount_0: u64, amount_1: u64) -> User {
+        let key = Pubkey::new_unique();
+        self.svm.airdrop(&key, 10_000_000_000);
+        let tokens = UserTokenAccounts {
+            token_account_0: self.svm.create_token_account(&self.keys.token_mint_0, &key, amount_0),
+            token_account_1: self.svm.create_token_account(&self.keys.token_mint_1, &key, amount_1),
+        };
+        let lp_token_account = self.svm.create_token_account(&self.keys.lp_mint, &key, 0);
+        User { key, tokens, lp_token_account }
+    }
+
+    pub fn add_liquidity(&mut self, user: &User, amount_token_0: u64, amount_token_1: u64) -> Result<Outcome, ProgramError> {
+        let args = instruction::AddLiquidity { amount_token_0, amount_token_1 };
+        let add = ix::add_liquidity(&self.keys, user.key, user.tokens, user.lp_token_account, args);
+        self.svm.process(&[add], &[user.key])
+    }
+
+    pub fn swap(&mut self, user: &User, amount_in: u64, zero_for_one: bool) -> Result<Outcome, ProgramError> {
+        let (account_in, account_out, sqrt_price_limit_x64) = if zero_for_one {
+            (user.tokens.token_account_0, user.tokens.token_account_1, orca::constants::MIN_SQRT_PRICE)
+        } else {
+            (user.tokens.token_account_1, user.tokens.token_account_0, orca::constants::MAX_SQRT_PRICE)
+        };
+        let args = instruction::Swap { amount_in, minimum_amount_out: 0, zero_for_one, sqrt_price_limit_x64 };
+        let swap = ix::swap(&self.keys, user.key, account_in, account_out, args);
+        self.svm.process(&[swap], &[user.key])
+    }
+}
@@ -0,0 +1,215 @@
+//! Random instruction sequences by several actors against one pool, with
+//! solvency invariants checked after every step. Driven by proptest in
+//! `tests/instruction_sequences.rs` and by the cargo-fuzz target in `fuzz/`.
+
+use anchor_lang::prelude::Pubkey;
+use arbitrary::Arbitrary;
+use orca::constants::MINIMUM_LIQUIDITY;
+use orca::instruction;
+use orca_math::{amounts_for_liquidity, mul_div, Q64};
+use orca_sdk::instructions as ix;
+use orca_sdk::{pda, LimitOrder, LimitOrderBook, LimitOrderRange, Position};
+
+use super::{PoolFixture, User};
+
+pub const ACTORS: usize = 4;
+const STARTING_BALANCE: u64 = 1 << 40;
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum Action {
+    AddLiquidity { actor: u8, amount_0: u32, amount_1: u32 },
+    RemoveLiquidity { actor: u8, share_bps: u16 },
+    Swap { actor: u8, amount_in: u32, zero_for_one: bool },
+    OpenPosition { actor: u8 },
+    IncreasePosition { actor: u8, amount_0: u32, amount_1: u32 },
+    DecreasePosition { actor: u8, amount_0: u32, amount_1: u32 },
+    CollectFees { actor: u8 },
+    FlashLoan { actor: u8, amount_0: u32, amount_1: u32 },
+    /// `offset` is in tick spacings from the range the price is in.
+    PlaceLimitOrder { actor: u8, offset: i8, amount: u32 },
+    /// `slot` picks a range of the order book.
+    CancelLimitOrder { actor: u8, slot: u8 },
+    ClaimLimitOrder { actor: u8, keeper: u8, slot: u8 },
+    Warp { seconds: u16 },
+}
+
+/// Applies `actions` in order, then has every actor withdraw everything.
+/// Instructions the program rejects are skipped; broken invariants panic.
+pub fn run(actions: &[Action]) {
+    let mut fixture = PoolFixture::new();
+    let actors: Vec<User> = (0..ACTORS).map(|_| fixture.user(STARTING_BALANCE, STARTING_BALANCE)).collect();
+    for action in actions {
+        let _ = apply(&mut fixture, &actors, *action);
+        check_invariants(&fixture, &actors);
+    }
+    for actor in &actors {
+        exit(&mut fixture, actor);
+        check_invariants(&fixture, &actors);
+    }
+}
+
+fn apply(fixture: &mut PoolFixture, actors: &[User], action: Action) -> Result<(), anchor_lang::prelude::ProgramError> {
+    let actor = |index: u8| actors[index as usize % actors.len()];
+    match action {
+        Action::AddLiquidity { actor: index, amount_0, amount_1 } => {
+            fixture.add_liquidity(&actor(index), amount_0 as u64, amount_1 as u64)?;
+        }
+        Action::RemoveLiquidity { actor: index, share_bps } => {
+            let user = actor(index);
+            let lp_amount = (fixture.svm.token_balance(&user.lp_token_account) as u128 * (share_bps % 10_001) as u128 / 10_000) as u64;
+            let remove = ix::remove_liquidity(&fixture.keys, user.key, user.tokens, user.lp_token_account, instruction::RemoveLiquidity { lp_amount });
+            fixture.svm.process(&[remove], &[user.key])?;
+        }
+        Action::Swap { actor: index, amount_in, zero_for_one } => {
+            fixture.swap(&actor(index), amount_in as u64, zero_for_one)?;
+        }
+        Action::OpenPosition { actor: index } => {
+            let user = actor(index);
+            fixture.svm.process(&[ix::open_position(fixture.keys.pool, user.key)], &[user.key])?;
+        }
+        Action::IncreasePosition { actor: index, amount_0, amount_1 } => {
+            let user = actor(index);
+            let args = instruction::IncreasePositionLiquidity { amount_token_0: amount_0 as u64, amount_token_1: amount_1 as u64 };
+            fixture.svm.process(&[ix::increase_position_liquidity(&fixture.keys, user.key, user.tokens, args)], &[user.key])?;
+        }
+        Action::DecreasePosition { actor: index, amount_0, amount_1 } => {
+            let user = actor(index);
+            let args = instruction::DecreasePositionLiquidity { amount_token_0: amount_0 as u64, amount_token_1: amount_1 as u64 };
+            fixture.svm.process(&[ix::decrease_position_liquidity(&fixture.keys, user.key, user.tokens, args)], &[user.key])?;
+        }
+        Action::CollectFees { actor: index } => {
+            let user = actor(index);
+            fixture.svm.process(&[ix::collect_fees(&fixture.keys, user.key, user.tokens)], &[user.key])?;
+        }
+        Action::FlashLoan { actor: index, amount_0, amount_1 } => {
+            let user = actor(index);
+            let borrow = ix::flash_borrow(&fixture.keys, user.key, user.tokens, instruction::FlashBorrow { amount_token_0: amount_0 as u64, amount_token_1: amount_1 as u64 });
+            fixture.svm.process(&[borrow, ix::flash_repay(&fixture.keys, user.key, user.tokens)], &[user.key])?;
+        }
+        Action::PlaceLimitOrder { actor: index, offset, amount } => {
+            let user = actor(index);
+            let pool = fixture.pool();
+            let spacing = pool.tick_spacing as i32;
+            let tick_lower = pool.tick_current.div_euclid(spacing) * spacing + offset as i32 * spacing;
+            let args = instruction::PlaceLimitOrder { tick_lower, amount: amount as u64 };
+            fixture.svm.process(&[ix::place_limit_order(&fixture.keys, user.key, user.tokens, args)], &[user.key])?;
+        }
+        Action::CancelLimitOrder { actor: index, slot } => {
+            let user = actor(index);
+            let tick_lower = book(fixture).ranges[slot as usize % LimitOrderBook::CAPACITY].tick_lower;
+            fixture.svm.process(&[ix::cancel_limit_order(&fixture.keys, user.key, user.tokens, tick_lower)], &[user.key])?;
+        }
+        Action::ClaimLimitOrder { actor: index, keeper, slot } => {
+            let (user, keeper) = (actor(index), actor(keeper));
+            let tick_lower = book(fixture).ranges[slot as usize % LimitOrderBook::CAPACITY].tick_lower;
+            fixture.svm.process(&[ix::claim_limit_order(&fixture.keys, keeper.key, user.key, user.tokens, tick_lower)], &[keeper.key])?;
+        }
+        Action::Warp { seconds } => {
+            let now = fixture.svm.clock().unix_timestamp;
+            fixture.svm.warp_to_timestamp(now + seconds as i64);
+        }
+    }
+    Ok(())
+}
+
+fn position(fixture: &PoolFixture, owner: &Pubkey) -> Option<Position> {
+    fixture.svm.try_account(&pda::position(&fixture.keys.pool, owner).0)
+}
+
+fn book(fixture: &PoolFixture) -> LimitOrderBook {
+    fixture.svm.account(&fixture.keys.limit_order_book)
+}
+
+/// `owner`'s open limit orders with the range each sits on.
+fn limit_orders(fixture: &PoolFixture, owner: &Pubkey) -> Vec<(LimitOrder, LimitOrderRange)> {
+    let ranges = book(fixture).ranges.into_iter().filter(|range| range.open_orders > 0);
+    ranges.filter_map(|range| Some((fixture.svm.try_account(&pda::limit_order(&fixture.keys.pool, owner, range.tick_lower).0)?, range))).collect()
+}
+
+/// Withdrawing must always succeed for whatever an actor holds.
+fn exit(fixture: &mut PoolFixture, actor: &User) {
+    let lp_amount = fixture.svm.token_balance(&actor.lp_token_account);
+    if lp_amount > 0 {
+        // A share holder leaves with their liquidity at the current price and their part of the share fees
+        let pool = fixture.pool();
+        let total_shares = fixture.svm.mint_supply(&fixture.keys.lp_mint) as u128 + MINIMUM_LIQUIDITY;
+        let delta = mul_div(lp_amount as u128, pool.lp_liquidity, total_shares, false).unwrap();
+        let (principal_0, principal_1) = amounts_for_liquidity(pool.sqrt_price_x64, delta, false).unwrap();
+        let (lp_fees_0, lp_fees_1) = lp_fees(&pool);
+        let expected_0 = principal_0 as u128 + mul_div(lp_fees_0, lp_amount as u128, total_shares, false).unwrap();
+        let expected_1 = principal_1 as u128 + mul_div(lp_fees_1, lp_amount as u128, total_shares, false).unwrap();
+        let before = (fixture.svm.token_balance(&actor.tokens.token_account_0), fixture.svm.token_balance(&actor.tokens.token_account_1));
+        let remove = ix::remove_liquidity(&fixture.keys, actor.key, actor.tokens, actor.lp_token_account, instruction::RemoveLiquidity { lp_amount });
+        fixture.svm.process(&[remove], &[actor.key]).expect("LP shares could not be redeemed");
+        let received_0 = (fixture.svm.token_balance(&actor.tokens.token_account_0) - before.0) as u128;
+        let received_1 = (fixture.svm.token_balance(&actor.tokens.token_account_1) - before.1) as u128;
+        assert_eq!((received_0, received_1), (expected_0, expected_1), "share holder did not receive their fee share");
+    }
+    let Some(state) = position(fixture, &actor.key) else { return };
+    let (amount_token_0, amount_token_1) = amounts_for_liquidity(fixture.pool().sqrt_price_x64, state.liquidity, false).unwrap();
+    if amount_token_0 > 0 && amount_token_1 > 0 {
+        let args = instruction::DecreasePositionLiquidity { amount_token_0, amount_token_1 };
+        fixture.svm.process(&[ix::decrease_position_liquidity(&fixture.keys, actor.key, actor.tokens, args)], &[actor.key]).expect("position could not be withdrawn");
+    }
+    fixture.svm.process(&[ix::collect_fees(&fixture.keys, actor.key, actor.tokens)], &[actor.key]).expect("fees could not be collected");
+    for (order, range) in limit_orders(fixture, &actor.key) {
+        let close = if range.filled != 0 {
+            ix::claim_limit_order(&fixture.keys, actor.key, actor.key, actor.tokens, order.tick_lower)
+        } else {
+            ix::cancel_limit_order(&fixture.keys, actor.key, actor.tokens, order.tick_lower)
+        };
+        fixture.svm.process(&[close], &[actor.key]).expect("limit order could not be closed");
+    }
+}
+
+/// Fees owed to all LP shares together, including what they earned since the pool last accrued them.
+fn lp_fees(pool: &orca_sdk::Pool) -> (u128, u128) {
+    let pending = |growth: u128, entry: u128| mul_div(pool.lp_liquidity, growth.wrapping_sub(entry), Q64, false).unwrap();
+    (
+        pool.lp_fees_owed_0 as u128 + pending(pool.fee_growth_global_0_x64, pool.lp_fee_growth_entry_0_x64),
+        pool.lp_fees_owed_1 as u128 + pending(pool.fee_growth_global_1_x64, pool.lp_fee_growth_entry_1_x64),
+    )
+}
+
+/// Fees owed to all positions together, including what they earned since the pool last accrued them.
+fn position_fees(pool: &orca_sdk::Pool) -> (u128, u128) {
+    let liquidity = pool.liquidity - pool.lp_liquidity;
+    let pending = |growth: u128, entry: u128| mul_div(liquidity, growth - entry, Q64, true).unwrap();
+    (
+        pool.position_fees_owed_0 as u128 + pending(pool.fee_growth_global_0_x64, pool.position_fee_growth_entry_0_x64),
+        pool.position_fees_owed_1 as u128 + pending(pool.fee_growth_global_1_x64, pool.position_fee_growth_entry_1_x64),
+    )
+}
+
+fn check_invariants(fixture: &PoolFixture, actors: &[User]) {
+    let pool = fixture.pool();
+    let positions: Vec<Position> = actors.iter().filter_map(|actor| position(fixture, &actor.key)).collect();
+
+    // Pool liquidity is exactly the share-backed part plus every position
+    let position_liquidity: u128 = positions.iter().map(|position| position.liquidity).sum();
+    assert_eq!(pool.liquidity, pool.lp_liquidity + position_liquidity, "liquidity is not accounted for");
+    let lp_supply = fixture.svm.mint_supply(&fixture.keys.lp_mint);
+    assert!(lp_supply == 0 || pool.lp_liquidity >= MINIMUM_LIQUIDITY, "shares outstanding without locked liquidity");
+
+    // Each vault covers the liquidity at the current price, the protocol's fees, the share fees and every position's fees
+    let (reserve_0, reserve_1) = amounts_for_liquidity(pool.sqrt_price_x64, pool.liquidity, false).unwrap();
+    let owed = |growth: u128, entry: u128, liquidity: u128, tokens_owed: u64| mul_div(liquidity, growth.wrapping_sub(entry), Q64, false).unwrap() + tokens_owed as u128;
+    let owed_0: u128 = positions.iter().map(|p| owed(pool.fee_growth_global_0_x64, p.fee_growth_entry_0_x64, p.liquidity, p.tokens_owed_0)).sum();
+    let owed_1: u128 = positions.iter().map(|p| owed(pool.fee_growth_global_1_x64, p.fee_growth_entry_1_x64, p.liquidity, p.tokens_owed_1)).sum();
+    // What the pool owes positions together covers what each can collect
+    let (position_fees_0, position_fees_1) = position_fees(&pool);
+    assert!(position_fees_0 >= owed_0 && position_fees_1 >= owed_1, "position fees are under-recorded");
+    let (lp_fees_0, lp_fees_1) = lp_fees(&pool);
+    // Limit orders are owed their part of what their range holds, and their fees
+    let (mut orders_0, mut orders_1) = (0u128, 0u128);
+    for (order, range) in actors.iter().flat_map(|actor| limit_orders(fixture, &actor.key)) {
+        orders_0 += mul_div(range.amount_0 as u128, order.liquidity, range.liquidity, false).unwrap();
+        orders_1 += mul_div(range.amount_1 as u128, order.liquidity, range.liquidity, false).unwrap();
+        orders_0 += mul_div(order.liquidity, range.fee_growth_0_x64 - order.fee_growth_entry_0_x64, Q64, false).unwrap();
+        orders_1 += mul_div(order.liquidity, range.fee_growth_1_x64 - order.fee_growth_entry_1_x64, Q64, false).unwrap();
+    }
+    let vault_0 = fixture.svm.token_balance(&fixture.keys.token_vault_0) as u128;
+    let vault_1 = fixture.svm.token_balance(&fixture.keys.token_vault_1) as u128;
+    assert!(vault_0 >= reserve_0 as u128 + pool.protocol_fees_token_0 as u128 + lp_fees_0 + owed_0 + orders_0, "vault 0 is insolvent");
+    assert!(vault_1 >= reserve_1 as u128 + pool.protocol_fees_token_1 as u128 + lp_fees_1 + owed_1 + orders_1, "vault 1 is insolvent");
+}
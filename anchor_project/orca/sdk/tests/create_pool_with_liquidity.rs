@@ -0,0 +1,103 @@
+//! `create_pool_with_liquidity` creates a pool and its first position in one
+//! instruction, so the pool never exists empty or at a price nobody deposited at.
+
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use common::*;
+use orca::errors::ErrorCode;
+use orca::events::{PoolCreated, PositionOpened};
+use orca::instruction;
+use orca::instructions::liquidity::LiquidityResult;
+use orca_math::Q64;
+use orca_sdk::instructions::{self as ix, PoolKeys, UserTokenAccounts};
+use orca_sdk::{pda, Pool, Position};
+
+/// An initialized AMM, two sorted mints and a funded creator, but no pool yet.
+struct Setup {
+    svm: Svm,
+    admin: Pubkey,
+    mints: [Pubkey; 2],
+    creator: Pubkey,
+    tokens: UserTokenAccounts,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let mut svm = Svm::new();
+        let admin = Pubkey::new_unique();
+        svm.airdrop(&admin, 100_000_000_000);
+        svm.set_program_data(&pda::program_data().0, Some(admin));
+        let mut mints = [svm.create_mint(&admin, 6), svm.create_mint(&admin, 6)];
+        mints.sort();
+        svm.process(&[ix::initialize_amm(admin, admin, admin, instruction::InitializeAmm { index: 0, fee_rate: FEE_RATE, protocol_fee_rate: PROTOCOL_FEE_RATE })], &[admin]).unwrap();
+        let creator = Pubkey::new_unique();
+        svm.airdrop(&creator, 10_000_000_000);
+        let tokens = UserTokenAccounts {
+            token_account_0: svm.create_token_account(&mints[0], &creator, 10_000_000),
+            token_account_1: svm.create_token_account(&mints[1], &creator, 10_000_000),
+        };
+        Self { svm, admin, mints, creator, tokens }
+    }
+
+    fn pool(&self) -> Pubkey {
+        pda::pool(&pda::amm_config(0).0, &self.mints[0], &self.mints[1]).0
+    }
+
+    fn create(&mut self, sqrt_price_x64: Option<u128>, amount_token_0: u64, amount_token_1: u64) -> Result<Outcome, ProgramError> {
+        let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let args = instruction::CreatePoolWithLiquidity { sqrt_price_x64, tick_spacing: 64, amount_token_0, amount_token_1 };
+        let create = ix::create_pool_with_liquidity(self.creator, pda::amm_config(0).0, self.mints[0], self.mints[1], vault_0, vault_1, self.tokens, args);
+        self.svm.process(&[create], &[self.creator, vault_0, vault_1])
+    }
+}
+
+#[test]
+fn without_a_price_the_deposit_ratio_sets_it() {
+    let mut setup = Setup::new();
+    let outcome = setup.create(None, 1_000_000, 4_000_000).unwrap();
+    let seeded = outcome.return_value::<LiquidityResult>();
+    let pool = setup.svm.account::<Pool>(&setup.pool());
+    // token 1 is worth a quarter of token 0, so sqrt(P) = 2
+    assert_eq!(pool.sqrt_price_x64, 2 * Q64);
+    assert_eq!(outcome.events::<PoolCreated>()[0].sqrt_price_x64, 2 * Q64);
+    assert_eq!((seeded.amount_token_0, seeded.amount_token_1), (1_000_000, 4_000_000));
+    assert_eq!(pool.liquidity, seeded.liquidity_delta);
+    assert_eq!(setup.svm.token_balance(&pool.token_vault_0), seeded.amount_token_0);
+    assert_eq!(setup.svm.token_balance(&pool.token_vault_1), seeded.amount_token_1);
+    let position_key = pda::position(&setup.pool(), &setup.creator).0;
+    assert_eq!(outcome.events::<PositionOpened>()[0].position, position_key);
+    let position = setup.svm.account::<Position>(&position_key);
+    assert_eq!((position.owner, position.liquidity), (setup.creator, seeded.liquidity_delta));
+}
+
+#[test]
+fn an_explicit_price_takes_the_amounts_as_maximums_and_the_position_earns_fees() {
+    let mut setup = Setup::new();
+    let seeded = setup.create(Some(INITIAL_SQRT_PRICE_X64), 1_000_000, 4_000_000).unwrap().return_value::<LiquidityResult>();
+    assert_eq!((seeded.amount_token_0, seeded.amount_token_1), (1_000_000, 1_000_000));
+    assert_eq!(setup.svm.token_balance(&setup.tokens.token_account_1), 9_000_000);
+
+    let pool = setup.pool();
+    let keys = PoolKeys::new(pool, &setup.svm.account::<Pool>(&pool));
+    let (creator, tokens) = (setup.creator, setup.tokens);
+    let mut fixture = PoolFixture { svm: setup.svm, admin: setup.admin, keys };
+    let trader = fixture.user(1_000_000, 1_000_000);
+    fixture.swap(&trader, 100_000, true).unwrap();
+    let collect = ix::collect_fees(&fixture.keys, creator, tokens);
+    let collected = fixture.svm.process(&[collect], &[creator]).unwrap().return_value::<LiquidityResult>();
+    assert!(collected.amount_token_0 > 0);
+}
+
+#[test]
+fn a_failed_seed_leaves_no_pool_behind() {
+    let mut setup = Setup::new();
+    // More than the creator holds: the transfer fails and the whole creation with it
+    assert!(setup.create(None, 20_000_000, 1_000_000).is_err());
+    assert!(setup.svm.try_account::<Pool>(&setup.pool()).is_none());
+    assert_eq!(setup.create(None, 0, 1_000_000).unwrap_err(), orca_error(ErrorCode::InvalidAmount));
+    assert_eq!(setup.create(Some(1), 1_000_000, 1_000_000).unwrap_err(), orca_error(ErrorCode::InvalidSqrtPrice));
+    // A ratio past the price bounds is rejected like an explicit price would be
+    assert_eq!(setup.create(None, u64::MAX, 1).unwrap_err(), orca_error(ErrorCode::InvalidSqrtPrice));
+    setup.create(None, 1_000_000, 1_000_000).unwrap();
+}
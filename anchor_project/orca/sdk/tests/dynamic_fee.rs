@@ -0,0 +1,95 @@
+//! With dynamic fees on, recent tick movement raises the fee above the base
+//! rate, never past the cap, and the surcharge decays as the market calms.
+
+mod common;
+
+use common::*;
+use orca::constants::{MAX_FEE_RATE, MIN_SQRT_PRICE, VOLATILITY_HALF_LIFE};
+use orca::errors::ErrorCode;
+use orca::events::Swap;
+use orca::instruction;
+use orca::instructions::swap::SwapQuote;
+use orca_sdk::instructions as ix;
+
+const RATE_PER_TICK: u16 = 2;
+const MAX_RATE: u16 = 8000;
+
+fn pool_with_trader(dynamic: bool) -> (PoolFixture, User) {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 50_000_000, 50_000_000).unwrap();
+    if dynamic {
+        let args = instruction::SetDynamicFee { dynamic_fee_max_rate: MAX_RATE, dynamic_fee_rate_per_tick: RATE_PER_TICK };
+        fixture.svm.process(&[ix::set_dynamic_fee(&fixture.keys, fixture.admin, args)], &[fixture.admin]).unwrap();
+    }
+    let trader = fixture.user(50_000_000, 50_000_000);
+    (fixture, trader)
+}
+
+fn swap_fee_rate(fixture: &mut PoolFixture, trader: &User, amount: u64, zero_for_one: bool) -> u16 {
+    fixture.swap(trader, amount, zero_for_one).unwrap().events::<Swap>()[0].fee_rate
+}
+
+fn expected_rate(volatility: u64) -> u16 {
+    (FEE_RATE as u64 + volatility * RATE_PER_TICK as u64).min(MAX_RATE as u64) as u16
+}
+
+#[test]
+fn the_fee_stays_at_the_base_rate_while_dynamic_fees_are_off() {
+    let (mut fixture, trader) = pool_with_trader(false);
+    assert_eq!(swap_fee_rate(&mut fixture, &trader, 5_000_000, true), FEE_RATE);
+    assert_eq!(swap_fee_rate(&mut fixture, &trader, 5_000_000, false), FEE_RATE);
+    // Movement is still tracked, ready for when they are turned on
+    assert!(fixture.pool().volatility_accumulator > 0);
+}
+
+#[test]
+fn tick_movement_raises_the_fee_up_to_the_cap() {
+    let (mut fixture, trader) = pool_with_trader(true);
+    assert_eq!(swap_fee_rate(&mut fixture, &trader, 100_000, true), FEE_RATE);
+    let after_small = fixture.pool();
+    assert_eq!(after_small.volatility_accumulator, after_small.tick_current.unsigned_abs() as u64);
+
+    let tick_before = fixture.pool().tick_current;
+    let rate = swap_fee_rate(&mut fixture, &trader, 1_000_000, false);
+    assert_eq!(rate, expected_rate(after_small.volatility_accumulator));
+    assert!(rate > FEE_RATE);
+    let moved = (fixture.pool().tick_current - tick_before).unsigned_abs() as u64;
+    assert_eq!(fixture.pool().volatility_accumulator, after_small.volatility_accumulator + moved);
+
+    // A violent move saturates at the cap
+    swap_fee_rate(&mut fixture, &trader, 20_000_000, true);
+    assert_eq!(swap_fee_rate(&mut fixture, &trader, 1_000, true), MAX_RATE);
+}
+
+#[test]
+fn the_surcharge_halves_every_half_life() {
+    let (mut fixture, trader) = pool_with_trader(true);
+    swap_fee_rate(&mut fixture, &trader, 1_000_000, true);
+    let volatility = fixture.pool().volatility_accumulator;
+    let start = fixture.svm.clock().unix_timestamp;
+    let quote = |fixture: &mut PoolFixture| {
+        let args = instruction::QuoteSwap { amount: 1_000, other_amount_threshold: 0, zero_for_one: true, sqrt_price_limit_x64: MIN_SQRT_PRICE, exact_input: true };
+        fixture.svm.process(&[ix::quote_swap(&fixture.keys, args)], &[]).unwrap().return_value::<SwapQuote>().fee_rate
+    };
+    assert_eq!(quote(&mut fixture), expected_rate(volatility));
+    fixture.svm.warp_to_timestamp(start + VOLATILITY_HALF_LIFE);
+    assert_eq!(quote(&mut fixture), expected_rate(volatility >> 1));
+    fixture.svm.warp_to_timestamp(start + 3 * VOLATILITY_HALF_LIFE);
+    assert_eq!(quote(&mut fixture), expected_rate(volatility >> 3));
+    fixture.svm.warp_to_timestamp(start + 100 * VOLATILITY_HALF_LIFE);
+    assert_eq!(quote(&mut fixture), FEE_RATE);
+    // The swap pays exactly what was quoted
+    assert_eq!(swap_fee_rate(&mut fixture, &trader, 1_000, true), FEE_RATE);
+}
+
+#[test]
+fn only_the_config_authority_sets_a_cap_between_the_base_rate_and_the_maximum() {
+    let (mut fixture, trader) = pool_with_trader(false);
+    let set = |authority, dynamic_fee_max_rate| ix::set_dynamic_fee(&fixture.keys, authority, instruction::SetDynamicFee { dynamic_fee_max_rate, dynamic_fee_rate_per_tick: 1 });
+    assert_eq!(fixture.svm.process(&[set(trader.key, MAX_RATE)], &[trader.key]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    assert_eq!(fixture.svm.process(&[set(fixture.admin, FEE_RATE - 1)], &[fixture.admin]).unwrap_err(), orca_error(ErrorCode::InvalidFeeRate));
+    assert_eq!(fixture.svm.process(&[set(fixture.admin, MAX_FEE_RATE + 1)], &[fixture.admin]).unwrap_err(), orca_error(ErrorCode::InvalidFeeRate));
+    fixture.svm.process(&[set(fixture.admin, MAX_RATE)], &[fixture.admin]).unwrap();
+    assert_eq!((fixture.pool().dynamic_fee_max_rate, fixture.pool().dynamic_fee_rate_per_tick), (MAX_RATE, 1));
+}
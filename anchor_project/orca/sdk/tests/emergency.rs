@@ -0,0 +1,213 @@
+//! In emergency mode a pool only lets liquidity leave, and pays it out of the
+//! vaults by stored liquidity and balances alone; leaving the mode takes a
+//! timelock. Limit orders can still be cancelled or claimed.
+
+mod common;
+
+use common::*;
+use orca::constants::{EMERGENCY_EXIT_TIMELOCK, MINIMUM_LIQUIDITY};
+use orca::errors::ErrorCode;
+use orca::instruction;
+use orca::instructions::liquidity::LiquidityResult;
+use orca_math::{tick_to_sqrt_price, Q64};
+use orca_sdk::instructions as ix;
+
+/// A share holder, a position holder and some trading so the vaults hold fees.
+fn traded_pool() -> (PoolFixture, User, User) {
+    let mut fixture = PoolFixture::new();
+    let holder = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&holder, 3_000_000, 3_000_000).unwrap();
+    let owner = fixture.user(10_000_000, 10_000_000);
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, owner.key)], &[owner.key]).unwrap();
+    let increase = ix::increase_position_liquidity(&fixture.keys, owner.key, owner.tokens, instruction::IncreasePositionLiquidity { amount_token_0: 1_000_000, amount_token_1: 1_000_000 });
+    fixture.svm.process(&[increase], &[owner.key]).unwrap();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    fixture.swap(&trader, 400_000, true).unwrap();
+    fixture.swap(&trader, 100_000, false).unwrap();
+    (fixture, holder, owner)
+}
+
+fn enter(fixture: &mut PoolFixture) {
+    fixture.svm.process(&[ix::enter_emergency_mode(&fixture.keys, fixture.admin)], &[fixture.admin]).unwrap();
+}
+
+#[test]
+fn emergency_mode_allows_nothing_but_emergency_withdrawals() {
+    let (mut fixture, holder, owner) = traded_pool();
+    enter(&mut fixture);
+    let disabled = orca_error(ErrorCode::PoolOperationDisabled);
+    assert_eq!(fixture.swap(&owner, 1_000, true).unwrap_err(), disabled);
+    assert_eq!(fixture.add_liquidity(&holder, 1_000, 1_000).unwrap_err(), disabled);
+    let remove = ix::remove_liquidity(&fixture.keys, holder.key, holder.tokens, holder.lp_token_account, instruction::RemoveLiquidity { lp_amount: 1_000 });
+    assert_eq!(fixture.svm.process(&[remove], &[holder.key]).unwrap_err(), disabled);
+    let decrease = ix::decrease_position_liquidity(&fixture.keys, owner.key, owner.tokens, instruction::DecreasePositionLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 });
+    assert_eq!(fixture.svm.process(&[decrease], &[owner.key]).unwrap_err(), disabled);
+    assert_eq!(fixture.svm.process(&[ix::collect_fees(&fixture.keys, owner.key, owner.tokens)], &[owner.key]).unwrap_err(), disabled);
+    // Status bits that would otherwise stop withdrawals do not trap liquidity
+    let all = ix::set_pool_status(&fixture.keys, fixture.admin, instruction::SetPoolStatus { status: orca::constants::POOL_STATUS_ALL });
+    fixture.svm.process(&[all], &[fixture.admin]).unwrap();
+    fixture.svm.process(&[ix::emergency_withdraw_position(&fixture.keys, owner.key, owner.tokens)], &[owner.key]).unwrap();
+}
+
+#[test]
+fn emergency_withdrawals_need_emergency_mode() {
+    let (mut fixture, holder, owner) = traded_pool();
+    let not_in_emergency = orca_error(ErrorCode::NotInEmergencyMode);
+    let remove = ix::emergency_remove_liquidity(&fixture.keys, holder.key, holder.tokens, holder.lp_token_account, instruction::EmergencyRemoveLiquidity { lp_amount: 1_000 });
+    assert_eq!(fixture.svm.process(&[remove], &[holder.key]).unwrap_err(), not_in_emergency);
+    let withdraw = ix::emergency_withdraw_position(&fixture.keys, owner.key, owner.tokens);
+    assert_eq!(fixture.svm.process(&[withdraw], &[owner.key]).unwrap_err(), not_in_emergency);
+}
+
+/// A position's fees, collected or not: `tokens_owed_*` plus what it earned since its entry.
+fn position_fees(pool: &orca_sdk::Pool, position: &orca_sdk::Position) -> (u128, u128) {
+    (
+        position.tokens_owed_0 as u128 + position.liquidity * (pool.fee_growth_global_0_x64 - position.fee_growth_entry_0_x64) / Q64,
+        position.tokens_owed_1 as u128 + position.liquidity * (pool.fee_growth_global_1_x64 - position.fee_growth_entry_1_x64) / Q64,
+    )
+}
+
+#[test]
+fn everyone_leaves_with_a_pro_rata_share_of_the_vaults_and_their_own_fees() {
+    let (mut fixture, holder, owner) = traded_pool();
+    enter(&mut fixture);
+    let pool = fixture.pool();
+    let vault_0 = fixture.svm.token_balance(&fixture.keys.token_vault_0);
+    let vault_1 = fixture.svm.token_balance(&fixture.keys.token_vault_1);
+    // Fees earned by shares are owed to share holders alone and stay out of the split by liquidity
+    let lp_fees_0 = pool.lp_fees_owed_0 as u128 + pool.lp_liquidity * (pool.fee_growth_global_0_x64 - pool.lp_fee_growth_entry_0_x64) / Q64;
+    let lp_fees_1 = pool.lp_fees_owed_1 as u128 + pool.lp_liquidity * (pool.fee_growth_global_1_x64 - pool.lp_fee_growth_entry_1_x64) / Q64;
+    assert!(lp_fees_0 > 0 && lp_fees_1 > 0);
+    // So do those owed to positions, which the pool records for all of them together, rounded up
+    let position_liquidity = pool.liquidity - pool.lp_liquidity;
+    let position_fees_0 = pool.position_fees_owed_0 as u128 + (position_liquidity * (pool.fee_growth_global_0_x64 - pool.position_fee_growth_entry_0_x64)).div_ceil(Q64);
+    let position_fees_1 = pool.position_fees_owed_1 as u128 + (position_liquidity * (pool.fee_growth_global_1_x64 - pool.position_fee_growth_entry_1_x64)).div_ceil(Q64);
+    let available_0 = (vault_0 - pool.protocol_fees_token_0) as u128 - lp_fees_0 - position_fees_0;
+    let available_1 = (vault_1 - pool.protocol_fees_token_1) as u128 - lp_fees_1 - position_fees_1;
+
+    // The position is paid its share of the rest and its own fees on top
+    let position_key = orca_sdk::pda::position(&fixture.keys.pool, &owner.key).0;
+    let position = fixture.svm.account::<orca_sdk::Position>(&position_key);
+    let (fees_0, fees_1) = position_fees(&pool, &position);
+    assert!(fees_0 > 0 && fees_1 > 0 && fees_0 <= position_fees_0 && fees_1 <= position_fees_1);
+    let withdraw = ix::emergency_withdraw_position(&fixture.keys, owner.key, owner.tokens);
+    let paid = fixture.svm.process(&[withdraw], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    assert_eq!(paid.amount_token_0 as u128, available_0 * position.liquidity / pool.liquidity + fees_0);
+    assert_eq!(paid.amount_token_1 as u128, available_1 * position.liquidity / pool.liquidity + fees_1);
+
+    // The share holder's payout includes their part of the share fees
+    let lp_amount = fixture.svm.token_balance(&holder.lp_token_account);
+    let total_shares = (fixture.svm.mint_supply(&fixture.keys.lp_mint) + MINIMUM_LIQUIDITY as u64) as u128;
+    let holder_liquidity = pool.lp_liquidity * lp_amount as u128 / total_shares;
+    let remove = ix::emergency_remove_liquidity(&fixture.keys, holder.key, holder.tokens, holder.lp_token_account, instruction::EmergencyRemoveLiquidity { lp_amount });
+    let paid = fixture.svm.process(&[remove], &[holder.key]).unwrap().return_value::<LiquidityResult>();
+    let remaining = pool.liquidity - position.liquidity;
+    let after_position_0 = available_0 - available_0 * position.liquidity / pool.liquidity;
+    let after_position_1 = available_1 - available_1 * position.liquidity / pool.liquidity;
+    assert_eq!(paid.amount_token_0 as u128, after_position_0 * holder_liquidity / remaining + lp_fees_0 * lp_amount as u128 / total_shares);
+    assert_eq!(paid.amount_token_1 as u128, after_position_1 * holder_liquidity / remaining + lp_fees_1 * lp_amount as u128 / total_shares);
+
+    // Only the locked minimum's share, the protocol's fees and rounding owed to no position are left behind
+    let after = fixture.pool();
+    assert_eq!(after.liquidity, MINIMUM_LIQUIDITY);
+    assert_eq!((after.position_fees_owed_0 as u128, after.position_fees_owed_1 as u128), (position_fees_0 - fees_0, position_fees_1 - fees_1));
+    assert!(after.position_fees_owed_0 <= 1 && after.position_fees_owed_1 <= 1);
+    let left_0 = fixture.svm.token_balance(&fixture.keys.token_vault_0) as u128 - pool.protocol_fees_token_0 as u128 - after.position_fees_owed_0 as u128;
+    let left_1 = fixture.svm.token_balance(&fixture.keys.token_vault_1) as u128 - pool.protocol_fees_token_1 as u128 - after.position_fees_owed_1 as u128;
+    assert!(left_0 <= available_0 * MINIMUM_LIQUIDITY / pool.liquidity + lp_fees_0 * MINIMUM_LIQUIDITY / total_shares + 2);
+    assert!(left_1 <= available_1 * MINIMUM_LIQUIDITY / pool.liquidity + lp_fees_1 * MINIMUM_LIQUIDITY / total_shares + 2);
+    // The position is closed out, fees included
+    let position = fixture.svm.account::<orca_sdk::Position>(&position_key);
+    assert_eq!((position.liquidity, position.tokens_owed_0, position.tokens_owed_1), (0, 0, 0));
+}
+
+#[test]
+fn a_partly_converted_order_is_held_out_of_the_split_at_its_stored_balances() {
+    let (mut fixture, holder, owner) = traded_pool();
+    let spacing = fixture.pool().tick_spacing as i32;
+    let tick_lower = fixture.pool().tick_current.div_euclid(spacing) * spacing + 2 * spacing;
+    let place = ix::place_limit_order(&fixture.keys, owner.key, owner.tokens, instruction::PlaceLimitOrder { tick_lower, amount: 100_000 });
+    fixture.svm.process(&[place], &[owner.key]).unwrap();
+    // A swap up to the middle of the order's range converts part of it to token 1
+    let trader = fixture.user(10_000_000, 10_000_000);
+    let sqrt_price_limit_x64 = tick_to_sqrt_price(tick_lower + spacing / 2).unwrap();
+    let args = instruction::Swap { amount_in: 10_000_000, minimum_amount_out: 0, zero_for_one: false, sqrt_price_limit_x64 };
+    fixture.svm.process(&[ix::swap(&fixture.keys, trader.key, trader.tokens.token_account_1, trader.tokens.token_account_0, args)], &[trader.key]).unwrap();
+    let range = fixture.svm.account::<orca_sdk::LimitOrderBook>(&fixture.keys.limit_order_book).ranges[0];
+    assert!(range.is_active() && range.amount_0 > 0 && range.amount_1 > 0);
+    enter(&mut fixture);
+
+    // The split leaves out what the range holds and the fees it owes, exactly as stored
+    let pool = fixture.pool();
+    let position = fixture.svm.account::<orca_sdk::Position>(&orca_sdk::pda::position(&fixture.keys.pool, &owner.key).0);
+    let (fees_0, _) = position_fees(&pool, &position);
+    let position_liquidity = pool.liquidity - pool.lp_liquidity;
+    let position_fees_0 = pool.position_fees_owed_0 as u128 + (position_liquidity * (pool.fee_growth_global_0_x64 - pool.position_fee_growth_entry_0_x64)).div_ceil(Q64);
+    let lp_fees_0 = pool.lp_fees_owed_0 as u128 + pool.lp_liquidity * (pool.fee_growth_global_0_x64 - pool.lp_fee_growth_entry_0_x64) / Q64;
+    let vault_0 = fixture.svm.token_balance(&fixture.keys.token_vault_0) as u128;
+    let available_0 = vault_0 - pool.protocol_fees_token_0 as u128 - lp_fees_0 - position_fees_0 - range.amount_0 as u128 - range.fees_owed_0 as u128;
+    let withdraw = ix::emergency_withdraw_position(&fixture.keys, owner.key, owner.tokens);
+    let paid = fixture.svm.process(&[withdraw], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    assert_eq!(paid.amount_token_0 as u128, available_0 * position.liquidity / pool.liquidity + fees_0);
+
+    // The order leaves with the range's balances, its only order, and the shares with the rest
+    let lp_amount = fixture.svm.token_balance(&holder.lp_token_account);
+    let remove = ix::emergency_remove_liquidity(&fixture.keys, holder.key, holder.tokens, holder.lp_token_account, instruction::EmergencyRemoveLiquidity { lp_amount });
+    fixture.svm.process(&[remove], &[holder.key]).unwrap();
+    let cancel = ix::cancel_limit_order(&fixture.keys, owner.key, owner.tokens, tick_lower);
+    let cancelled = fixture.svm.process(&[cancel], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    let order_fee_1 = (range.liquidity * range.fee_growth_1_x64 / Q64) as u64;
+    assert_eq!((cancelled.amount_token_0, cancelled.amount_token_1), (range.amount_0, range.amount_1 + order_fee_1));
+}
+
+#[test]
+fn limit_order_owners_can_cancel_in_emergency_mode() {
+    let (mut fixture, holder, owner) = traded_pool();
+    let spacing = fixture.pool().tick_spacing as i32;
+    let tick_lower = fixture.pool().tick_current.div_euclid(spacing) * spacing + 4 * spacing;
+    let place = ix::place_limit_order(&fixture.keys, owner.key, owner.tokens, instruction::PlaceLimitOrder { tick_lower, amount: 100_000 });
+    let placed = fixture.svm.process(&[place], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    enter(&mut fixture);
+    // Withdrawal status bits do not trap orders once the pool is in emergency mode
+    let all = ix::set_pool_status(&fixture.keys, fixture.admin, instruction::SetPoolStatus { status: orca::constants::POOL_STATUS_ALL });
+    fixture.svm.process(&[all], &[fixture.admin]).unwrap();
+    let cancel = ix::cancel_limit_order(&fixture.keys, owner.key, owner.tokens, tick_lower);
+    let cancelled = fixture.svm.process(&[cancel], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    // Above the frozen price the order is still all token 0, less rounding
+    assert_eq!(cancelled.amount_token_1, 0);
+    assert!(cancelled.amount_token_0 + 2 >= placed.amount_token_0 && cancelled.amount_token_0 <= placed.amount_token_0);
+    let order = orca_sdk::pda::limit_order(&fixture.keys.pool, &owner.key, tick_lower).0;
+    assert!(fixture.svm.try_account::<orca_sdk::LimitOrder>(&order).is_none());
+    // Everyone else still leaves through the emergency withdrawals
+    fixture.svm.process(&[ix::emergency_withdraw_position(&fixture.keys, owner.key, owner.tokens)], &[owner.key]).unwrap();
+    let lp_amount = fixture.svm.token_balance(&holder.lp_token_account);
+    let remove = ix::emergency_remove_liquidity(&fixture.keys, holder.key, holder.tokens, holder.lp_token_account, instruction::EmergencyRemoveLiquidity { lp_amount });
+    fixture.svm.process(&[remove], &[holder.key]).unwrap();
+}
+
+#[test]
+fn leaving_emergency_mode_waits_out_the_timelock() {
+    let (mut fixture, holder, _) = traded_pool();
+    let by_holder = ix::enter_emergency_mode(&fixture.keys, holder.key);
+    assert_eq!(fixture.svm.process(&[by_holder], &[holder.key]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    enter(&mut fixture);
+    let admin = fixture.admin;
+    let exit = [ix::exit_emergency_mode(&fixture.keys, admin)];
+    let timelocked = orca_error(ErrorCode::EmergencyExitTimelocked);
+    assert_eq!(fixture.svm.process(&exit, &[admin]).unwrap_err(), timelocked);
+
+    fixture.svm.process(&[ix::schedule_emergency_exit(&fixture.keys, admin)], &[admin]).unwrap();
+    let scheduled_at = fixture.svm.clock().unix_timestamp;
+    fixture.svm.warp_to_timestamp(scheduled_at + EMERGENCY_EXIT_TIMELOCK - 1);
+    assert_eq!(fixture.svm.process(&exit, &[admin]).unwrap_err(), timelocked);
+    // Entering again cancels the scheduled exit
+    enter(&mut fixture);
+    fixture.svm.warp_to_timestamp(scheduled_at + EMERGENCY_EXIT_TIMELOCK);
+    assert_eq!(fixture.svm.process(&exit, &[admin]).unwrap_err(), timelocked);
+
+    fixture.svm.process(&[ix::schedule_emergency_exit(&fixture.keys, admin)], &[admin]).unwrap();
+    fixture.svm.warp_to_timestamp(fixture.svm.clock().unix_timestamp + EMERGENCY_EXIT_TIMELOCK);
+    fixture.svm.process(&exit, &[admin]).unwrap();
+    assert_eq!(fixture.pool().emergency_mode, 0);
+    fixture.add_liquidity(&holder, 1_000, 1_000).unwrap();
+}
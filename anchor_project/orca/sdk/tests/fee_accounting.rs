@@ -0,0 +1,230 @@
+//! Several providers and traders sharing one pool: fees must be tracked
+//! exactly, shares must stay proportional, and the vaults must always cover
+//! what providers can withdraw.
+
+mod common;
+
+use common::*;
+use orca::events::Swap;
+use orca::instructions::liquidity::LiquidityResult;
+use orca::instructions::swap::SwapQuote;
+use orca::instruction;
+use orca_sdk::instructions as ix;
+
+const Q64: u128 = 1 << 64;
+
+#[test]
+fn fee_growth_and_protocol_fees_match_every_swap() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(100_000_000, 100_000_000);
+    let bob = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&alice, 5_000_000, 5_000_000).unwrap();
+    fixture.add_liquidity(&bob, 15_000_000, 15_000_000).unwrap();
+    let traders = [fixture.user(10_000_000, 10_000_000), fixture.user(10_000_000, 10_000_000)];
+    let (mut fees_0, mut fees_1, mut growth_0, mut growth_1) = (0u64, 0u64, 0u128, 0u128);
+    for (round, amount) in [10_000u64, 250_000, 7, 1_000_000, 33_333].into_iter().enumerate() {
+        let trader = traders[round % 2];
+        let zero_for_one = round % 2 == 0;
+        let liquidity = fixture.pool().liquidity;
+        let outcome = fixture.swap(&trader, amount, zero_for_one).unwrap();
+        let event = outcome.events::<Swap>().pop().unwrap();
+        assert_eq!(event.user, trader.key);
+        assert_eq!(event.amount_in, amount);
+        assert_eq!(event.fee_amount as u128 * 1_000_000, (amount as u128 * FEE_RATE as u128).next_multiple_of(1_000_000));
+        let protocol_fee = event.fee_amount * PROTOCOL_FEE_RATE as u64 / 10_000;
+        let lp_fee = event.fee_amount - protocol_fee;
+        if zero_for_one {
+            fees_0 += protocol_fee;
+            growth_0 += ((lp_fee as u128) << 64) / liquidity;
+        } else {
+            fees_1 += protocol_fee;
+            growth_1 += ((lp_fee as u128) << 64) / liquidity;
+        }
+    }
+    let pool = fixture.pool();
+    assert_eq!((pool.protocol_fees_token_0, pool.protocol_fees_token_1), (fees_0, fees_1));
+    assert_eq!((pool.fee_growth_global_0_x64, pool.fee_growth_global_1_x64), (growth_0, growth_1));
+}
+
+#[test]
+fn each_fee_is_credited_once_between_the_protocol_and_liquidity() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+    let fee = fixture.swap(&trader, 1_000_000, true).unwrap().events::<Swap>().pop().unwrap().fee_amount as u128;
+    let pool = fixture.pool();
+    let protocol_fee = pool.protocol_fees_token_0 as u128;
+    let lp_fee = pool.liquidity * pool.fee_growth_global_0_x64 / Q64;
+    assert!(protocol_fee > 0 && lp_fee > 0);
+    // Together the claims never exceed the fee charged, losing at most a unit to rounding
+    assert!(protocol_fee + lp_fee <= fee && fee - (protocol_fee + lp_fee) <= 1);
+}
+
+#[test]
+fn swap_results_are_returned_and_match_the_transfers() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    let quote_args = instruction::QuoteSwap { amount: 40_000, other_amount_threshold: 0, zero_for_one: false, sqrt_price_limit_x64: orca::constants::MAX_SQRT_PRICE, exact_input: true };
+    let quote = fixture.svm.process(&[ix::quote_swap(&fixture.keys, quote_args)], &[]).unwrap().return_value::<SwapQuote>();
+    let result = fixture.swap(&trader, 40_000, false).unwrap().return_value::<SwapQuote>();
+    assert_eq!(result, quote);
+    assert_eq!(fixture.svm.token_balance(&trader.tokens.token_account_1), 1_000_000 - result.amount_in);
+    assert_eq!(fixture.svm.token_balance(&trader.tokens.token_account_0), 1_000_000 + result.amount_out);
+    assert_eq!(fixture.pool().sqrt_price_x64, result.sqrt_price_after_x64);
+}
+
+#[test]
+fn shares_stay_proportional_to_deposits() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(100_000_000, 100_000_000);
+    let bob = fixture.user(100_000_000, 100_000_000);
+    let first = fixture.add_liquidity(&alice, 1_000_000, 1_000_000).unwrap().return_value::<LiquidityResult>();
+    // The first depositor's shares are short by the locked minimum
+    assert_eq!(first.lp_amount as u128, first.liquidity_delta - orca::constants::MINIMUM_LIQUIDITY);
+    let second = fixture.add_liquidity(&bob, 3_000_000, 3_000_000).unwrap().return_value::<LiquidityResult>();
+    assert_eq!(second.liquidity_delta, first.liquidity_delta * 3);
+    assert_eq!(second.lp_amount as u128, second.liquidity_delta);
+    assert_eq!(fixture.svm.mint_supply(&fixture.keys.lp_mint), first.lp_amount + second.lp_amount);
+}
+
+#[test]
+fn vaults_cover_every_withdrawal_after_trading() {
+    let mut fixture = PoolFixture::new();
+    let providers = [fixture.user(100_000_000, 100_000_000), fixture.user(100_000_000, 100_000_000), fixture.user(100_000_000, 100_000_000)];
+    for (provider, amount) in providers.iter().zip([2_000_000u64, 5_000_000, 11_000_000]) {
+        fixture.add_liquidity(provider, amount, amount).unwrap();
+    }
+    let trader = fixture.user(50_000_000, 50_000_000);
+    for round in 0..20u64 {
+        fixture.swap(&trader, 10_000 + round * 37_111, round % 3 != 0).unwrap();
+    }
+    let mut withdrawn = Vec::new();
+    for provider in &providers {
+        let lp_amount = fixture.svm.token_balance(&provider.lp_token_account);
+        let remove = ix::remove_liquidity(&fixture.keys, provider.key, provider.tokens, provider.lp_token_account, instruction::RemoveLiquidity { lp_amount });
+        withdrawn.push(fixture.svm.process(&[remove], &[provider.key]).unwrap().return_value::<LiquidityResult>());
+    }
+    // Payouts follow the share split: the larger providers get proportionally more of each token
+    for pair in withdrawn.windows(2) {
+        assert!(pair[1].amount_token_0 > pair[0].amount_token_0 && pair[1].amount_token_1 > pair[0].amount_token_1);
+    }
+    let pool = fixture.pool();
+    assert_eq!(pool.lp_liquidity, orca::constants::MINIMUM_LIQUIDITY);
+    // Whatever is left backs the locked shares and the fees charged along the way
+    assert!(fixture.svm.token_balance(&fixture.keys.token_vault_0) >= pool.protocol_fees_token_0);
+    assert!(fixture.svm.token_balance(&fixture.keys.token_vault_1) >= pool.protocol_fees_token_1);
+}
+
+#[test]
+fn positions_snapshot_fee_growth_when_they_join() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+    let (early, late) = (fixture.user(10_000_000, 10_000_000), fixture.user(10_000_000, 10_000_000));
+    let deposit = |fixture: &mut PoolFixture, owner: &User| {
+        fixture.svm.process(&[ix::open_position(fixture.keys.pool, owner.key)], &[owner.key]).unwrap();
+        let args = instruction::IncreasePositionLiquidity { amount_token_0: 1_000_000, amount_token_1: 1_000_000 };
+        fixture.svm.process(&[ix::increase_position_liquidity(&fixture.keys, owner.key, owner.tokens, args)], &[owner.key]).unwrap();
+    };
+    deposit(&mut fixture, &early);
+    fixture.swap(&trader, 500_000, true).unwrap();
+    let growth_at_join = fixture.pool().fee_growth_global_0_x64;
+    deposit(&mut fixture, &late);
+    fixture.swap(&trader, 500_000, true).unwrap();
+
+    let position = |fixture: &PoolFixture, owner: &User| fixture.svm.account::<orca_sdk::Position>(&orca_sdk::pda::position(&fixture.keys.pool, &owner.key).0);
+    let (early_position, late_position) = (position(&fixture, &early), position(&fixture, &late));
+    assert_eq!(early_position.fee_growth_entry_0_x64, 0);
+    assert_eq!(late_position.fee_growth_entry_0_x64, growth_at_join);
+    // Per unit of liquidity, only the early position earns the first swap's fees
+    let growth = fixture.pool().fee_growth_global_0_x64;
+    let owed_per_liquidity = |entry: u128| (growth - entry) * 1_000_000 / Q64;
+    assert!(owed_per_liquidity(early_position.fee_growth_entry_0_x64) > owed_per_liquidity(late_position.fee_growth_entry_0_x64));
+    assert!(owed_per_liquidity(late_position.fee_growth_entry_0_x64) > 0);
+}
+
+#[test]
+fn positions_collect_exactly_what_they_accrued() {
+    let mut fixture = PoolFixture::new();
+    let owner = fixture.user(10_000_000, 10_000_000);
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, owner.key)], &[owner.key]).unwrap();
+    let args = instruction::IncreasePositionLiquidity { amount_token_0: 5_000_000, amount_token_1: 5_000_000 };
+    fixture.svm.process(&[ix::increase_position_liquidity(&fixture.keys, owner.key, owner.tokens, args)], &[owner.key]).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+    fixture.swap(&trader, 300_000, true).unwrap();
+    fixture.swap(&trader, 200_000, false).unwrap();
+
+    let pool = fixture.pool();
+    let position = fixture.svm.account::<orca_sdk::Position>(&orca_sdk::pda::position(&fixture.keys.pool, &owner.key).0);
+    let expected_0 = (position.liquidity * pool.fee_growth_global_0_x64 / Q64) as u64;
+    let expected_1 = (position.liquidity * pool.fee_growth_global_1_x64 / Q64) as u64;
+    assert!(expected_0 > 0 && expected_1 > 0);
+    let (balance_0, balance_1) = (fixture.svm.token_balance(&owner.tokens.token_account_0), fixture.svm.token_balance(&owner.tokens.token_account_1));
+    let collect = ix::collect_fees(&fixture.keys, owner.key, owner.tokens);
+    let outcome = fixture.svm.process(std::slice::from_ref(&collect), &[owner.key]).unwrap();
+    let collected = outcome.return_value::<LiquidityResult>();
+    assert_eq!((collected.amount_token_0, collected.amount_token_1), (expected_0, expected_1));
+    assert_eq!(outcome.events::<orca::events::FeesCollected>().len(), 1);
+    assert_eq!(fixture.svm.token_balance(&owner.tokens.token_account_0), balance_0 + expected_0);
+    assert_eq!(fixture.svm.token_balance(&owner.tokens.token_account_1), balance_1 + expected_1);
+    // Nothing is left to collect a second time
+    let again = fixture.svm.process(&[collect], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    assert_eq!((again.amount_token_0, again.amount_token_1), (0, 0));
+}
+
+#[test]
+fn increasing_a_position_keeps_the_fees_it_already_earned() {
+    let mut fixture = PoolFixture::new();
+    let owner = fixture.user(10_000_000, 10_000_000);
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, owner.key)], &[owner.key]).unwrap();
+    let args = instruction::IncreasePositionLiquidity { amount_token_0: 1_000_000, amount_token_1: 1_000_000 };
+    let increase = ix::increase_position_liquidity(&fixture.keys, owner.key, owner.tokens, args);
+    fixture.svm.process(std::slice::from_ref(&increase), &[owner.key]).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+    fixture.swap(&trader, 500_000, true).unwrap();
+
+    let key = orca_sdk::pda::position(&fixture.keys.pool, &owner.key).0;
+    let growth = fixture.pool().fee_growth_global_0_x64;
+    let earned = (fixture.svm.account::<orca_sdk::Position>(&key).liquidity * growth / Q64) as u64;
+    assert!(earned > 0);
+    fixture.svm.process(&[increase], &[owner.key]).unwrap();
+    // The fees earned at the old liquidity are owed before the entry moves up
+    let position = fixture.svm.account::<orca_sdk::Position>(&key);
+    assert_eq!((position.tokens_owed_0, position.fee_growth_entry_0_x64), (earned, growth));
+}
+
+#[test]
+fn share_holders_who_exit_receive_their_fee_share() {
+    let mut fixture = PoolFixture::new();
+    let early = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&early, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+    fixture.swap(&trader, 1_000_000, true).unwrap();
+    fixture.swap(&trader, 400_000, false).unwrap();
+
+    // A late holder buys into the fees already owed to shares, so it cannot take them from the early one
+    let late = fixture.user(100_000_000, 100_000_000);
+    let pool = fixture.pool();
+    let joined = fixture.add_liquidity(&late, 10_000_000, 10_000_000).unwrap().return_value::<LiquidityResult>();
+    let (principal_0, _) = orca_math::amounts_for_liquidity(pool.sqrt_price_x64, joined.liquidity_delta, true).unwrap();
+    assert!(joined.amount_token_0 > principal_0);
+    let owed = fixture.pool().lp_fees_owed_0;
+    assert!(owed > 0);
+
+    let pool = fixture.pool();
+    let lp_amount = fixture.svm.token_balance(&early.lp_token_account);
+    let total_shares = fixture.svm.mint_supply(&fixture.keys.lp_mint) as u128 + orca::constants::MINIMUM_LIQUIDITY;
+    let delta = lp_amount as u128 * pool.lp_liquidity / total_shares;
+    let (principal_0, principal_1) = orca_math::amounts_for_liquidity(pool.sqrt_price_x64, delta, false).unwrap();
+    let fees_0 = (pool.lp_fees_owed_0 as u128 * lp_amount as u128 / total_shares) as u64;
+    let fees_1 = (pool.lp_fees_owed_1 as u128 * lp_amount as u128 / total_shares) as u64;
+    assert!(fees_0 > 0 && fees_1 > 0);
+    let remove = ix::remove_liquidity(&fixture.keys, early.key, early.tokens, early.lp_token_account, instruction::RemoveLiquidity { lp_amount });
+    let paid = fixture.svm.process(&[remove], &[early.key]).unwrap().return_value::<LiquidityResult>();
+    assert_eq!((paid.amount_token_0, paid.amount_token_1), (principal_0 + fees_0, principal_1 + fees_1));
+    assert_eq!(fixture.pool().lp_fees_owed_0, pool.lp_fees_owed_0 - fees_0);
+}
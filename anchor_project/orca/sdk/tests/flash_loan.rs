@@ -0,0 +1,109 @@
+//! Flash loans must be repaid with their fee in the same transaction, and
+//! nothing else may touch the pool while one is outstanding.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use common::*;
+use orca::errors::ErrorCode;
+use orca::events::{FlashBorrow, FlashLoan};
+use orca::instruction;
+use orca_sdk::instructions as ix;
+
+fn funded_pool() -> (PoolFixture, User) {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&provider, 1_000_000, 1_000_000).unwrap();
+    let borrower = fixture.user(10_000, 10_000);
+    (fixture, borrower)
+}
+
+fn borrow(fixture: &PoolFixture, user: &User, amount_token_0: u64, amount_token_1: u64) -> Instruction {
+    ix::flash_borrow(&fixture.keys, user.key, user.tokens, instruction::FlashBorrow { amount_token_0, amount_token_1 })
+}
+
+fn repay(fixture: &PoolFixture, user: &User) -> Instruction {
+    ix::flash_repay(&fixture.keys, user.key, user.tokens)
+}
+
+/// The flash fee rounds up.
+fn flash_fee(amount: u64, rate: u16) -> u64 {
+    (amount as u128 * rate as u128).div_ceil(1_000_000) as u64
+}
+
+#[test]
+fn repaid_loans_credit_their_fee_to_lp_fee_growth() {
+    let (mut fixture, borrower) = funded_pool();
+    let before = fixture.pool();
+    let vault_0 = fixture.svm.token_balance(&fixture.keys.token_vault_0);
+    let vault_1 = fixture.svm.token_balance(&fixture.keys.token_vault_1);
+    let outcome = fixture.svm.process(&[borrow(&fixture, &borrower, 500_000, 3), repay(&fixture, &borrower)], &[borrower.key]).unwrap();
+
+    let (fee_0, fee_1) = (flash_fee(500_000, FEE_RATE), flash_fee(3, FEE_RATE));
+    assert_eq!((fee_0, fee_1), (1_500, 1));
+    let drawn = outcome.events::<FlashBorrow>().pop().unwrap();
+    assert_eq!((drawn.pool, drawn.borrower, drawn.amount_token_0, drawn.amount_token_1), (fixture.keys.pool, borrower.key, 500_000, 3));
+    let event = outcome.events::<FlashLoan>().pop().unwrap();
+    assert_eq!((event.amount_token_0, event.amount_token_1, event.fee_token_0, event.fee_token_1), (500_000, 3, fee_0, fee_1));
+    assert_eq!(fixture.svm.token_balance(&fixture.keys.token_vault_0), vault_0 + fee_0);
+    assert_eq!(fixture.svm.token_balance(&fixture.keys.token_vault_1), vault_1 + fee_1);
+    let after = fixture.pool();
+    assert_eq!(after.fee_growth_global_0_x64, before.fee_growth_global_0_x64 + ((fee_0 as u128) << 64) / before.liquidity);
+    assert_eq!(after.fee_growth_global_1_x64, before.fee_growth_global_1_x64 + ((fee_1 as u128) << 64) / before.liquidity);
+    assert_eq!(after.protocol_fees_token_0, before.protocol_fees_token_0);
+    assert_eq!(after.flash_loan_locked, 0);
+    assert_eq!((after.flash_loan_amount_0, after.flash_loan_amount_1), (0, 0));
+}
+
+#[test]
+fn borrowing_without_a_later_repay_is_rejected() {
+    let (mut fixture, borrower) = funded_pool();
+    let alone = borrow(&fixture, &borrower, 1_000, 0);
+    assert_eq!(fixture.svm.process(&[alone], &[borrower.key]).unwrap_err(), orca_error(ErrorCode::FlashLoanNotRepaid));
+    // A repay ahead of the borrow does not count, and has nothing to repay
+    let reversed = [repay(&fixture, &borrower), borrow(&fixture, &borrower, 1_000, 0)];
+    assert_eq!(fixture.svm.process(&reversed, &[borrower.key]).unwrap_err(), orca_error(ErrorCode::NoFlashLoanOutstanding));
+    // A repay of some other pool does not count either
+    let mut other = repay(&fixture, &borrower);
+    other.accounts[0].pubkey = Pubkey::new_unique();
+    assert_eq!(fixture.svm.process(&[borrow(&fixture, &borrower, 1_000, 0), other], &[borrower.key]).unwrap_err(), orca_error(ErrorCode::FlashLoanNotRepaid));
+}
+
+#[test]
+fn a_loan_the_borrower_cannot_repay_in_full_reverts() {
+    let (mut fixture, borrower) = funded_pool();
+    let vault_0 = fixture.svm.token_balance(&fixture.keys.token_vault_0);
+    // The fee on 5_000_000 is more than the borrower's 10_000 spare tokens
+    assert!(fixture.svm.process(&[borrow(&fixture, &borrower, 5_000_000, 0), repay(&fixture, &borrower)], &[borrower.key]).is_err());
+    assert_eq!(fixture.svm.token_balance(&fixture.keys.token_vault_0), vault_0);
+    assert_eq!(fixture.pool().flash_loan_locked, 0);
+}
+
+#[test]
+fn the_pool_is_locked_while_a_loan_is_outstanding() {
+    let (mut fixture, borrower) = funded_pool();
+    let locked = orca_error(ErrorCode::PoolLocked);
+    let args = instruction::Swap { amount_in: 1_000, minimum_amount_out: 0, zero_for_one: true, sqrt_price_limit_x64: orca::constants::MIN_SQRT_PRICE };
+    let swap = ix::swap(&fixture.keys, borrower.key, borrower.tokens.token_account_0, borrower.tokens.token_account_1, args);
+    let add = ix::add_liquidity(&fixture.keys, borrower.key, borrower.tokens, borrower.lp_token_account, instruction::AddLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 });
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, borrower.key)], &[borrower.key]).unwrap();
+    let increase = ix::increase_position_liquidity(&fixture.keys, borrower.key, borrower.tokens, instruction::IncreasePositionLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 });
+    let second_borrow = borrow(&fixture, &borrower, 1, 0);
+    for inner in [swap, add, increase, second_borrow] {
+        let transaction = [borrow(&fixture, &borrower, 100_000, 100_000), inner, repay(&fixture, &borrower)];
+        assert_eq!(fixture.svm.process(&transaction, &[borrower.key]).unwrap_err(), locked);
+    }
+}
+
+#[test]
+fn only_the_config_authority_sets_the_flash_fee_rate() {
+    let (mut fixture, borrower) = funded_pool();
+    let set = |authority| ix::set_flash_fee_rate(fixture.keys.amm_config, authority, instruction::SetFlashFeeRate { flash_fee_rate: 100 });
+    assert_eq!(fixture.svm.process(&[set(borrower.key)], &[borrower.key]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    let too_high = ix::set_flash_fee_rate(fixture.keys.amm_config, fixture.admin, instruction::SetFlashFeeRate { flash_fee_rate: orca::constants::MAX_FEE_RATE + 1 });
+    assert_eq!(fixture.svm.process(&[too_high], &[fixture.admin]).unwrap_err(), orca_error(ErrorCode::InvalidFeeRate));
+    fixture.svm.process(&[set(fixture.admin)], &[fixture.admin]).unwrap();
+    let outcome = fixture.svm.process(&[borrow(&fixture, &borrower, 500_000, 0), repay(&fixture, &borrower)], &[borrower.key]).unwrap();
+    assert_eq!(outcome.events::<FlashLoan>()[0].fee_token_0, flash_fee(500_000, 100));
+}
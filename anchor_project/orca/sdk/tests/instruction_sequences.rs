@@ -0,0 +1,71 @@
+//! Random interleavings of liquidity, position, limit-order and swap instructions by
+//! several actors. `fuzz/` runs the same sequences under libFuzzer for longer.
+
+mod common;
+
+use arbitrary::{Arbitrary, Unstructured};
+use common::sequence::{self, Action};
+use proptest::prelude::*;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn random_sequences_keep_the_pool_solvent(bytes in proptest::collection::vec(any::<u8>(), 0..2048)) {
+        let actions = Vec::<Action>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        sequence::run(&actions);
+    }
+}
+
+#[test]
+fn positions_and_shares_can_all_exit_after_heavy_trading() {
+    let mut actions = vec![
+        Action::AddLiquidity { actor: 0, amount_0: 5_000_000, amount_1: 5_000_000 },
+        Action::OpenPosition { actor: 1 },
+        Action::IncreasePosition { actor: 1, amount_0: 3_000_000, amount_1: 3_000_000 },
+        Action::OpenPosition { actor: 2 },
+    ];
+    for round in 0..40u32 {
+        actions.push(Action::Swap { actor: 3, amount_in: 50_000 + round * 10_007, zero_for_one: round % 3 == 0 });
+        actions.push(Action::Warp { seconds: 7 });
+        if round == 20 {
+            actions.push(Action::IncreasePosition { actor: 2, amount_0: 1_000_000, amount_1: 1_000_000 });
+            actions.push(Action::RemoveLiquidity { actor: 0, share_bps: 5_000 });
+            actions.push(Action::CollectFees { actor: 1 });
+        }
+    }
+    sequence::run(&actions);
+}
+
+#[test]
+fn positions_alone_are_owed_no_more_than_swaps_paid() {
+    // Without share-backed liquidity there is no unclaimed fee surplus in the vaults to hide an overpayment
+    let mut actions = Vec::new();
+    for actor in 0..3u8 {
+        actions.push(Action::OpenPosition { actor });
+        actions.push(Action::IncreasePosition { actor, amount_0: 2_000_000 + actor as u32 * 777_777, amount_1: 2_000_000 + actor as u32 * 777_777 });
+    }
+    for round in 0..60u32 {
+        actions.push(Action::Swap { actor: 3, amount_in: 20_000 + round * 3_331, zero_for_one: round % 2 == 0 });
+        if round % 15 == 0 {
+            actions.push(Action::CollectFees { actor: (round % 3) as u8 });
+        }
+    }
+    sequence::run(&actions);
+}
+
+#[test]
+fn limit_orders_fill_and_close_without_draining_the_pool() {
+    let mut actions = vec![Action::AddLiquidity { actor: 0, amount_0: 5_000_000, amount_1: 5_000_000 }];
+    for (actor, offset) in [(1u8, 1i8), (2, 1), (1, -2), (2, 3)] {
+        actions.push(Action::PlaceLimitOrder { actor, offset, amount: 40_000 + offset.unsigned_abs() as u32 * 1_111 });
+    }
+    for round in 0..30u32 {
+        actions.push(Action::Swap { actor: 3, amount_in: 30_000 + round * 4_999, zero_for_one: round % 4 == 3 });
+        if round == 12 {
+            actions.push(Action::ClaimLimitOrder { actor: 1, keeper: 3, slot: 0 });
+            actions.push(Action::CancelLimitOrder { actor: 2, slot: 3 });
+        }
+    }
+    sequence::run(&actions);
+}
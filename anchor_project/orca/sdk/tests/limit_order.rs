@@ -0,0 +1,185 @@
+//! Limit orders are liquidity on a single tick-spacing range on one side of
+//! the price. A swap that carries the price out through the far side fills
+//! the range: it leaves the curve fully converted until claimed.
+
+mod common;
+
+use anchor_lang::prelude::ProgramError;
+use anchor_lang::InstructionData;
+use common::*;
+use orca::errors::ErrorCode;
+use orca::constants::DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY;
+use orca::events::{LimitOrderClosed, LimitOrderMinLiquidityUpdated, LimitOrderRangeFilled};
+use orca::instruction;
+use orca::instructions::liquidity::LiquidityResult;
+use orca::instructions::swap::SwapQuote;
+use orca_math::{get_amount_0_delta, get_amount_1_delta, tick_to_sqrt_price};
+use orca_sdk::instructions as ix;
+use orca_sdk::{pda, LimitOrder, LimitOrderBook};
+
+const SPACING: i32 = 64;
+
+fn funded_pool() -> (PoolFixture, User) {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(100_000_000, 100_000_000);
+    (fixture, trader)
+}
+
+fn place(fixture: &mut PoolFixture, owner: &User, tick_lower: i32, amount: u64) -> Result<LiquidityResult, ProgramError> {
+    let place = ix::place_limit_order(&fixture.keys, owner.key, owner.tokens, instruction::PlaceLimitOrder { tick_lower, amount });
+    Ok(fixture.svm.process(&[place], &[owner.key])?.return_value())
+}
+
+fn book(fixture: &PoolFixture) -> LimitOrderBook {
+    fixture.svm.account(&fixture.keys.limit_order_book)
+}
+
+fn sqrt_prices(tick_lower: i32) -> (u128, u128) {
+    (tick_to_sqrt_price(tick_lower).unwrap(), tick_to_sqrt_price(tick_lower + SPACING).unwrap())
+}
+
+#[test]
+fn crossing_fills_the_order_and_a_keeper_claims_it_for_the_owner() {
+    let (mut fixture, trader) = funded_pool();
+    let owner = fixture.user(10_000_000, 10_000_000);
+    let placed = place(&mut fixture, &owner, SPACING, 100_000).unwrap();
+    // Above the price the order is all token 0
+    assert!(placed.amount_token_0 > 0 && placed.amount_token_0 <= 100_000 && placed.amount_token_1 == 0);
+    let range = book(&fixture).ranges[0];
+    assert!(range.sells_token_0 == 1 && range.filled == 0 && range.open_orders == 1 && range.liquidity == placed.liquidity_delta);
+
+    let outcome = fixture.swap(&trader, 500_000, false).unwrap();
+    let filled = outcome.events::<LimitOrderRangeFilled>();
+    assert_eq!(filled.len(), 1);
+    assert_eq!((filled[0].tick_lower, filled[0].liquidity), (SPACING, placed.liquidity_delta));
+    let (lower, upper) = sqrt_prices(SPACING);
+    assert!(fixture.pool().sqrt_price_x64 > upper);
+    assert_eq!(book(&fixture).ranges[0].filled, 1);
+
+    // A swap back down passes the frozen range by without buying it back
+    fixture.swap(&trader, 600_000, true).unwrap();
+    assert!(fixture.pool().sqrt_price_x64 < lower);
+
+    let keeper = fixture.user(0, 0);
+    let rent = fixture.svm.lamports(&pda::limit_order(&fixture.keys.pool, &owner.key, SPACING).0);
+    let lamports = fixture.svm.lamports(&owner.key);
+    let balance_1 = fixture.svm.token_balance(&owner.tokens.token_account_1);
+    let claim = ix::claim_limit_order(&fixture.keys, keeper.key, owner.key, owner.tokens, SPACING);
+    let outcome = fixture.svm.process(&[claim], &[keeper.key]).unwrap();
+    let closed = outcome.events::<LimitOrderClosed>().pop().unwrap();
+    let proceeds = get_amount_1_delta(lower, upper, placed.liquidity_delta, false).unwrap() as u64;
+    assert!(closed.filled && closed.fee_token_1 > 0 && closed.fee_token_0 == 0);
+    assert_eq!(closed.amount_token_1, proceeds + closed.fee_token_1);
+    assert_eq!(fixture.svm.token_balance(&owner.tokens.token_account_1), balance_1 + closed.amount_token_1);
+    // Selling above the price means receiving more of token 1 than token 0 was placed
+    assert!(proceeds > placed.amount_token_0);
+    assert_eq!(fixture.svm.lamports(&owner.key), lamports + rent);
+    assert!(fixture.svm.try_account::<LimitOrder>(&pda::limit_order(&fixture.keys.pool, &owner.key, SPACING).0).is_none());
+    assert_eq!(book(&fixture).ranges[0], Default::default());
+}
+
+#[test]
+fn orders_below_the_price_sell_token_1_and_fill_on_the_way_down() {
+    let (mut fixture, trader) = funded_pool();
+    let owner = fixture.user(10_000_000, 10_000_000);
+    let placed = place(&mut fixture, &owner, -2 * SPACING, 100_000).unwrap();
+    assert!(placed.amount_token_0 == 0 && placed.amount_token_1 > 0);
+    fixture.swap(&trader, 500_000, true).unwrap();
+    assert_eq!(book(&fixture).ranges[0].filled, 1);
+    let claim = ix::claim_limit_order(&fixture.keys, owner.key, owner.key, owner.tokens, -2 * SPACING);
+    let claimed = fixture.svm.process(&[claim], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    let (lower, upper) = sqrt_prices(-2 * SPACING);
+    let proceeds = get_amount_0_delta(lower, upper, placed.liquidity_delta, false).unwrap() as u64;
+    assert!(claimed.amount_token_0 > proceeds && claimed.amount_token_1 == 0);
+}
+
+#[test]
+fn a_partly_crossed_order_converts_back_and_is_cancelled_for_what_its_range_holds() {
+    let (mut fixture, trader) = funded_pool();
+    let owner = fixture.user(10_000_000, 10_000_000);
+    let placed = place(&mut fixture, &owner, SPACING, 100_000).unwrap();
+    let (lower, upper) = sqrt_prices(SPACING);
+    // Into the range but not through it
+    fixture.swap(&trader, 90_000, false).unwrap();
+    let price = fixture.pool().sqrt_price_x64;
+    assert!(price > lower && price < upper);
+    let claim = ix::claim_limit_order(&fixture.keys, owner.key, owner.key, owner.tokens, SPACING);
+    assert_eq!(fixture.svm.process(&[claim], &[owner.key]).unwrap_err(), orca_error(ErrorCode::LimitOrderNotFilled));
+    // New orders cannot join a range the price is inside
+    let late = fixture.user(10_000_000, 10_000_000);
+    assert_eq!(place(&mut fixture, &late, SPACING, 1_000).unwrap_err(), orca_error(ErrorCode::InvalidPositionRange));
+
+    fixture.swap(&trader, 200_000, true).unwrap();
+    assert!(fixture.pool().sqrt_price_x64 < lower);
+    assert_eq!(book(&fixture).ranges[0].filled, 0);
+    let stranger = fixture.user(0, 0);
+    let mut cancel = ix::claim_limit_order(&fixture.keys, stranger.key, owner.key, owner.tokens, SPACING);
+    cancel.data = instruction::CancelLimitOrder {}.data();
+    assert_eq!(fixture.svm.process(&[cancel], &[stranger.key]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    let range = book(&fixture).ranges[0];
+    let cancel = ix::cancel_limit_order(&fixture.keys, owner.key, owner.tokens, SPACING);
+    let outcome = fixture.svm.process(&[cancel], &[owner.key]).unwrap();
+    let cancelled = outcome.return_value::<LiquidityResult>();
+    // Back below the range the order is token 0 again, as its range stores it, plus the fees it earned both ways
+    let closed = outcome.events::<LimitOrderClosed>().pop().unwrap();
+    assert_eq!((closed.amount_token_0 - closed.fee_token_0, closed.amount_token_1 - closed.fee_token_1), (range.amount_0, range.amount_1));
+    assert!(cancelled.amount_token_0 + 2 >= placed.amount_token_0 && cancelled.amount_token_1 > 0);
+}
+
+#[test]
+fn swaps_through_orders_match_their_quotes() {
+    let (mut fixture, trader) = funded_pool();
+    let owner = fixture.user(10_000_000, 10_000_000);
+    for tick_lower in [SPACING, 3 * SPACING, 4 * SPACING] {
+        place(&mut fixture, &owner, tick_lower, 100_000).unwrap();
+    }
+    let quote_args = instruction::QuoteSwap { amount: 600_000, other_amount_threshold: 0, zero_for_one: false, sqrt_price_limit_x64: orca::constants::MAX_SQRT_PRICE, exact_input: true };
+    let quote = fixture.svm.process(&[ix::quote_swap(&fixture.keys, quote_args)], &[]).unwrap().return_value::<SwapQuote>();
+    let outcome = fixture.swap(&trader, 600_000, false).unwrap();
+    assert_eq!(outcome.return_value::<SwapQuote>(), quote);
+    assert_eq!(outcome.events::<LimitOrderRangeFilled>().len(), 3);
+    // The same trade with no orders in the way moves the price further for the same input
+    let (mut bare, bare_trader) = funded_pool();
+    let bare_quote = bare.swap(&bare_trader, 600_000, false).unwrap().return_value::<SwapQuote>();
+    assert!(bare_quote.sqrt_price_after_x64 > quote.sqrt_price_after_x64);
+}
+
+#[test]
+fn orders_must_sit_on_one_aligned_range_and_the_book_is_bounded() {
+    let (mut fixture, _) = funded_pool();
+    let owner = fixture.user(100_000_000, 100_000_000);
+    assert_eq!(place(&mut fixture, &owner, SPACING + 1, 10_000).unwrap_err(), orca_error(ErrorCode::TickNotAligned));
+    // The price sits exactly on tick 0, the upper bound of [-64, 0], so that range is below it
+    assert_eq!(place(&mut fixture, &owner, -SPACING, 10_000).unwrap().amount_token_0, 0);
+    for index in 1..LimitOrderBook::CAPACITY as i32 {
+        place(&mut fixture, &owner, index * SPACING, 10_000).unwrap();
+    }
+    assert_eq!(place(&mut fixture, &owner, 100 * SPACING, 10_000).unwrap_err(), orca_error(ErrorCode::LimitOrderBookFull));
+    // Joining a range already in the book takes no new slot
+    let other = fixture.user(100_000_000, 100_000_000);
+    place(&mut fixture, &other, SPACING, 10_000).unwrap();
+    assert_eq!(book(&fixture).ranges[1].open_orders, 2);
+}
+
+#[test]
+fn orders_below_the_pools_minimum_are_refused_until_the_authority_lowers_it() {
+    let (mut fixture, _) = funded_pool();
+    let owner = fixture.user(100_000_000, 100_000_000);
+    assert_eq!(fixture.pool().limit_order_min_liquidity, DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY);
+    // A few hundred tokens on one range is far below the default at this spacing
+    assert_eq!(place(&mut fixture, &owner, SPACING, 300).unwrap_err(), orca_error(ErrorCode::LimitOrderTooSmall));
+    let keys = fixture.keys;
+    let set = |authority, limit_order_min_liquidity| ix::set_limit_order_min_liquidity(&keys, authority, instruction::SetLimitOrderMinLiquidity { limit_order_min_liquidity });
+    assert_eq!(fixture.svm.process(&[set(owner.key, 0)], &[owner.key]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    let outcome = fixture.svm.process(&[set(fixture.admin, 1_000)], &[fixture.admin]).unwrap();
+    assert_eq!(outcome.events::<LimitOrderMinLiquidityUpdated>()[0].limit_order_min_liquidity, 1_000);
+    let placed = place(&mut fixture, &owner, SPACING, 300).unwrap();
+    assert!(placed.liquidity_delta >= 1_000 && placed.liquidity_delta < DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY);
+    // Raising it again leaves the order in the book
+    fixture.svm.process(&[set(fixture.admin, DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY)], &[fixture.admin]).unwrap();
+    assert_eq!(book(&fixture).ranges[0].liquidity, placed.liquidity_delta);
+    let cancel = ix::cancel_limit_order(&fixture.keys, owner.key, owner.tokens, SPACING);
+    fixture.svm.process(&[cancel], &[owner.key]).unwrap();
+}
@@ -0,0 +1,420 @@
+//! Accounts created before the `version` byte existed are stored at the
+//! layouts in `orca::state::legacy`. The SDK decodes them as version 0, and
+//! the `migrate_*` instructions rewrite them in place at the current layout.
+//! Deployments that old have a single config, at its pre-index address, and
+//! the fixtures here are written with the legacy types as those deployments
+//! left them.
+
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey, Rent};
+use anchor_lang::{AccountSerialize, Discriminator};
+use common::*;
+use orca::constants::{ACCOUNT_VERSION, DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY, Q64};
+use orca::errors::ErrorCode;
+use orca::instruction;
+use orca::instructions::liquidity::LiquidityResult;
+use orca::state::legacy;
+use orca_sdk::instructions::{self as ix, PoolKeys};
+use orca_sdk::{accounts, pda, AmmConfig, LimitOrderBook, ObservationState, Pool, Position};
+
+/// Liquidity of the legacy pool, all of it the owner's position.
+const LIQUIDITY: u128 = 1 << 24;
+const FEE_GROWTH_0: u128 = 3 << 54;
+const FEE_GROWTH_1: u128 = 5 << 54;
+/// The position's fees not yet moved to `tokens_owed_*`, at `FEE_GROWTH_*`
+/// over entries of `1 << 54`: `LIQUIDITY * (growth - entry) / Q64`.
+const ACCRUED_0: u64 = 1 << 15;
+const ACCRUED_1: u64 = 1 << 16;
+const TOKENS_OWED: (u64, u64) = (1_000, 2_000);
+const PROTOCOL_FEES: (u64, u64) = (300, 400);
+
+/// A deployment from before versioning: its config, one pool at 1:1 holding
+/// the liquidity of one position with fees owed, and the pool's observation.
+struct LegacyDeployment {
+    svm: Svm,
+    admin: Pubkey,
+    owner: User,
+    config: legacy::AmmConfig,
+    pool: legacy::Pool,
+    position: legacy::Position,
+    observation: legacy::ObservationState,
+    pool_key: Pubkey,
+}
+
+impl LegacyDeployment {
+    fn new() -> Self {
+        let mut svm = Svm::new();
+        let admin = Pubkey::new_unique();
+        svm.airdrop(&admin, 100_000_000_000);
+        let (config_key, config_bump) = pda::legacy_amm_config();
+        svm.set_program_account(&config_key, legacy_amm_config(config_bump, admin));
+        let config = legacy::read::<AmmConfig, legacy::AmmConfig>(&svm.data(&config_key)).unwrap();
+
+        let mut mints = [svm.create_mint(&admin, 6), svm.create_mint(&admin, 6)];
+        mints.sort();
+        let (pool_key, bump) = pda::pool(&config_key, &mints[0], &mints[1]);
+        let (authority, auth_bump) = pda::pool_authority(&pool_key);
+        let (principal_0, principal_1) = orca_math::amounts_for_liquidity(INITIAL_SQRT_PRICE_X64, LIQUIDITY, true).unwrap();
+        let vault_0 = svm.create_token_account(&mints[0], &authority, principal_0 + ACCRUED_0 + TOKENS_OWED.0 + PROTOCOL_FEES.0);
+        let vault_1 = svm.create_token_account(&mints[1], &authority, principal_1 + ACCRUED_1 + TOKENS_OWED.1 + PROTOCOL_FEES.1);
+        let pool = legacy::Pool {
+            bump,
+            amm_config: config_key,
+            token_mint_0: mints[0],
+            token_mint_1: mints[1],
+            token_vault_0: vault_0,
+            token_vault_1: vault_1,
+            auth_bump,
+            liquidity: LIQUIDITY,
+            sqrt_price_x64: INITIAL_SQRT_PRICE_X64,
+            tick_current: 0,
+            tick_spacing: 64,
+            fee_rate: FEE_RATE,
+            protocol_fees_token_0: PROTOCOL_FEES.0,
+            protocol_fees_token_1: PROTOCOL_FEES.1,
+            fee_growth_global_0_x64: FEE_GROWTH_0,
+            fee_growth_global_1_x64: FEE_GROWTH_1,
+            fee_split_lp_bps: 2500,
+            fee_split_protocol_bps: 400,
+            fee_split_impact_bps: 100,
+        };
+        // Pools were allocated past their serialized fields
+        let mut pool_data = legacy_account::<Pool>(&pool);
+        pool_data.resize(legacy::Pool::SIZE, 0);
+        svm.set_program_account(&pool_key, pool_data);
+
+        let owner = Pubkey::new_unique();
+        svm.airdrop(&owner, 10_000_000_000);
+        let (position_key, position_bump) = pda::position(&pool_key, &owner);
+        let position = legacy::Position {
+            bump: position_bump,
+            pool: pool_key,
+            owner,
+            liquidity: LIQUIDITY,
+            fee_growth_entry_0_x64: 1 << 54,
+            fee_growth_entry_1_x64: 1 << 54,
+            tokens_owed_0: TOKENS_OWED.0,
+            tokens_owed_1: TOKENS_OWED.1,
+        };
+        svm.set_program_account(&position_key, legacy_account::<Position>(&position));
+
+        let (observation_key, observation_bump) = pda::observation(&pool_key);
+        let observation = legacy::ObservationState {
+            bump: observation_bump,
+            pool: pool_key,
+            index: 0,
+            cardinality: 1,
+            tick_cumulative: -42,
+            last_timestamp: STARTING_TIMESTAMP - 60,
+        };
+        svm.set_program_account(&observation_key, legacy_account::<ObservationState>(&observation));
+
+        let tokens = ix::UserTokenAccounts {
+            token_account_0: svm.create_token_account(&mints[0], &owner, 0),
+            token_account_1: svm.create_token_account(&mints[1], &owner, 0),
+        };
+        let owner = User { key: owner, tokens, lp_token_account: Pubkey::default() };
+        Self { svm, admin, owner, config, pool, position, observation, pool_key }
+    }
+
+    fn position_key(&self) -> Pubkey {
+        pda::position(&self.pool_key, &self.owner.key).0
+    }
+
+    fn observation_key(&self) -> Pubkey {
+        pda::observation(&self.pool_key).0
+    }
+
+    /// Migrates the config, then everything under the pool.
+    fn migrate_all(&mut self) {
+        let (config, pool, admin) = (self.pool.amm_config, self.pool_key, self.admin);
+        let migrations = [
+            ix::migrate_amm_config(config, admin),
+            ix::migrate_pool(pool, config, self.pool.token_vault_0, self.pool.token_vault_1, admin),
+            ix::migrate_position(self.position_key(), pool, config, admin),
+            ix::migrate_observation_state(self.observation_key(), pool, config, admin),
+        ];
+        for migration in migrations {
+            self.svm.process(&[migration], &[admin]).unwrap();
+        }
+    }
+
+    /// The migrated pool as a fixture, with the owner's LP token account made
+    /// for the LP mint migration created.
+    fn into_fixture(mut self) -> (PoolFixture, User) {
+        let keys = PoolKeys::new(self.pool_key, &self.svm.account::<Pool>(&self.pool_key));
+        self.owner.lp_token_account = self.svm.create_token_account(&keys.lp_mint, &self.owner.key, 0);
+        (PoolFixture { svm: self.svm, admin: self.admin, keys }, self.owner)
+    }
+}
+
+fn anchor_error(code: anchor_lang::error::ErrorCode) -> ProgramError {
+    anchor_lang::error::Error::from(code).into()
+}
+
+#[test]
+fn legacy_accounts_decode_as_version_0() {
+    let deployment = LegacyDeployment::new();
+    let svm = &deployment.svm;
+    let config = accounts::decode_amm_config(&svm.data(&deployment.pool.amm_config)).unwrap();
+    assert_eq!(svm.data(&deployment.pool.amm_config).len(), legacy::AmmConfig::SIZE);
+    assert_eq!((config.version, config.index, config.authority), (0, 0, deployment.admin));
+    assert_eq!((config.default_fee_rate, config.protocol_fee_rate, config.flash_fee_rate), (FEE_RATE, PROTOCOL_FEE_RATE, FEE_RATE));
+
+    let pool = accounts::decode_pool(&svm.data(&deployment.pool_key)).unwrap();
+    assert_eq!((pool.version, pool.lp_mint, pool.lp_liquidity), (0, Pubkey::default(), 0));
+    assert_eq!((pool.amm_config, pool.token_vault_0, pool.token_vault_1), (deployment.pool.amm_config, deployment.pool.token_vault_0, deployment.pool.token_vault_1));
+    assert_eq!((pool.liquidity, pool.sqrt_price_x64, pool.tick_current), (LIQUIDITY, INITIAL_SQRT_PRICE_X64, 0));
+    assert_eq!((pool.fee_growth_global_0_x64, pool.fee_growth_global_1_x64), (FEE_GROWTH_0, FEE_GROWTH_1));
+    assert_eq!((pool.protocol_fees_token_0, pool.protocol_fees_token_1), PROTOCOL_FEES);
+    assert_eq!((pool.fee_split_lp_bps, pool.fee_split_protocol_bps, pool.fee_split_impact_bps), (2500, 400, 100));
+
+    let position = accounts::decode_position(&svm.data(&deployment.position_key())).unwrap();
+    assert_eq!((position.version, position.owner, position.pool, position.liquidity), (0, deployment.owner.key, deployment.pool_key, LIQUIDITY));
+    assert_eq!((position.tokens_owed_0, position.tokens_owed_1), TOKENS_OWED);
+
+    let observation = accounts::decode_observation_state(&svm.data(&deployment.observation_key())).unwrap();
+    assert_eq!((observation.version, observation.pool, observation.cardinality), (0, deployment.pool_key, 1));
+    assert_eq!((observation.tick_cumulative, observation.last_timestamp), (-42, STARTING_TIMESTAMP - 60));
+}
+
+#[test]
+fn migration_translates_every_field_in_place() {
+    let mut deployment = LegacyDeployment::new();
+    let (config_key, pool_key, admin) = (deployment.pool.amm_config, deployment.pool_key, deployment.admin);
+    // Everything else loads the config, so it goes first, and only its own authority can migrate it
+    let migrate_pool = ix::migrate_pool(pool_key, config_key, deployment.pool.token_vault_0, deployment.pool.token_vault_1, admin);
+    assert_eq!(deployment.svm.process(std::slice::from_ref(&migrate_pool), &[admin]).unwrap_err(), anchor_error(anchor_lang::error::ErrorCode::AccountDidNotDeserialize));
+    let owner = deployment.owner.key;
+    assert_eq!(deployment.svm.process(&[ix::migrate_amm_config(config_key, owner)], &[owner]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+
+    let admin_lamports = deployment.svm.lamports(&admin);
+    deployment.migrate_all();
+    let svm = &deployment.svm;
+    let mut config = deployment.config.upgrade();
+    config.version = ACCOUNT_VERSION;
+    let mut data = Vec::new();
+    config.try_serialize(&mut data).unwrap();
+    assert_eq!(svm.data(&config_key), data);
+
+    let pool = svm.account::<Pool>(&pool_key);
+    let mut expected = deployment.pool.upgrade();
+    expected.lp_mint = pda::lp_mint(&pool_key).0;
+    expected.volatility_updated_at = STARTING_TIMESTAMP;
+    expected.version = ACCOUNT_VERSION;
+    // Share fees are tracked from migration on; there are no shares yet
+    expected.lp_fees_tracked = 1;
+    expected.lp_fee_growth_entry_0_x64 = FEE_GROWTH_0;
+    expected.lp_fee_growth_entry_1_x64 = FEE_GROWTH_1;
+    // Positions are owed what the vaults hold beyond the protocol's fees and the liquidity's principal
+    let (principal_0, principal_1) = orca_math::amounts_for_liquidity(INITIAL_SQRT_PRICE_X64, LIQUIDITY, false).unwrap();
+    expected.position_fee_growth_entry_0_x64 = FEE_GROWTH_0;
+    expected.position_fee_growth_entry_1_x64 = FEE_GROWTH_1;
+    expected.position_fees_owed_0 = svm.token_balance(&expected.token_vault_0) - PROTOCOL_FEES.0 - principal_0;
+    expected.position_fees_owed_1 = svm.token_balance(&expected.token_vault_1) - PROTOCOL_FEES.1 - principal_1;
+    assert!(expected.position_fees_owed_0 >= TOKENS_OWED.0 + ACCRUED_0 && expected.position_fees_owed_1 >= TOKENS_OWED.1 + ACCRUED_1);
+    expected.limit_order_min_liquidity = DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY;
+    assert_eq!(bytemuck::bytes_of(&pool), bytemuck::bytes_of(&expected));
+    assert_eq!(svm.mint_supply(&pool.lp_mint), 0);
+    let book = svm.account::<LimitOrderBook>(&pda::limit_order_book(&pool_key).0);
+    assert_eq!((book.pool, book.bump, book.version), (pool_key, pda::limit_order_book(&pool_key).1, ACCOUNT_VERSION));
+
+    let mut expected = deployment.position.upgrade();
+    expected.version = ACCOUNT_VERSION;
+    let mut data = Vec::new();
+    expected.try_serialize(&mut data).unwrap();
+    assert_eq!(svm.data(&deployment.position_key()), data);
+    let observation = svm.account::<ObservationState>(&deployment.observation_key());
+    let mut expected = deployment.observation.upgrade();
+    expected.version = ACCOUNT_VERSION;
+    assert_eq!(bytemuck::bytes_of(&observation), bytemuck::bytes_of(&expected));
+
+    // The authority paid the rent of the grown accounts and of those pools lacked
+    let rent = Rent::default();
+    let grown = [(config_key, legacy::AmmConfig::SIZE, AmmConfig::SIZE), (pool_key, legacy::Pool::SIZE, Pool::SIZE), (deployment.position_key(), legacy::Position::SIZE, Position::SIZE), (deployment.observation_key(), legacy::ObservationState::SIZE, ObservationState::SIZE)];
+    let created = rent.minimum_balance(anchor_spl::token::Mint::LEN) + rent.minimum_balance(LimitOrderBook::SIZE);
+    let rent_paid = grown.iter().map(|(_, legacy_size, size)| rent.minimum_balance(*size) - rent.minimum_balance(*legacy_size)).sum::<u64>() + created;
+    assert_eq!(svm.lamports(&admin), admin_lamports - rent_paid);
+    for (key, _, size) in grown {
+        assert_eq!((svm.data(&key).len(), svm.lamports(&key)), (size, rent.minimum_balance(size)));
+    }
+}
+
+#[test]
+fn migrated_pools_trade_and_pay_their_positions() {
+    let mut deployment = LegacyDeployment::new();
+    deployment.migrate_all();
+    let (mut fixture, owner) = deployment.into_fixture();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    fixture.swap(&trader, 10_000, true).unwrap();
+
+    // The owner collects the fees owed before migration and their share of the swap's
+    let pool = fixture.pool();
+    let position = fixture.svm.account::<Position>(&pda::position(&fixture.keys.pool, &owner.key).0);
+    let swap_fees_0 = orca_math::mul_div(LIQUIDITY, pool.fee_growth_global_0_x64 - FEE_GROWTH_0, Q64, false).unwrap() as u64;
+    assert!(swap_fees_0 > 0);
+    assert_eq!(position.fee_growth_entry_0_x64, 1 << 54);
+    fixture.svm.process(&[ix::collect_fees(&fixture.keys, owner.key, owner.tokens)], &[owner.key]).unwrap();
+    assert_eq!(fixture.svm.token_balance(&owner.tokens.token_account_0), TOKENS_OWED.0 + ACCRUED_0 + swap_fees_0);
+    assert_eq!(fixture.svm.token_balance(&owner.tokens.token_account_1), TOKENS_OWED.1 + ACCRUED_1);
+
+    // And withdraws from it, out of the principal the legacy vaults held
+    let (amount_0, amount_1) = orca_math::amounts_for_liquidity(fixture.pool().sqrt_price_x64, LIQUIDITY / 2, false).unwrap();
+    let balance_0 = fixture.svm.token_balance(&owner.tokens.token_account_0);
+    let withdraw = ix::decrease_position_liquidity(&fixture.keys, owner.key, owner.tokens, instruction::DecreasePositionLiquidity { amount_token_0: amount_0, amount_token_1: amount_1 });
+    let withdrawn = fixture.svm.process(&[withdraw], &[owner.key]).unwrap().return_value::<LiquidityResult>();
+    assert!(withdrawn.liquidity_delta > 0 && withdrawn.amount_token_0 > 0);
+    assert_eq!(fixture.svm.token_balance(&owner.tokens.token_account_0), balance_0 + withdrawn.amount_token_0);
+    let position = fixture.svm.account::<Position>(&pda::position(&fixture.keys.pool, &owner.key).0);
+    assert_eq!(position.liquidity, LIQUIDITY - withdrawn.liquidity_delta);
+}
+
+#[test]
+fn only_legacy_accounts_of_the_named_type_migrate() {
+    let mut deployment = LegacyDeployment::new();
+    let (config, pool, admin) = (deployment.pool.amm_config, deployment.pool_key, deployment.admin);
+    let position = deployment.position_key();
+    let vaults = (deployment.pool.token_vault_0, deployment.pool.token_vault_1);
+    deployment.migrate_all();
+    let svm = &mut deployment.svm;
+    for migration in [ix::migrate_amm_config(config, admin), ix::migrate_pool_v1(pool, config, vaults.0, vaults.1, admin), ix::migrate_position(position, pool, config, admin)] {
+        assert_eq!(svm.process(&[migration], &[admin]).unwrap_err(), orca_error(ErrorCode::AccountAlreadyMigrated));
+    }
+    // A pool's LP mint and book exist once it is migrated, so its migration cannot run again
+    assert!(svm.process(&[ix::migrate_pool(pool, config, vaults.0, vaults.1, admin)], &[admin]).is_err());
+
+    let mut legacy_position = LegacyDeployment::new();
+    let (config, pool, admin) = (legacy_position.pool.amm_config, legacy_position.pool_key, legacy_position.admin);
+    let position = legacy_position.position_key();
+    let svm = &mut legacy_position.svm;
+    svm.process(&[ix::migrate_amm_config(config, admin)], &[admin]).unwrap();
+    let mismatch = anchor_error(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch);
+    assert_eq!(svm.process(&[ix::migrate_observation_state(position, pool, config, admin)], &[admin]).unwrap_err(), mismatch);
+    svm.process(&[ix::migrate_position(position, pool, config, admin)], &[admin]).unwrap();
+    assert_eq!(svm.account::<Position>(&position).version, ACCOUNT_VERSION);
+}
+
+#[test]
+fn only_the_pools_config_authority_migrates_its_accounts() {
+    let mut deployment = LegacyDeployment::new();
+    let (config, pool, admin) = (deployment.pool.amm_config, deployment.pool_key, deployment.admin);
+    deployment.svm.process(&[ix::migrate_amm_config(config, admin)], &[admin]).unwrap();
+    // Config 1 with its own authority and a pool of its own
+    let other = Pubkey::new_unique();
+    deployment.svm.airdrop(&other, 10_000_000_000);
+    deployment.svm.set_program_data(&pda::program_data().0, Some(admin));
+    let args = instruction::InitializeAmm { index: 1, fee_rate: FEE_RATE, protocol_fee_rate: 0 };
+    deployment.svm.process(&[ix::initialize_amm(admin, other, other, args)], &[admin]).unwrap();
+    let other_config = pda::amm_config(1).0;
+    let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mint_0, mint_1) = (deployment.pool.token_mint_0, deployment.pool.token_mint_1);
+    let args = instruction::CreatePool { sqrt_price_x64: INITIAL_SQRT_PRICE_X64, tick_spacing: 64 };
+    deployment.svm.process(&[ix::create_pool(other, other_config, mint_0, mint_1, vault_0, vault_1, args)], &[other, vault_0, vault_1]).unwrap();
+    let other_pool = pda::pool(&other_config, &mint_0, &mint_1).0;
+
+    // Another config's authority can neither migrate the pool nor reach its accounts through a pool of its own
+    let position = deployment.position_key();
+    let vaults = (deployment.pool.token_vault_0, deployment.pool.token_vault_1);
+    let svm = &mut deployment.svm;
+    assert_eq!(svm.process(&[ix::migrate_pool(pool, other_config, vaults.0, vaults.1, other)], &[other]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    assert_eq!(svm.process(&[ix::migrate_position(position, pool, other_config, other)], &[other]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    assert_eq!(svm.process(&[ix::migrate_position(position, other_pool, other_config, other)], &[other]).unwrap_err(), orca_error(ErrorCode::InvalidPoolConfig));
+    svm.process(&[ix::migrate_position(position, pool, config, admin)], &[admin]).unwrap();
+    svm.process(&[ix::migrate_pool(pool, config, vaults.0, vaults.1, admin)], &[admin]).unwrap();
+    assert_eq!(svm.account::<Pool>(&pool).version, ACCOUNT_VERSION);
+}
+
+/// Writes `book` at its version 1 layout, without the ranges' balances.
+fn version_1_book(book: &LimitOrderBook) -> Vec<u8> {
+    let mut v1: legacy::LimitOrderBookV1 = bytemuck::Zeroable::zeroed();
+    for (legacy, range) in v1.ranges.iter_mut().zip(&book.ranges) {
+        *legacy = legacy::LimitOrderRangeV1 {
+            liquidity: range.liquidity,
+            fee_growth_0_x64: range.fee_growth_0_x64,
+            fee_growth_1_x64: range.fee_growth_1_x64,
+            fees_owed_0: range.fees_owed_0,
+            fees_owed_1: range.fees_owed_1,
+            tick_lower: range.tick_lower,
+            open_orders: range.open_orders,
+            sells_token_0: range.sells_token_0,
+            filled: range.filled,
+            padding: [0; 6],
+        };
+    }
+    (v1.pool, v1.bump, v1.version) = (book.pool, book.bump, 1);
+    [LimitOrderBook::DISCRIMINATOR, bytemuck::bytes_of(&v1)].concat()
+}
+
+#[test]
+fn version_1_pools_get_a_reserved_tail_and_their_books_balances() {
+    let mut fixture = PoolFixture::new();
+    let user = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&user, 10_000_000, 10_000_000).unwrap();
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, user.key)], &[user.key]).unwrap();
+    let increase = ix::increase_position_liquidity(&fixture.keys, user.key, user.tokens, instruction::IncreasePositionLiquidity { amount_token_0: 1_000_000, amount_token_1: 1_000_000 });
+    fixture.svm.process(&[increase], &[user.key]).unwrap();
+    let tick_lower = 4 * fixture.pool().tick_spacing as i32;
+    let place = ix::place_limit_order(&fixture.keys, user.key, user.tokens, instruction::PlaceLimitOrder { tick_lower, amount: 100_000 });
+    fixture.svm.process(&[place], &[user.key]).unwrap();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    fixture.swap(&trader, 100_000, true).unwrap();
+    // A version 1 pool from before share fees were credited, when those bytes were still reserved, and its book
+    let mut pool = fixture.pool();
+    pool.version = 1;
+    pool.lp_fees_tracked = 0;
+    (pool.lp_fee_growth_entry_0_x64, pool.lp_fee_growth_entry_1_x64) = (0, 0);
+    (pool.lp_fees_owed_0, pool.lp_fees_owed_1) = (0, 0);
+    (pool.position_fee_growth_entry_0_x64, pool.position_fee_growth_entry_1_x64) = (0, 0);
+    (pool.position_fees_owed_0, pool.position_fees_owed_1) = (0, 0);
+    pool.limit_order_min_liquidity = 0;
+    let v1 = [Pool::DISCRIMINATOR, &bytemuck::bytes_of(&pool)[..legacy::POOL_V1_SIZE - 8]].concat();
+    let key = fixture.keys.pool;
+    fixture.svm.set_data(&key, v1);
+    assert_eq!(bytemuck::bytes_of(&accounts::decode_pool(&fixture.svm.data(&key)).unwrap()), bytemuck::bytes_of(&pool));
+    let book_key = fixture.keys.limit_order_book;
+    let book = fixture.svm.account::<LimitOrderBook>(&book_key);
+    fixture.svm.set_data(&book_key, version_1_book(&book));
+    // Its ranges read with what their liquidity holds at the pool's price
+    let range = book.ranges[0];
+    let mut expected_book = book;
+    expected_book.version = 1;
+    (expected_book.ranges[0].amount_0, expected_book.ranges[0].amount_1) = range.amounts(pool.sqrt_price_x64, pool.tick_spacing, range.liquidity, false).unwrap();
+    assert_eq!(bytemuck::bytes_of(&accounts::decode_limit_order_book(&fixture.svm.data(&book_key), &pool).unwrap()), bytemuck::bytes_of(&expected_book));
+
+    let (config, admin) = (fixture.keys.amm_config, fixture.admin);
+    let (vault_0, vault_1) = (fixture.keys.token_vault_0, fixture.keys.token_vault_1);
+    assert_eq!(fixture.svm.process(&[ix::migrate_pool_v1(key, config, vault_0, vault_1, user.key)], &[user.key]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    let swapped = ix::migrate_pool_v1(key, config, vault_1, vault_0, admin);
+    assert_eq!(fixture.svm.process(&[swapped], &[admin]).unwrap_err(), orca_error(ErrorCode::InvalidTokenAccount));
+    let admin_lamports = fixture.svm.lamports(&admin);
+    fixture.svm.process(&[ix::migrate_pool_v1(key, config, vault_0, vault_1, admin)], &[admin]).unwrap();
+    let rent = Rent::default();
+    let grown = [(key, legacy::POOL_V1_SIZE, Pool::SIZE), (book_key, legacy::LimitOrderBookV1::SIZE, LimitOrderBook::SIZE)];
+    assert_eq!(fixture.svm.lamports(&admin), admin_lamports - grown.iter().map(|(_, v1_size, size)| rent.minimum_balance(*size) - rent.minimum_balance(*v1_size)).sum::<u64>());
+    for (key, _, size) in grown {
+        assert_eq!(fixture.svm.data(&key).len(), size);
+    }
+    expected_book.version = ACCOUNT_VERSION;
+    assert_eq!(bytemuck::bytes_of(&fixture.svm.account::<LimitOrderBook>(&book_key)), bytemuck::bytes_of(&expected_book));
+    // Share fees are tracked from migration on, like a legacy pool's, and positions are owed what the
+    // vaults hold beyond the protocol's fees, the order and the liquidity's principal
+    pool.version = ACCOUNT_VERSION;
+    pool.lp_fees_tracked = 1;
+    (pool.lp_fee_growth_entry_0_x64, pool.lp_fee_growth_entry_1_x64) = (pool.fee_growth_global_0_x64, pool.fee_growth_global_1_x64);
+    (pool.position_fee_growth_entry_0_x64, pool.position_fee_growth_entry_1_x64) = (pool.fee_growth_global_0_x64, pool.fee_growth_global_1_x64);
+    let (principal_0, principal_1) = orca_math::amounts_for_liquidity(pool.sqrt_price_x64, pool.liquidity, false).unwrap();
+    let (orders_0, orders_1) = expected_book.reserved_amounts().unwrap();
+    pool.position_fees_owed_0 = fixture.svm.token_balance(&vault_0) - pool.protocol_fees_token_0 - orders_0 - principal_0;
+    pool.position_fees_owed_1 = fixture.svm.token_balance(&vault_1) - pool.protocol_fees_token_1 - orders_1 - principal_1;
+    pool.limit_order_min_liquidity = DEFAULT_LIMIT_ORDER_MIN_LIQUIDITY;
+    assert_eq!(bytemuck::bytes_of(&fixture.pool()), bytemuck::bytes_of(&pool));
+    assert_eq!(fixture.svm.process(&[ix::migrate_pool_v1(key, config, vault_0, vault_1, admin)], &[admin]).unwrap_err(), orca_error(ErrorCode::AccountAlreadyMigrated));
+
+    // The position collects its fees out of what the pool records for positions
+    fixture.swap(&trader, 100_000, false).unwrap();
+    assert!(fixture.pool().lp_fee_growth_entry_1_x64 < fixture.pool().fee_growth_global_1_x64);
+    let collected = fixture.svm.process(&[ix::collect_fees(&fixture.keys, user.key, user.tokens)], &[user.key]).unwrap().return_value::<LiquidityResult>();
+    assert!(collected.amount_token_0 > 0 && collected.amount_token_1 > 0);
+    assert!(fixture.pool().position_fees_owed_0 < pool.position_fees_owed_0);
+}
@@ -0,0 +1,81 @@
+//! The observation accumulates the tick in force over each interval between
+//! swaps, so a TWAP is the cumulative difference over the elapsed time.
+
+mod common;
+
+use common::*;
+use orca_sdk::ObservationState;
+
+fn observation(fixture: &PoolFixture) -> ObservationState {
+    fixture.svm.account(&fixture.keys.observation_state)
+}
+
+#[test]
+fn pool_creation_starts_the_clock() {
+    let fixture = PoolFixture::new();
+    let observation = observation(&fixture);
+    assert_eq!(observation.pool, fixture.keys.pool);
+    assert_eq!(observation.last_timestamp, STARTING_TIMESTAMP);
+    assert_eq!(observation.tick_cumulative, 0);
+}
+
+#[test]
+fn each_interval_accumulates_the_tick_that_held_during_it() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+
+    fixture.svm.warp_to_timestamp(STARTING_TIMESTAMP + 100);
+    fixture.swap(&trader, 500_000, true).unwrap();
+    // The pool sat at tick 0 for the first 100 seconds
+    assert_eq!(observation(&fixture).tick_cumulative, 0);
+    let tick_1 = fixture.pool().tick_current;
+    assert!(tick_1 < 0);
+
+    fixture.svm.warp_to_timestamp(STARTING_TIMESTAMP + 250);
+    fixture.swap(&trader, 2_000_000, false).unwrap();
+    let tick_2 = fixture.pool().tick_current;
+    assert!(tick_2 > 0);
+    let observed = observation(&fixture);
+    assert_eq!(observed.tick_cumulative, tick_1 as i128 * 150);
+    assert_eq!(observed.last_timestamp, STARTING_TIMESTAMP + 250);
+
+    fixture.svm.warp_to_timestamp(STARTING_TIMESTAMP + 1_250);
+    fixture.swap(&trader, 1_000, true).unwrap();
+    let cumulative = observation(&fixture).tick_cumulative;
+    assert_eq!(cumulative, tick_1 as i128 * 150 + tick_2 as i128 * 1_000);
+    // Time-weighted over [100, 1250]: mostly tick_2
+    let twap = cumulative / 1_150;
+    assert!(twap > 0 && twap < tick_2 as i128);
+}
+
+#[test]
+fn swaps_within_the_same_second_add_nothing() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+    fixture.svm.warp_to_timestamp(STARTING_TIMESTAMP + 10);
+    fixture.swap(&trader, 500_000, true).unwrap();
+    let before = observation(&fixture);
+    fixture.swap(&trader, 500_000, true).unwrap();
+    fixture.swap(&trader, 500_000, false).unwrap();
+    let after = observation(&fixture);
+    assert_eq!(after.tick_cumulative, before.tick_cumulative);
+    assert_eq!(after.last_timestamp, before.last_timestamp);
+}
+
+#[test]
+fn a_long_quiet_period_is_weighted_by_its_length() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(100_000_000, 100_000_000);
+    fixture.add_liquidity(&provider, 10_000_000, 10_000_000).unwrap();
+    let trader = fixture.user(10_000_000, 10_000_000);
+    fixture.swap(&trader, 1_000_000, true).unwrap();
+    let tick = fixture.pool().tick_current;
+    let one_year = 365 * 24 * 60 * 60;
+    fixture.svm.warp_to_timestamp(STARTING_TIMESTAMP + one_year);
+    fixture.swap(&trader, 1_000, false).unwrap();
+    assert_eq!(observation(&fixture).tick_cumulative, tick as i128 * one_year as i128);
+}
@@ -0,0 +1,154 @@
+//! A config can restrict pool creation to allowlisted creators and to mints
+//! it has badged. Open configs take any mint, freezable ones included.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{program_error::ProgramError, program_pack::Pack};
+use anchor_spl::token::spl_token;
+use common::*;
+use orca::errors::ErrorCode;
+use orca::events::{ConfigUpdated, PoolCreatorUpdated, TokenBadgeUpdated};
+use orca::instruction;
+use orca_sdk::instructions::{self as ix, UserTokenAccounts};
+use orca_sdk::{accounts, pda, PoolCreator};
+
+/// Sorted mints for a new pool under the fixture's config.
+fn mints(fixture: &mut PoolFixture) -> [Pubkey; 2] {
+    let mut mints = [fixture.svm.create_mint(&fixture.admin, 6), fixture.svm.create_mint(&fixture.admin, 6)];
+    mints.sort();
+    mints
+}
+
+fn create_pool(fixture: &mut PoolFixture, creator: Pubkey, mints: [Pubkey; 2]) -> Result<(), ProgramError> {
+    fixture.svm.airdrop(&creator, 10_000_000_000);
+    let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let args = instruction::CreatePool { sqrt_price_x64: INITIAL_SQRT_PRICE_X64, tick_spacing: 64 };
+    let create = ix::create_pool(creator, pda::amm_config(0).0, mints[0], mints[1], vault_0, vault_1, args);
+    fixture.svm.process(&[create], &[creator, vault_0, vault_1]).map(|_| ())
+}
+
+fn set_policy(fixture: &mut PoolFixture, permissioned_pool_creation: bool, token_badges_required: bool) {
+    let args = instruction::SetPoolCreationPolicy { permissioned_pool_creation, token_badges_required };
+    let outcome = fixture.svm.process(&[ix::set_pool_creation_policy(pda::amm_config(0).0, fixture.admin, args)], &[fixture.admin]).unwrap();
+    let event = outcome.events::<ConfigUpdated>().pop().unwrap();
+    assert_eq!((event.permissioned_pool_creation, event.token_badges_required), (permissioned_pool_creation, token_badges_required));
+}
+
+fn badge(fixture: &mut PoolFixture, mint: Pubkey) {
+    let outcome = fixture.svm.process(&[ix::add_token_badge(pda::amm_config(0).0, fixture.admin, mint)], &[fixture.admin]).unwrap();
+    assert!(outcome.events::<TokenBadgeUpdated>().pop().unwrap().badged);
+}
+
+#[test]
+fn only_allowlisted_creators_create_pools_under_a_permissioned_config() {
+    let mut fixture = PoolFixture::new();
+    set_policy(&mut fixture, true, false);
+    let config = pda::amm_config(0).0;
+    let creator = Pubkey::new_unique();
+    let pair = mints(&mut fixture);
+    assert_eq!(create_pool(&mut fixture, creator, pair).unwrap_err(), orca_error(ErrorCode::PoolCreatorNotAllowed));
+
+    let outcome = fixture.svm.process(&[ix::add_pool_creator(config, fixture.admin, creator)], &[fixture.admin]).unwrap();
+    let event = outcome.events::<PoolCreatorUpdated>().pop().unwrap();
+    assert_eq!((event.amm_config, event.creator, event.allowed), (config, creator, true));
+    let allowed = accounts::decode_pool_creator(&fixture.svm.data(&pda::pool_creator(&config, &creator).0)).unwrap();
+    assert_eq!((allowed.amm_config, allowed.creator, allowed.version), (config, creator, orca::constants::ACCOUNT_VERSION));
+    create_pool(&mut fixture, creator, pair).unwrap();
+
+    // Removal closes the account and shuts the creator out again
+    let outcome = fixture.svm.process(&[ix::remove_pool_creator(config, fixture.admin, creator)], &[fixture.admin]).unwrap();
+    assert!(!outcome.events::<PoolCreatorUpdated>().pop().unwrap().allowed);
+    assert!(fixture.svm.try_account::<PoolCreator>(&pda::pool_creator(&config, &creator).0).is_none());
+    let other_pair = mints(&mut fixture);
+    assert_eq!(create_pool(&mut fixture, creator, other_pair).unwrap_err(), orca_error(ErrorCode::PoolCreatorNotAllowed));
+
+    // Turning the allowlist off opens creation to anyone
+    set_policy(&mut fixture, false, false);
+    create_pool(&mut fixture, creator, other_pair).unwrap();
+}
+
+#[test]
+fn both_mints_need_a_badge_when_badges_are_required() {
+    let mut fixture = PoolFixture::new();
+    set_policy(&mut fixture, false, true);
+    let config = pda::amm_config(0).0;
+    let creator = Pubkey::new_unique();
+    let pair = mints(&mut fixture);
+    assert_eq!(create_pool(&mut fixture, creator, pair).unwrap_err(), orca_error(ErrorCode::TokenBadgeRequired));
+    badge(&mut fixture, pair[0]);
+    assert_eq!(create_pool(&mut fixture, creator, pair).unwrap_err(), orca_error(ErrorCode::TokenBadgeRequired));
+    badge(&mut fixture, pair[1]);
+    let token_badge = accounts::decode_token_badge(&fixture.svm.data(&pda::token_badge(&config, &pair[1]).0)).unwrap();
+    assert_eq!((token_badge.amm_config, token_badge.token_mint), (config, pair[1]));
+    create_pool(&mut fixture, creator, pair).unwrap();
+
+    // A removed badge no longer lets its mint into new pools
+    let other_pair = mints(&mut fixture);
+    badge(&mut fixture, other_pair[0]);
+    badge(&mut fixture, other_pair[1]);
+    let outcome = fixture.svm.process(&[ix::remove_token_badge(config, fixture.admin, other_pair[1])], &[fixture.admin]).unwrap();
+    assert!(!outcome.events::<TokenBadgeUpdated>().pop().unwrap().badged);
+    assert_eq!(create_pool(&mut fixture, creator, other_pair).unwrap_err(), orca_error(ErrorCode::TokenBadgeRequired));
+}
+
+#[test]
+fn a_freezable_mint_needs_a_badge_only_when_the_config_requires_badges() {
+    let mut fixture = PoolFixture::new();
+    let creator = Pubkey::new_unique();
+    let pair = mints(&mut fixture);
+    let mut mint = spl_token::state::Mint::unpack(&fixture.svm.data(&pair[0])).unwrap();
+    mint.freeze_authority = Some(fixture.admin).into();
+    let mut data = fixture.svm.data(&pair[0]);
+    spl_token::state::Mint::pack(mint, &mut data).unwrap();
+    fixture.svm.set_data(&pair[0], data);
+    create_pool(&mut fixture, creator, pair).unwrap();
+    // Under a config that requires badges it is held to them like any other mint
+    set_policy(&mut fixture, false, true);
+    let (freezable, plain) = (pair[0], mints(&mut fixture)[0]);
+    let mut pair = [freezable, plain];
+    pair.sort();
+    badge(&mut fixture, plain);
+    assert_eq!(create_pool(&mut fixture, creator, pair).unwrap_err(), orca_error(ErrorCode::TokenBadgeRequired));
+    badge(&mut fixture, freezable);
+    create_pool(&mut fixture, creator, pair).unwrap();
+}
+
+#[test]
+fn create_pool_with_liquidity_is_held_to_the_same_policy() {
+    let mut fixture = PoolFixture::new();
+    set_policy(&mut fixture, true, false);
+    let creator = Pubkey::new_unique();
+    fixture.svm.airdrop(&creator, 10_000_000_000);
+    let pair = mints(&mut fixture);
+    let creator_accounts = UserTokenAccounts {
+        token_account_0: fixture.svm.create_token_account(&pair[0], &creator, 1_000_000),
+        token_account_1: fixture.svm.create_token_account(&pair[1], &creator, 1_000_000),
+    };
+    let (vault_0, vault_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let args = instruction::CreatePoolWithLiquidity { sqrt_price_x64: None, tick_spacing: 64, amount_token_0: 1_000_000, amount_token_1: 1_000_000 };
+    let create = ix::create_pool_with_liquidity(creator, pda::amm_config(0).0, pair[0], pair[1], vault_0, vault_1, creator_accounts, args);
+    assert_eq!(fixture.svm.process(std::slice::from_ref(&create), &[creator, vault_0, vault_1]).unwrap_err(), orca_error(ErrorCode::PoolCreatorNotAllowed));
+    fixture.svm.process(&[ix::add_pool_creator(pda::amm_config(0).0, fixture.admin, creator)], &[fixture.admin]).unwrap();
+    fixture.svm.process(&[create], &[creator, vault_0, vault_1]).unwrap();
+}
+
+#[test]
+fn only_the_config_authority_manages_the_policy() {
+    let mut fixture = PoolFixture::new();
+    let config = pda::amm_config(0).0;
+    let stranger = Pubkey::new_unique();
+    fixture.svm.airdrop(&stranger, 10_000_000_000);
+    let mint = fixture.keys.token_mint_0;
+    let attempts = [
+        ix::set_pool_creation_policy(config, stranger, instruction::SetPoolCreationPolicy { permissioned_pool_creation: false, token_badges_required: false }),
+        ix::add_pool_creator(config, stranger, stranger),
+        ix::add_token_badge(config, stranger, mint),
+    ];
+    for attempt in attempts {
+        assert_eq!(fixture.svm.process(&[attempt], &[stranger]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    }
+    fixture.svm.process(&[ix::add_token_badge(config, fixture.admin, mint)], &[fixture.admin]).unwrap();
+    let remove = ix::remove_token_badge(config, stranger, mint);
+    assert_eq!(fixture.svm.process(&[remove], &[stranger]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+}
@@ -0,0 +1,77 @@
+//! Each pool status bit halts exactly one kind of operation on one pool, and
+//! only the config authority can set them.
+
+mod common;
+
+use anchor_lang::solana_program::instruction::Instruction;
+use common::*;
+use orca::constants::*;
+use orca::errors::ErrorCode;
+use orca::instruction;
+use orca_sdk::instructions as ix;
+
+/// A pool with a funded provider who also holds a position, and a fresh user.
+fn pool_with_position() -> (PoolFixture, User, User) {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.add_liquidity(&provider, 1_000_000, 1_000_000).unwrap();
+    fixture.svm.process(&[ix::open_position(fixture.keys.pool, provider.key)], &[provider.key]).unwrap();
+    let increase = ix::increase_position_liquidity(&fixture.keys, provider.key, provider.tokens, instruction::IncreasePositionLiquidity { amount_token_0: 100_000, amount_token_1: 100_000 });
+    fixture.svm.process(&[increase], &[provider.key]).unwrap();
+    let newcomer = fixture.user(1_000_000, 1_000_000);
+    (fixture, provider, newcomer)
+}
+
+/// One instruction per operation, tagged with the bit that should stop it.
+fn operations(fixture: &PoolFixture, provider: &User, newcomer: &User) -> Vec<(u8, Instruction, User)> {
+    let keys = &fixture.keys;
+    let swap_args = instruction::Swap { amount_in: 1_000, minimum_amount_out: 0, zero_for_one: true, sqrt_price_limit_x64: MIN_SQRT_PRICE };
+    let quote_args = instruction::QuoteSwap { amount: 1_000, other_amount_threshold: 0, zero_for_one: true, sqrt_price_limit_x64: MIN_SQRT_PRICE, exact_input: true };
+    vec![
+        (POOL_STATUS_SWAP_DISABLED, ix::swap(keys, newcomer.key, newcomer.tokens.token_account_0, newcomer.tokens.token_account_1, swap_args), *newcomer),
+        (POOL_STATUS_SWAP_DISABLED, ix::quote_swap(keys, quote_args), *newcomer),
+        (POOL_STATUS_DEPOSIT_DISABLED, ix::add_liquidity(keys, newcomer.key, newcomer.tokens, newcomer.lp_token_account, instruction::AddLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 }), *newcomer),
+        (POOL_STATUS_WITHDRAW_DISABLED, ix::remove_liquidity(keys, provider.key, provider.tokens, provider.lp_token_account, instruction::RemoveLiquidity { lp_amount: 1_000 }), *provider),
+        (POOL_STATUS_OPEN_POSITION_DISABLED, ix::open_position(keys.pool, newcomer.key), *newcomer),
+        (POOL_STATUS_DEPOSIT_DISABLED, ix::increase_position_liquidity(keys, provider.key, provider.tokens, instruction::IncreasePositionLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 }), *provider),
+        (POOL_STATUS_WITHDRAW_DISABLED, ix::decrease_position_liquidity(keys, provider.key, provider.tokens, instruction::DecreasePositionLiquidity { amount_token_0: 1_000, amount_token_1: 1_000 }), *provider),
+        (POOL_STATUS_COLLECT_FEES_DISABLED, ix::collect_fees(keys, provider.key, provider.tokens), *provider),
+    ]
+}
+
+#[test]
+fn each_status_bit_disables_only_its_operations() {
+    let bits = [POOL_STATUS_SWAP_DISABLED, POOL_STATUS_DEPOSIT_DISABLED, POOL_STATUS_WITHDRAW_DISABLED, POOL_STATUS_OPEN_POSITION_DISABLED, POOL_STATUS_COLLECT_FEES_DISABLED];
+    for bit in bits {
+        let (mut fixture, provider, newcomer) = pool_with_position();
+        fixture.svm.process(&[ix::set_pool_status(&fixture.keys, fixture.admin, instruction::SetPoolStatus { status: bit })], &[fixture.admin]).unwrap();
+        for (disabled_by, operation, signer) in operations(&fixture, &provider, &newcomer) {
+            let result = fixture.svm.process(&[operation], &[signer.key]);
+            if disabled_by == bit {
+                assert_eq!(result.unwrap_err(), orca_error(ErrorCode::PoolOperationDisabled), "bit {bit:#b}");
+            } else {
+                result.unwrap_or_else(|err| panic!("bit {bit:#b} blocked an unrelated operation: {err}"));
+            }
+        }
+    }
+}
+
+#[test]
+fn clearing_the_status_re_enables_the_pool() {
+    let (mut fixture, _, trader) = pool_with_position();
+    let set = |fixture: &PoolFixture, status| ix::set_pool_status(&fixture.keys, fixture.admin, instruction::SetPoolStatus { status });
+    fixture.svm.process(&[set(&fixture, POOL_STATUS_ALL)], &[fixture.admin]).unwrap();
+    assert_eq!(fixture.swap(&trader, 1_000, true).unwrap_err(), orca_error(ErrorCode::PoolOperationDisabled));
+    fixture.svm.process(&[set(&fixture, 0)], &[fixture.admin]).unwrap();
+    fixture.swap(&trader, 1_000, true).unwrap();
+}
+
+#[test]
+fn only_the_config_authority_sets_known_status_bits() {
+    let (mut fixture, provider, _) = pool_with_position();
+    let by_provider = ix::set_pool_status(&fixture.keys, provider.key, instruction::SetPoolStatus { status: POOL_STATUS_SWAP_DISABLED });
+    assert_eq!(fixture.svm.process(&[by_provider], &[provider.key]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    let unknown = ix::set_pool_status(&fixture.keys, fixture.admin, instruction::SetPoolStatus { status: POOL_STATUS_ALL + 1 });
+    assert_eq!(fixture.svm.process(&[unknown], &[fixture.admin]).unwrap_err(), orca_error(ErrorCode::InvalidPoolStatus));
+    assert_eq!(fixture.pool().status, 0);
+}
@@ -0,0 +1,58 @@
+//! Only the upgrade authority recorded in the program's `ProgramData` can
+//! create configs, so whoever deploys the program is the one to set it up.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use common::*;
+use orca::errors::ErrorCode;
+use orca::instruction;
+use orca_sdk::instructions as ix;
+use orca_sdk::{pda, AmmConfig};
+
+fn initialize(svm: &mut Svm, upgrade_authority: Pubkey, authority: Pubkey) -> Instruction {
+    svm.airdrop(&upgrade_authority, 10_000_000_000);
+    ix::initialize_amm(upgrade_authority, authority, authority, instruction::InitializeAmm { index: 0, fee_rate: FEE_RATE, protocol_fee_rate: PROTOCOL_FEE_RATE })
+}
+
+#[test]
+fn the_upgrade_authority_creates_a_config_for_another_authority() {
+    let mut svm = Svm::new();
+    let (deployer, operator) = (Pubkey::new_unique(), Pubkey::new_unique());
+    svm.set_program_data(&pda::program_data().0, Some(deployer));
+    let config = pda::amm_config(0).0;
+
+    let stranger = Pubkey::new_unique();
+    let front_run = initialize(&mut svm, stranger, stranger);
+    assert_eq!(svm.process(&[front_run], &[stranger]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+    assert!(svm.try_account::<AmmConfig>(&config).is_none());
+
+    let create = initialize(&mut svm, deployer, operator);
+    let balance = svm.lamports(&deployer);
+    svm.process(&[create], &[deployer]).unwrap();
+    let state = svm.account::<AmmConfig>(&config);
+    assert_eq!((state.authority, state.protocol_fee_destination), (operator, operator));
+    assert_eq!(svm.lamports(&deployer), balance - svm.lamports(&config));
+}
+
+#[test]
+fn only_the_programs_own_program_data_counts() {
+    let mut svm = Svm::new();
+    let stranger = Pubkey::new_unique();
+    // Program data the stranger controls, just not this program's
+    let foreign = Pubkey::new_unique();
+    svm.set_program_data(&foreign, Some(stranger));
+    let mut create = initialize(&mut svm, stranger, stranger);
+    create.accounts[2].pubkey = foreign;
+    assert_eq!(svm.process(&[create], &[stranger]).unwrap_err(), orca_error(ErrorCode::InvalidProgramData));
+}
+
+#[test]
+fn an_immutable_program_admits_no_one() {
+    let mut svm = Svm::new();
+    svm.set_program_data(&pda::program_data().0, None);
+    let deployer = Pubkey::new_unique();
+    let create = initialize(&mut svm, deployer, deployer);
+    assert_eq!(svm.process(&[create], &[deployer]).unwrap_err(), orca_error(ErrorCode::Unauthorized));
+}
@@ -1,16 +1,23 @@
 import * as anchor from "@coral-xyz/anchor";
 import BN from "bn.js";
 import { expect } from "chai";
-import { PublicKey, Keypair, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { PublicKey, Keypair, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
   createMint,
   getOrCreateAssociatedTokenAccount,
   mintTo,
+  transfer,
 } from "@solana/spl-token";
 
 // Covers: initialize_amm, create_pool, add_liquidity, remove_liquidity, swap,
-// open_position, increase_position_liquidity, decrease_position_liquidity
+// open_position, increase_position_liquidity, decrease_position_liquidity,
+// collect_fees, quote_swap, flash_borrow, flash_repay, set_flash_fee_rate,
+// set_pool_status, set_dynamic_fee, emergency_remove_liquidity, exit_emergency_mode,
+// place_limit_order, cancel_limit_order, claim_limit_order, set_limit_order_min_liquidity,
+// create_pool_with_liquidity,
+// migrate_amm_config, migrate_pool, set_pool_creation_policy, add_pool_creator,
+// add_token_badge, remove_token_badge
 
 describe("orca instructions", () => {
   const provider = anchor.AnchorProvider.env();
@@ -24,12 +31,14 @@ describe("orca instructions", () => {
   let pool: PublicKey;
   let poolAuthority: PublicKey;
   let observationState: PublicKey;
+  let lpMint: PublicKey;
 
   // Token mints and user balances
   let mint0: PublicKey;
   let mint1: PublicKey;
   let userAta0: PublicKey;
   let userAta1: PublicKey;
+  let userLpAta: PublicKey;
 
   // Vault accounts to be created by program (as init accounts)
   const tokenVault0 = Keypair.generate();
@@ -39,12 +48,21 @@ describe("orca instructions", () => {
   const FEE_RATE = 3000; // 0.3%
   const PROTOCOL_FEE_RATE = 2000; // 20% of trading fees
   const TICK_SPACING = 60;
-  // Pick a sqrt price within bounds (between MIN_SQRT_PRICE and MAX_SQRT_PRICE)
-  const INITIAL_SQRT_PRICE_X64 = new BN("5000000000");
+  // Pick a sqrt price within bounds (between MIN_SQRT_PRICE and MAX_SQRT_PRICE); 2^64 is a 1:1 price
+  const INITIAL_SQRT_PRICE_X64 = new BN("18446744073709551616");
+  const MIN_SQRT_PRICE = new BN("4295048016");
+  const MAX_SQRT_PRICE = new BN("79226673515401279992447579061");
+  const SWAP_COMPUTE_BUDGET = 120_000;
+
+  // Configs are derived from a u16 index
+  const configAddress = (index: number) => {
+    const indexBytes = Buffer.alloc(2);
+    indexBytes.writeUInt16LE(index);
+    return PublicKey.findProgramAddressSync([Buffer.from("amm_config"), indexBytes], program.programId)[0];
+  };
 
   before("setup mints and accounts", async () => {
-    // Derive config PDA
-    ammConfig = PublicKey.findProgramAddressSync([Buffer.from("amm_config")], program.programId)[0];
+    ammConfig = configAddress(0);
 
     // Create two token mints (9 decimals) and ensure ordering mint0 < mint1
     const kp0 = Keypair.generate();
@@ -71,11 +89,19 @@ describe("orca instructions", () => {
     await mintTo(connection, wallet.payer, mint1, userAta1, wallet.payer, 1_000_000_000_000n);
   });
 
+  // `anchor test` deploys the program upgradeable, with the provider wallet as its upgrade authority
+  const programData = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  )[0];
+
   it("initialize_amm happy", async () => {
     const tx = await program.methods
-      .initializeAmm(FEE_RATE, PROTOCOL_FEE_RATE)
+      .initializeAmm(0, FEE_RATE, PROTOCOL_FEE_RATE)
       .accounts({
         ammConfig,
+        upgradeAuthority: wallet.publicKey,
+        programData,
         authority: wallet.publicKey,
         protocolFeeDestination: wallet.publicKey,
         systemProgram: SystemProgram.programId,
@@ -85,17 +111,37 @@ describe("orca instructions", () => {
     expect(tx).to.be.a("string");
   });
 
+  it("initialize_amm happy: a second config with its own authority and fees", async () => {
+    const authority = Keypair.generate();
+    const secondConfig = configAddress(2);
+    await program.methods
+      .initializeAmm(2, 500, 0)
+      .accounts({
+        ammConfig: secondConfig,
+        upgradeAuthority: wallet.publicKey,
+        programData,
+        authority: authority.publicKey,
+        protocolFeeDestination: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .rpc();
+    const config = (await program.account.ammConfig.fetch(secondConfig)) as any;
+    expect(config.index).to.eq(2);
+    expect(config.defaultFeeRate).to.eq(500);
+    expect(config.authority.toBase58()).to.eq(authority.publicKey.toBase58());
+  });
+
   it("initialize_amm unhappy: invalid fee rate", async () => {
-    const badConfig = PublicKey.findProgramAddressSync(
-      [Buffer.from("amm_config"), Buffer.from("bad")],
-      program.programId
-    )[0];
+    const badConfig = configAddress(1);
     let failed = false;
     try {
       await program.methods
-        .initializeAmm(10001, PROTOCOL_FEE_RATE)
+        .initializeAmm(1, 10001, PROTOCOL_FEE_RATE)
         .accounts({
           ammConfig: badConfig,
+          upgradeAuthority: wallet.publicKey,
+          programData,
           authority: wallet.publicKey,
           protocolFeeDestination: wallet.publicKey,
           systemProgram: SystemProgram.programId,
@@ -108,6 +154,29 @@ describe("orca instructions", () => {
     expect(failed).to.eq(true);
   });
 
+  it("initialize_amm unhappy: signer is not the upgrade authority", async () => {
+    const stranger = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(stranger.publicKey, 1_000_000_000)
+    );
+    const code = await errorCode(
+      program.methods
+        .initializeAmm(1, FEE_RATE, PROTOCOL_FEE_RATE)
+        .accounts({
+          ammConfig: configAddress(1),
+          upgradeAuthority: stranger.publicKey,
+          programData,
+          authority: stranger.publicKey,
+          protocolFeeDestination: stranger.publicKey,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([stranger])
+        .rpc()
+    );
+    expect(code).to.eq("Unauthorized");
+  });
+
   it("create_pool happy", async () => {
     pool = PublicKey.findProgramAddressSync(
       [Buffer.from("pool"), ammConfig.toBuffer(), mint0.toBuffer(), mint1.toBuffer()],
@@ -121,6 +190,10 @@ describe("orca instructions", () => {
       [Buffer.from("observation"), pool.toBuffer()],
       program.programId
     )[0];
+    lpMint = PublicKey.findProgramAddressSync(
+      [Buffer.from("lp_mint"), pool.toBuffer()],
+      program.programId
+    )[0];
 
     const tx = await program.methods
       .createPool(INITIAL_SQRT_PRICE_X64, TICK_SPACING)
@@ -132,6 +205,7 @@ describe("orca instructions", () => {
         tokenVault0: tokenVault0.publicKey,
         tokenVault1: tokenVault1.publicKey,
         poolAuthority,
+        lpMint,
         observationState,
         creator: wallet.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -141,6 +215,10 @@ describe("orca instructions", () => {
       .signers([tokenVault0, tokenVault1])
       .rpc();
     expect(tx).to.be.a("string");
+
+    userLpAta = (
+      await getOrCreateAssociatedTokenAccount(connection, wallet.payer, lpMint, wallet.publicKey)
+    ).address;
   });
 
   it("create_pool unhappy: invalid token order", async () => {
@@ -166,6 +244,10 @@ describe("orca instructions", () => {
           tokenVault0: badVault0.publicKey,
           tokenVault1: badVault1.publicKey,
           poolAuthority,
+          lpMint: PublicKey.findProgramAddressSync(
+            [Buffer.from("lp_mint"), badPool.toBuffer()],
+            program.programId
+          )[0],
           observationState: badObs,
           creator: wallet.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -182,19 +264,25 @@ describe("orca instructions", () => {
 
   it("add_liquidity happy", async () => {
     const tx = await program.methods
-      .addLiquidity(new BN(1000), new BN(1000))
+      .addLiquidity(new BN(1_000_000), new BN(1_000_000))
       .accounts({
         pool,
         tokenVault0: tokenVault0.publicKey,
         tokenVault1: tokenVault1.publicKey,
         userTokenAccount0: userAta0,
         userTokenAccount1: userAta1,
+        lpMint,
+        userLpTokenAccount: userLpAta,
         poolAuthority,
         user: wallet.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .rpc();
     expect(tx).to.be.a("string");
+    // At a 1:1 price, 1_000_000 of each token is 1_000_000 units of full-range liquidity,
+    // less the 1000 shares locked on the first deposit
+    const shares = (await connection.getTokenAccountBalance(userLpAta)).value.amount;
+    expect(shares).to.eq("999000");
   });
 
   it("add_liquidity unhappy: zero amount", async () => {
@@ -208,6 +296,8 @@ describe("orca instructions", () => {
           tokenVault1: tokenVault1.publicKey,
           userTokenAccount0: userAta0,
           userTokenAccount1: userAta1,
+          lpMint,
+          userLpTokenAccount: userLpAta,
           poolAuthority,
           user: wallet.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -221,13 +311,15 @@ describe("orca instructions", () => {
 
   it("remove_liquidity happy", async () => {
     const tx = await program.methods
-      .removeLiquidity(new BN(10), new BN(10))
+      .removeLiquidity(new BN(10))
       .accounts({
         pool,
         tokenVault0: tokenVault0.publicKey,
         tokenVault1: tokenVault1.publicKey,
         userTokenAccount0: userAta0,
         userTokenAccount1: userAta1,
+        lpMint,
+        userLpTokenAccount: userLpAta,
         poolAuthority,
         user: wallet.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -240,13 +332,15 @@ describe("orca instructions", () => {
     let failed = false;
     try {
       await program.methods
-        .removeLiquidity(new BN(9_999_999_999), new BN(9_999_999_999))
+        .removeLiquidity(new BN(9_999_999_999))
         .accounts({
           pool,
           tokenVault0: tokenVault0.publicKey,
           tokenVault1: tokenVault1.publicKey,
           userTokenAccount0: userAta0,
           userTokenAccount1: userAta1,
+          lpMint,
+          userLpTokenAccount: userLpAta,
           poolAuthority,
           user: wallet.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -258,9 +352,47 @@ describe("orca instructions", () => {
     expect(failed).to.eq(true);
   });
 
+  it("remove_liquidity unhappy: caller holds no shares", async () => {
+    const outsider = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(outsider.publicKey, 1_000_000_000)
+    );
+    const outsiderAta0 = (
+      await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mint0, outsider.publicKey)
+    ).address;
+    const outsiderAta1 = (
+      await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mint1, outsider.publicKey)
+    ).address;
+    const outsiderLpAta = (
+      await getOrCreateAssociatedTokenAccount(connection, wallet.payer, lpMint, outsider.publicKey)
+    ).address;
+    let failed = false;
+    try {
+      await program.methods
+        .removeLiquidity(new BN(10))
+        .accounts({
+          pool,
+          tokenVault0: tokenVault0.publicKey,
+          tokenVault1: tokenVault1.publicKey,
+          userTokenAccount0: outsiderAta0,
+          userTokenAccount1: outsiderAta1,
+          lpMint,
+          userLpTokenAccount: outsiderLpAta,
+          poolAuthority,
+          user: outsider.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([outsider])
+        .rpc();
+    } catch (_) {
+      failed = true;
+    }
+    expect(failed).to.eq(true);
+  });
+
   it("swap happy", async () => {
     const tx = await program.methods
-      .swap(new BN(500), new BN(1), true)
+      .swap(new BN(500), new BN(1), true, MIN_SQRT_PRICE)
       .accounts({
         pool,
         ammConfig,
@@ -277,11 +409,206 @@ describe("orca instructions", () => {
     expect(tx).to.be.a("string");
   });
 
+  it("swap emits a Swap event through a self-CPI", async () => {
+    const sig = await program.methods
+      .swap(new BN(100), new BN(0), true, MIN_SQRT_PRICE)
+      .accounts({
+        pool,
+        ammConfig,
+        userTokenAccountIn: userAta0,
+        userTokenAccountOut: userAta1,
+        tokenVaultIn: tokenVault0.publicKey,
+        tokenVaultOut: tokenVault1.publicKey,
+        poolAuthority,
+        observationState,
+        user: wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+    const tx = await connection.getTransaction(sig, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    // emit_cpi! data is the 8-byte event-ix tag followed by the regular event encoding
+    const events = tx!.meta!.innerInstructions!
+      .flatMap((inner) => inner.instructions)
+      .map((ix) => {
+        const data = anchor.utils.bytes.bs58.decode(ix.data);
+        return program.coder.events.decode(anchor.utils.bytes.base64.encode(data.subarray(8)));
+      })
+      .filter((event) => event !== null);
+    const swapEvent = events.find((event) => event!.name.toLowerCase() === "swap")!;
+    expect(swapEvent.data.amountIn.toString()).to.eq("100");
+    expect(swapEvent.data.zeroForOne).to.eq(true);
+    expect(
+      new BN(swapEvent.data.sqrtPriceAfterX64).lt(new BN(swapEvent.data.sqrtPriceBeforeX64))
+    ).to.eq(true);
+  });
+
+  it("swap returns its result to the caller", async () => {
+    const sig = await program.methods
+      .swap(new BN(100), new BN(0), true, MIN_SQRT_PRICE)
+      .accounts({
+        pool,
+        ammConfig,
+        userTokenAccountIn: userAta0,
+        userTokenAccountOut: userAta1,
+        tokenVaultIn: tokenVault0.publicKey,
+        tokenVaultOut: tokenVault1.publicKey,
+        poolAuthority,
+        observationState,
+        user: wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+    const tx = await connection.getTransaction(sig, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const returnData = (tx!.meta as any).returnData;
+    expect(returnData.programId).to.eq(program.programId.toBase58());
+    const result = program.coder.types.decode("SwapQuote", Buffer.from(returnData.data[0], "base64"));
+    const after = (await program.account.pool.fetch(pool)) as any;
+    expect(result.amountIn.toString()).to.eq("100");
+    expect(result.sqrtPriceAfterX64.toString()).to.eq(after.sqrtPriceX64.toString());
+  });
+
   it("swap unhappy: min out too high", async () => {
     let failed = false;
     try {
       await program.methods
-        .swap(new BN(100), new BN(10_000), true)
+        .swap(new BN(100), new BN(10_000), true, MIN_SQRT_PRICE)
+        .accounts({
+          pool,
+          ammConfig,
+          userTokenAccountIn: userAta0,
+          userTokenAccountOut: userAta1,
+          tokenVaultIn: tokenVault0.publicKey,
+          tokenVaultOut: tokenVault1.publicKey,
+          poolAuthority,
+          observationState,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    } catch (_) {
+      failed = true;
+    }
+    expect(failed).to.eq(true);
+  });
+
+  it("swap partial fill: stops at sqrt_price_limit", async () => {
+    const before = (await program.account.pool.fetch(pool)) as any;
+    const balanceBefore = (await connection.getTokenAccountBalance(userAta0)).value.amount;
+    // A limit 1% below the current price is reached long before 1_000_000 token0 is spent
+    const limit = before.sqrtPriceX64.sub(before.sqrtPriceX64.divn(100));
+    await program.methods
+      .swap(new BN(1_000_000), new BN(0), true, limit)
+      .accounts({
+        pool,
+        ammConfig,
+        userTokenAccountIn: userAta0,
+        userTokenAccountOut: userAta1,
+        tokenVaultIn: tokenVault0.publicKey,
+        tokenVaultOut: tokenVault1.publicKey,
+        poolAuthority,
+        observationState,
+        user: wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const after = (await program.account.pool.fetch(pool)) as any;
+    const balanceAfter = (await connection.getTokenAccountBalance(userAta0)).value.amount;
+    expect(after.sqrtPriceX64.toString()).to.eq(limit.toString());
+    const spent = BigInt(balanceBefore) - BigInt(balanceAfter);
+    expect(spent > 0n && spent < 1_000_000n).to.eq(true);
+  });
+
+  // A plain swap must stay within the swap budget the Rust tests hold it to (`COMPUTE_BUDGETS` in
+  // sdk/tests/common/mod.rs); raise both together when a change makes swaps costlier on purpose.
+  // The budget is an unmeasured ceiling, not a benchmark: swap compute units were never recorded
+  // before or after the move to zero-copy accounts, so that saving is unverified.
+  it("swap compute units", async () => {
+    const signature = await program.methods
+      .swap(new BN(1_000), new BN(0), true, MIN_SQRT_PRICE)
+      .accounts({
+        pool,
+        ammConfig,
+        userTokenAccountIn: userAta0,
+        userTokenAccountOut: userAta1,
+        tokenVaultIn: tokenVault0.publicKey,
+        tokenVaultOut: tokenVault1.publicKey,
+        poolAuthority,
+        observationState,
+        user: wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+    const transaction = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const computeUnits = transaction!.meta!.computeUnitsConsumed!;
+    expect(computeUnits).to.be.at.most(SWAP_COMPUTE_BUDGET);
+  });
+
+  it("quote_swap matches the swap it prices", async () => {
+    const quote = (await program.methods
+      .quoteSwap(new BN(1_000), new BN(0), true, MIN_SQRT_PRICE, true)
+      .accounts({ pool, ammConfig })
+      .view()) as any;
+    const balanceBefore = (await connection.getTokenAccountBalance(userAta1)).value.amount;
+    await program.methods
+      .swap(new BN(1_000), new BN(0), true, MIN_SQRT_PRICE)
+      .accounts({
+        pool,
+        ammConfig,
+        userTokenAccountIn: userAta0,
+        userTokenAccountOut: userAta1,
+        tokenVaultIn: tokenVault0.publicKey,
+        tokenVaultOut: tokenVault1.publicKey,
+        poolAuthority,
+        observationState,
+        user: wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const balanceAfter = (await connection.getTokenAccountBalance(userAta1)).value.amount;
+    const after = (await program.account.pool.fetch(pool)) as any;
+    expect((BigInt(balanceAfter) - BigInt(balanceBefore)).toString()).to.eq(quote.amountOut.toString());
+    expect(quote.amountIn.toString()).to.eq("1000");
+    expect(after.sqrtPriceX64.toString()).to.eq(quote.sqrtPriceAfterX64.toString());
+    expect(after.tickCurrent).to.eq(quote.tickAfter);
+  });
+
+  it("quote_swap exact output: pays out exactly the requested amount", async () => {
+    const quote = (await program.methods
+      .quoteSwap(new BN(500), new BN(10_000), false, MAX_SQRT_PRICE, false)
+      .accounts({ pool, ammConfig })
+      .view()) as any;
+    expect(quote.amountOut.toString()).to.eq("500");
+    expect(quote.amountIn.gt(quote.feeAmount)).to.eq(true);
+  });
+
+  it("quote_swap unhappy: exact output costs more than the maximum input", async () => {
+    let failed = false;
+    try {
+      await program.methods
+        .quoteSwap(new BN(500), new BN(1), false, MAX_SQRT_PRICE, false)
+        .accounts({ pool, ammConfig })
+        .view();
+    } catch (_) {
+      failed = true;
+    }
+    expect(failed).to.eq(true);
+  });
+
+  it("swap unhappy: sqrt_price_limit on the wrong side of the price", async () => {
+    const current = ((await program.account.pool.fetch(pool)) as any).sqrtPriceX64;
+    let failed = false;
+    try {
+      await program.methods
+        .swap(new BN(100), new BN(0), true, current.addn(1))
         .accounts({
           pool,
           ammConfig,
@@ -421,4 +748,703 @@ describe("orca instructions", () => {
       .rpc();
     expect(tx).to.be.a("string");
   });
+
+  it("collect_fees happy", async () => {
+    const tx = await program.methods
+      .collectFees()
+      .accounts({
+        pool,
+        position,
+        owner: wallet.publicKey,
+        tokenVault0: tokenVault0.publicKey,
+        tokenVault1: tokenVault1.publicKey,
+        userTokenAccount0: userAta0,
+        userTokenAccount1: userAta1,
+        poolAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    expect(tx).to.be.a("string");
+    const state = await program.account.position.fetch(position);
+    expect(state.tokensOwed0.toNumber()).to.equal(0);
+    expect(state.tokensOwed1.toNumber()).to.equal(0);
+  });
+
+  // Account validation: every vault and user account must belong to the pool
+  const errorCode = async (request: Promise<string>): Promise<string | undefined> => {
+    try {
+      await request;
+    } catch (err: any) {
+      return err.error?.errorCode?.code ?? "unknown";
+    }
+    return undefined;
+  };
+
+  it("add_liquidity unhappy: vault not owned by the pool", async () => {
+    const code = await errorCode(
+      program.methods
+        .addLiquidity(new BN(100), new BN(100))
+        .accounts({
+          pool,
+          tokenVault0: userAta0, // attacker-controlled account with the right mint
+          tokenVault1: tokenVault1.publicKey,
+          userTokenAccount0: userAta0,
+          userTokenAccount1: userAta1,
+          lpMint,
+          userLpTokenAccount: userLpAta,
+          poolAuthority,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("InvalidTokenAccount");
+  });
+
+  it("remove_liquidity unhappy: user account with the wrong mint", async () => {
+    const code = await errorCode(
+      program.methods
+        .removeLiquidity(new BN(1))
+        .accounts({
+          pool,
+          tokenVault0: tokenVault0.publicKey,
+          tokenVault1: tokenVault1.publicKey,
+          userTokenAccount0: userAta1,
+          userTokenAccount1: userAta1,
+          lpMint,
+          userLpTokenAccount: userLpAta,
+          poolAuthority,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("InvalidMint");
+  });
+
+  it("swap unhappy: vaults reversed for the direction", async () => {
+    const code = await errorCode(
+      program.methods
+        .swap(new BN(100), new BN(0), true, MIN_SQRT_PRICE)
+        .accounts({
+          pool,
+          ammConfig,
+          userTokenAccountIn: userAta1,
+          userTokenAccountOut: userAta0,
+          tokenVaultIn: tokenVault1.publicKey,
+          tokenVaultOut: tokenVault0.publicKey,
+          poolAuthority,
+          observationState,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("InvalidTokenAccount");
+  });
+
+  it("swap unhappy: user account mint does not match the vault", async () => {
+    const code = await errorCode(
+      program.methods
+        .swap(new BN(100), new BN(0), true, MIN_SQRT_PRICE)
+        .accounts({
+          pool,
+          ammConfig,
+          userTokenAccountIn: userAta1,
+          userTokenAccountOut: userAta1,
+          tokenVaultIn: tokenVault0.publicKey,
+          tokenVaultOut: tokenVault1.publicKey,
+          poolAuthority,
+          observationState,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("InvalidMint");
+  });
+
+  it("swap unhappy: config other than the pool's own", async () => {
+    let failed = false;
+    try {
+      await program.methods
+        .swap(new BN(100), new BN(0), true, MIN_SQRT_PRICE)
+        .accounts({
+          pool,
+          ammConfig: observationState, // any account other than the pool's config
+          userTokenAccountIn: userAta0,
+          userTokenAccountOut: userAta1,
+          tokenVaultIn: tokenVault0.publicKey,
+          tokenVaultOut: tokenVault1.publicKey,
+          poolAuthority,
+          observationState,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    } catch (_) {
+      failed = true;
+    }
+    expect(failed).to.eq(true);
+  });
+
+  it("increase_position_liquidity unhappy: vault not owned by the pool", async () => {
+    const code = await errorCode(
+      program.methods
+        .increasePositionLiquidity(new BN(100), new BN(100))
+        .accounts({
+          pool,
+          position,
+          owner: wallet.publicKey,
+          tokenVault0: tokenVault0.publicKey,
+          tokenVault1: userAta1,
+          userTokenAccount0: userAta0,
+          userTokenAccount1: userAta1,
+          poolAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("InvalidTokenAccount");
+  });
+
+  it("decrease_position_liquidity unhappy: user account with the wrong mint", async () => {
+    const code = await errorCode(
+      program.methods
+        .decreasePositionLiquidity(new BN(1), new BN(1))
+        .accounts({
+          pool,
+          position,
+          owner: wallet.publicKey,
+          tokenVault0: tokenVault0.publicKey,
+          tokenVault1: tokenVault1.publicKey,
+          userTokenAccount0: userAta0,
+          userTokenAccount1: userAta0,
+          poolAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("InvalidMint");
+  });
+
+  // Flash loans: borrow and repay share one account list
+  const flashLoanAccounts = () => ({
+    pool,
+    ammConfig,
+    tokenVault0: tokenVault0.publicKey,
+    tokenVault1: tokenVault1.publicKey,
+    userTokenAccount0: userAta0,
+    userTokenAccount1: userAta1,
+    poolAuthority,
+    user: wallet.publicKey,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+  });
+
+  it("flash_borrow happy: repaid with its fee in the same transaction", async () => {
+    const before = await program.account.pool.fetch(pool);
+    const repay = await program.methods.flashRepay().accounts(flashLoanAccounts()).instruction();
+    const tx = await program.methods
+      .flashBorrow(new BN(100_000), new BN(0))
+      .accounts(flashLoanAccounts())
+      .postInstructions([repay])
+      .rpc();
+    expect(tx).to.be.a("string");
+    const after = await program.account.pool.fetch(pool);
+    expect(after.flashLoanLocked).to.eq(0);
+    expect(after.feeGrowthGlobal0X64.gt(before.feeGrowthGlobal0X64)).to.eq(true);
+  });
+
+  it("flash_borrow unhappy: not repaid later in the transaction", async () => {
+    const code = await errorCode(
+      program.methods.flashBorrow(new BN(1_000), new BN(0)).accounts(flashLoanAccounts()).rpc()
+    );
+    expect(code).to.eq("FlashLoanNotRepaid");
+  });
+
+  it("set_flash_fee_rate unhappy: not the config authority", async () => {
+    const stranger = Keypair.generate();
+    const code = await errorCode(
+      program.methods
+        .setFlashFeeRate(100)
+        .accounts({ ammConfig, authority: stranger.publicKey })
+        .signers([stranger])
+        .rpc()
+    );
+    expect(code).to.eq("Unauthorized");
+  });
+
+  it("set_pool_status happy: a disabled swap bit halts swaps until cleared", async () => {
+    const POOL_STATUS_SWAP_DISABLED = 1;
+    const setStatus = (status: number) =>
+      program.methods.setPoolStatus(status).accounts({ pool, ammConfig, authority: wallet.publicKey }).rpc();
+    await setStatus(POOL_STATUS_SWAP_DISABLED);
+    let failed = false;
+    try {
+      await program.methods
+        .quoteSwap(new BN(1_000), new BN(0), true, MIN_SQRT_PRICE, true)
+        .accounts({ pool, ammConfig })
+        .view();
+    } catch (_) {
+      failed = true;
+    }
+    expect(failed).to.eq(true);
+    await setStatus(0);
+    expect((await program.account.pool.fetch(pool)).status).to.eq(0);
+  });
+
+  it("set_pool_status unhappy: unknown status bits", async () => {
+    const code = await errorCode(
+      program.methods.setPoolStatus(1 << 7).accounts({ pool, ammConfig, authority: wallet.publicKey }).rpc()
+    );
+    expect(code).to.eq("InvalidPoolStatus");
+  });
+
+  it("set_dynamic_fee unhappy: cap below the base fee rate", async () => {
+    const code = await errorCode(
+      program.methods.setDynamicFee(FEE_RATE - 1, 1).accounts({ pool, ammConfig, authority: wallet.publicKey }).rpc()
+    );
+    expect(code).to.eq("InvalidFeeRate");
+  });
+
+  // Emergency mode cannot be left within a test run, so only its guards are exercised on the shared pool
+  it("emergency_remove_liquidity unhappy: pool not in emergency mode", async () => {
+    const code = await errorCode(
+      program.methods
+        .emergencyRemoveLiquidity(new BN(1_000))
+        .accounts({
+          pool,
+          tokenVault0: tokenVault0.publicKey,
+          tokenVault1: tokenVault1.publicKey,
+          userTokenAccount0: userAta0,
+          userTokenAccount1: userAta1,
+          lpMint,
+          userLpTokenAccount: userLpAta,
+          poolAuthority,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("NotInEmergencyMode");
+  });
+
+  it("exit_emergency_mode unhappy: pool not in emergency mode", async () => {
+    const code = await errorCode(
+      program.methods.exitEmergencyMode().accounts({ pool, ammConfig, authority: wallet.publicKey }).rpc()
+    );
+    expect(code).to.eq("NotInEmergencyMode");
+  });
+
+  // A small order well above the price, so the shared pool's later tests never cross it
+  const limitOrderAccounts = async () => {
+    const { tickCurrent } = await program.account.pool.fetch(pool);
+    const tickLower = (Math.floor(tickCurrent / TICK_SPACING) + 5) * TICK_SPACING;
+    const tickBytes = Buffer.alloc(4);
+    tickBytes.writeInt32LE(tickLower);
+    const limitOrder = PublicKey.findProgramAddressSync(
+      [Buffer.from("limit_order"), pool.toBuffer(), wallet.publicKey.toBuffer(), tickBytes],
+      program.programId
+    )[0];
+    const limitOrderBook = PublicKey.findProgramAddressSync([Buffer.from("limit_order_book"), pool.toBuffer()], program.programId)[0];
+    return { tickLower, limitOrder, limitOrderBook };
+  };
+  const closeLimitOrderAccounts = (limitOrder: PublicKey, limitOrderBook: PublicKey) => ({
+    pool,
+    limitOrderBook,
+    limitOrder,
+    owner: wallet.publicKey,
+    authority: wallet.publicKey,
+    tokenVault0: tokenVault0.publicKey,
+    tokenVault1: tokenVault1.publicKey,
+    ownerTokenAccount0: userAta0,
+    ownerTokenAccount1: userAta1,
+    poolAuthority,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  it("place_limit_order happy, claim_limit_order unhappy: not filled, cancel_limit_order happy", async () => {
+    const { tickLower, limitOrder, limitOrderBook } = await limitOrderAccounts();
+    await program.methods
+      .placeLimitOrder(tickLower, new BN(10_000))
+      .accounts({
+        pool,
+        limitOrderBook,
+        limitOrder,
+        owner: wallet.publicKey,
+        tokenVault0: tokenVault0.publicKey,
+        tokenVault1: tokenVault1.publicKey,
+        userTokenAccount0: userAta0,
+        userTokenAccount1: userAta1,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    const order = await program.account.limitOrder.fetch(limitOrder);
+    expect(order.tickLower).to.eq(tickLower);
+    const code = await errorCode(
+      program.methods.claimLimitOrder().accounts(closeLimitOrderAccounts(limitOrder, limitOrderBook)).rpc()
+    );
+    expect(code).to.eq("LimitOrderNotFilled");
+    await program.methods.cancelLimitOrder().accounts(closeLimitOrderAccounts(limitOrder, limitOrderBook)).rpc();
+    expect(await connection.getAccountInfo(limitOrder)).to.eq(null);
+  });
+
+  it("place_limit_order unhappy: below the pool's minimum, set_limit_order_min_liquidity happy", async () => {
+    const { tickLower, limitOrder, limitOrderBook } = await limitOrderAccounts();
+    const setMinimum = (liquidity: BN) =>
+      program.methods.setLimitOrderMinLiquidity(liquidity).accounts({ pool, ammConfig, authority: wallet.publicKey }).rpc();
+    const { limitOrderMinLiquidity } = await program.account.pool.fetch(pool);
+    await setMinimum(new BN("1000000000000"));
+    const code = await errorCode(
+      program.methods
+        .placeLimitOrder(tickLower, new BN(10_000))
+        .accounts({
+          pool,
+          limitOrderBook,
+          limitOrder,
+          owner: wallet.publicKey,
+          tokenVault0: tokenVault0.publicKey,
+          tokenVault1: tokenVault1.publicKey,
+          userTokenAccount0: userAta0,
+          userTokenAccount1: userAta1,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("LimitOrderTooSmall");
+    await setMinimum(limitOrderMinLiquidity);
+    expect((await program.account.pool.fetch(pool)).limitOrderMinLiquidity.eq(limitOrderMinLiquidity)).to.eq(true);
+  });
+
+  it("place_limit_order unhappy: tick not aligned to the spacing", async () => {
+    const { tickLower, limitOrderBook } = await limitOrderAccounts();
+    const tickBytes = Buffer.alloc(4);
+    tickBytes.writeInt32LE(tickLower + 1);
+    const limitOrder = PublicKey.findProgramAddressSync(
+      [Buffer.from("limit_order"), pool.toBuffer(), wallet.publicKey.toBuffer(), tickBytes],
+      program.programId
+    )[0];
+    const code = await errorCode(
+      program.methods
+        .placeLimitOrder(tickLower + 1, new BN(1_000))
+        .accounts({
+          pool,
+          limitOrderBook,
+          limitOrder,
+          owner: wallet.publicKey,
+          tokenVault0: tokenVault0.publicKey,
+          tokenVault1: tokenVault1.publicKey,
+          userTokenAccount0: userAta0,
+          userTokenAccount1: userAta1,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+    );
+    expect(code).to.eq("TickNotAligned");
+  });
+
+  // Accounts at the legacy layout cannot be created from here; the SDK tests migrate them
+  it("migrate_observation_state unhappy: already at the current layout", async () => {
+    expect(((await program.account.observationState.fetch(observationState)) as any).version).to.eq(2);
+    const code = await errorCode(
+      program.methods
+        .migrateObservationState()
+        .accounts({ account: observationState, pool, ammConfig, authority: wallet.publicKey, systemProgram: SystemProgram.programId })
+        .rpc()
+    );
+    expect(code).to.eq("AccountAlreadyMigrated");
+  });
+
+  it("migrate_amm_config unhappy: not the config authority", async () => {
+    const stranger = Keypair.generate();
+    const code = await errorCode(
+      program.methods
+        .migrateAmmConfig()
+        .accounts({ ammConfig, authority: stranger.publicKey, systemProgram: SystemProgram.programId })
+        .signers([stranger])
+        .rpc()
+    );
+    expect(code).to.eq("Unauthorized");
+  });
+
+  // First-depositor protection, on a fresh pool so the first deposit can be observed
+  describe("share inflation", () => {
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let freshPool: PublicKey;
+    let freshAuthority: PublicKey;
+    let freshLpMint: PublicKey;
+    const freshVault0 = Keypair.generate();
+    const freshVault1 = Keypair.generate();
+    let attackerAta0: PublicKey;
+    let attackerAta1: PublicKey;
+    let attackerLpAta: PublicKey;
+    const victim = Keypair.generate();
+    let victimAta0: PublicKey;
+    let victimAta1: PublicKey;
+    let victimLpAta: PublicKey;
+
+    const deposit = (
+      user: Keypair,
+      ata0: PublicKey,
+      ata1: PublicKey,
+      lpAta: PublicKey,
+      amount0: number,
+      amount1: number
+    ) =>
+      program.methods
+        .addLiquidity(new BN(amount0), new BN(amount1))
+        .accounts({
+          pool: freshPool,
+          tokenVault0: freshVault0.publicKey,
+          tokenVault1: freshVault1.publicKey,
+          userTokenAccount0: ata0,
+          userTokenAccount1: ata1,
+          lpMint: freshLpMint,
+          userLpTokenAccount: lpAta,
+          poolAuthority: freshAuthority,
+          user: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers(user === wallet.payer ? [] : [user])
+        .rpc();
+
+    const balance = async (account: PublicKey) =>
+      BigInt((await connection.getTokenAccountBalance(account)).value.amount);
+
+    before("create an empty pool", async () => {
+      const kpA = Keypair.generate();
+      const kpB = Keypair.generate();
+      await createMint(connection, wallet.payer, wallet.publicKey, null, 9, kpA);
+      await createMint(connection, wallet.payer, wallet.publicKey, null, 9, kpB);
+      [mintA, mintB] =
+        kpA.publicKey.toBuffer().compare(kpB.publicKey.toBuffer()) < 0
+          ? [kpA.publicKey, kpB.publicKey]
+          : [kpB.publicKey, kpA.publicKey];
+
+      freshPool = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool"), ammConfig.toBuffer(), mintA.toBuffer(), mintB.toBuffer()],
+        program.programId
+      )[0];
+      freshAuthority = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_authority"), freshPool.toBuffer()],
+        program.programId
+      )[0];
+      freshLpMint = PublicKey.findProgramAddressSync(
+        [Buffer.from("lp_mint"), freshPool.toBuffer()],
+        program.programId
+      )[0];
+      await program.methods
+        .createPool(INITIAL_SQRT_PRICE_X64, TICK_SPACING)
+        .accounts({
+          ammConfig,
+          pool: freshPool,
+          tokenMint0: mintA,
+          tokenMint1: mintB,
+          tokenVault0: freshVault0.publicKey,
+          tokenVault1: freshVault1.publicKey,
+          poolAuthority: freshAuthority,
+          lpMint: freshLpMint,
+          observationState: PublicKey.findProgramAddressSync(
+            [Buffer.from("observation"), freshPool.toBuffer()],
+            program.programId
+          )[0],
+          creator: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([freshVault0, freshVault1])
+        .rpc();
+
+      const ata = async (mint: PublicKey, owner: PublicKey) =>
+        (await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mint, owner)).address;
+      attackerAta0 = await ata(mintA, wallet.publicKey);
+      attackerAta1 = await ata(mintB, wallet.publicKey);
+      attackerLpAta = await ata(freshLpMint, wallet.publicKey);
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(victim.publicKey, 1_000_000_000)
+      );
+      victimAta0 = await ata(mintA, victim.publicKey);
+      victimAta1 = await ata(mintB, victim.publicKey);
+      victimLpAta = await ata(freshLpMint, victim.publicKey);
+      await mintTo(connection, wallet.payer, mintA, attackerAta0, wallet.payer, 1_000_000_000_000n);
+      await mintTo(connection, wallet.payer, mintB, attackerAta1, wallet.payer, 1_000_000_000_000n);
+      await mintTo(connection, wallet.payer, mintA, victimAta0, wallet.payer, 1_000_000n);
+      await mintTo(connection, wallet.payer, mintB, victimAta1, wallet.payer, 1_000_000n);
+    });
+
+    it("first deposit unhappy: not above the locked minimum", async () => {
+      let failed = false;
+      try {
+        await deposit(wallet.payer, attackerAta0, attackerAta1, attackerLpAta, 1000, 1000);
+      } catch (_) {
+        failed = true;
+      }
+      expect(failed).to.eq(true);
+    });
+
+    it("donating to the vaults does not inflate the share price", async () => {
+      // Attacker seeds the pool with the smallest accepted deposit: one share above the lock
+      await deposit(wallet.payer, attackerAta0, attackerAta1, attackerLpAta, 1001, 1001);
+      expect(await balance(attackerLpAta)).to.eq(1n);
+
+      // ...then donates straight into the vaults to try to make that single share expensive
+      await transfer(connection, wallet.payer, attackerAta0, freshVault0.publicKey, wallet.payer, 1_000_000_000n);
+      await transfer(connection, wallet.payer, attackerAta1, freshVault1.publicKey, wallet.payer, 1_000_000_000n);
+
+      // The victim is still priced against tracked liquidity and is not rounded down to zero shares
+      await deposit(victim, victimAta0, victimAta1, victimLpAta, 2000, 2000);
+      expect(await balance(victimLpAta)).to.eq(2000n);
+
+      // Redeeming the attacker's share returns its liquidity, not the donation
+      const before0 = await balance(attackerAta0);
+      await program.methods
+        .removeLiquidity(new BN(1))
+        .accounts({
+          pool: freshPool,
+          tokenVault0: freshVault0.publicKey,
+          tokenVault1: freshVault1.publicKey,
+          userTokenAccount0: attackerAta0,
+          userTokenAccount1: attackerAta1,
+          lpMint: freshLpMint,
+          userLpTokenAccount: attackerLpAta,
+          poolAuthority: freshAuthority,
+          user: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      expect((await balance(attackerAta0)) - before0 <= 1n).to.eq(true);
+
+      // The victim can still withdraw what it put in, less rounding
+      const victimBefore0 = await balance(victimAta0);
+      await program.methods
+        .removeLiquidity(new BN(2000))
+        .accounts({
+          pool: freshPool,
+          tokenVault0: freshVault0.publicKey,
+          tokenVault1: freshVault1.publicKey,
+          userTokenAccount0: victimAta0,
+          userTokenAccount1: victimAta1,
+          lpMint: freshLpMint,
+          userLpTokenAccount: victimLpAta,
+          poolAuthority: freshAuthority,
+          user: victim.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([victim])
+        .rpc();
+      const returned = (await balance(victimAta0)) - victimBefore0;
+      expect(returned >= 1998n && returned <= 2000n).to.eq(true);
+    });
+  });
+
+  // The pool and its first position in one instruction, on fresh mints
+  describe("seeded pool creation", () => {
+    const seededVault0 = Keypair.generate();
+    const seededVault1 = Keypair.generate();
+    let mintA: PublicKey;
+    let mintB: PublicKey;
+    let seededPool: PublicKey;
+    let creatorAta0: PublicKey;
+    let creatorAta1: PublicKey;
+
+    const createSeeded = (amount0: number, amount1: number) =>
+      program.methods
+        .createPoolWithLiquidity(null, TICK_SPACING, new BN(amount0), new BN(amount1))
+        .accounts({
+          ammConfig,
+          pool: seededPool,
+          tokenMint0: mintA,
+          tokenMint1: mintB,
+          tokenVault0: seededVault0.publicKey,
+          tokenVault1: seededVault1.publicKey,
+          creatorTokenAccount0: creatorAta0,
+          creatorTokenAccount1: creatorAta1,
+          creator: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([seededVault0, seededVault1])
+        .rpc();
+
+    before("mint two fresh tokens", async () => {
+      const kpA = Keypair.generate();
+      const kpB = Keypair.generate();
+      await createMint(connection, wallet.payer, wallet.publicKey, null, 9, kpA);
+      await createMint(connection, wallet.payer, wallet.publicKey, null, 9, kpB);
+      [mintA, mintB] =
+        kpA.publicKey.toBuffer().compare(kpB.publicKey.toBuffer()) < 0
+          ? [kpA.publicKey, kpB.publicKey]
+          : [kpB.publicKey, kpA.publicKey];
+      seededPool = PublicKey.findProgramAddressSync(
+        [Buffer.from("pool"), ammConfig.toBuffer(), mintA.toBuffer(), mintB.toBuffer()],
+        program.programId
+      )[0];
+      creatorAta0 = (await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mintA, wallet.publicKey)).address;
+      creatorAta1 = (await getOrCreateAssociatedTokenAccount(connection, wallet.payer, mintB, wallet.publicKey)).address;
+      await mintTo(connection, wallet.payer, mintA, creatorAta0, wallet.payer, 10_000_000n);
+      await mintTo(connection, wallet.payer, mintB, creatorAta1, wallet.payer, 10_000_000n);
+    });
+
+    it("create_pool_with_liquidity unhappy: empty deposit", async () => {
+      const code = await errorCode(createSeeded(0, 1_000_000));
+      expect(code).to.eq("InvalidAmount");
+    });
+
+    it("create_pool_with_liquidity happy: the deposit ratio sets the price", async () => {
+      await createSeeded(1_000_000, 4_000_000);
+      const created = await program.account.pool.fetch(seededPool);
+      // One token 0 buys four token 1, so sqrt(P) = 2
+      expect(created.sqrtPriceX64.toString()).to.eq(new BN(2).shln(64).toString());
+      const position = PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), seededPool.toBuffer(), wallet.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const seeded = await program.account.position.fetch(position);
+      expect(seeded.liquidity.toString()).to.eq(created.liquidity.toString());
+      expect(seeded.liquidity.gtn(0)).to.eq(true);
+    });
+  });
+
+  it("set_pool_creation_policy unhappy: not the config authority", async () => {
+    const stranger = Keypair.generate();
+    const code = await errorCode(
+      program.methods
+        .setPoolCreationPolicy(true, true)
+        .accounts({ ammConfig, authority: stranger.publicKey })
+        .signers([stranger])
+        .rpc()
+    );
+    expect(code).to.eq("Unauthorized");
+  });
+
+  it("add_pool_creator unhappy: not the config authority", async () => {
+    const stranger = Keypair.generate();
+    const code = await errorCode(
+      program.methods
+        .addPoolCreator()
+        .accounts({ ammConfig, creator: stranger.publicKey, authority: stranger.publicKey })
+        .signers([stranger])
+        .rpc()
+    );
+    expect(code).to.eq("Unauthorized");
+  });
+
+  it("add_token_badge and remove_token_badge happy", async () => {
+    const tokenBadge = PublicKey.findProgramAddressSync(
+      [Buffer.from("token_badge"), ammConfig.toBuffer(), mint0.toBuffer()],
+      program.programId
+    )[0];
+    await program.methods.addTokenBadge().accounts({ ammConfig, tokenMint: mint0, authority: wallet.publicKey }).rpc();
+    const badge = await program.account.tokenBadge.fetch(tokenBadge);
+    expect(badge.tokenMint.toBase58()).to.eq(mint0.toBase58());
+    await program.methods.removeTokenBadge().accounts({ ammConfig, tokenBadge, authority: wallet.publicKey }).rpc();
+    expect(await program.account.tokenBadge.fetchNullable(tokenBadge)).to.eq(null);
+  });
 });
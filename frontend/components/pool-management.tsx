@@ -3,6 +3,7 @@
 import { useState } from 'react';
 import { Button } from '@/components/ui/button';
 import { PublicKey } from '@solana/web3.js';
+import { BN } from '@coral-xyz/anchor';
 import { useOrcaProgram } from '@/hooks/use-orca-program';
 import { useWallet } from '@solana/wallet-adapter-react';
 
@@ -21,6 +22,7 @@ export function PoolManagement() {
   const [tokenVault1, setTokenVault1] = useState('');
   const [amountToken0, setAmountToken0] = useState('');
   const [amountToken1, setAmountToken1] = useState('');
+  const [lpAmount, setLpAmount] = useState('');
   
   const [loading, setLoading] = useState(false);
   const [txSignature, setTxSignature] = useState('');
@@ -105,19 +107,19 @@ export function PoolManagement() {
       return;
     }
 
-    if (!poolAddress || !tokenVault0 || !tokenVault1 || !amountToken0 || !amountToken1 || !tokenMint0 || !tokenMint1) {
+    if (!poolAddress || !tokenVault0 || !tokenVault1 || !lpAmount || !tokenMint0 || !tokenMint1) {
       alert('Please fill in all fields');
       return;
     }
 
     try {
       setLoading(true);
+      // LP shares have 9 decimals; the pool pays out their part of both vaults and of the share fees
       const tx = await removeLiquidity(
         new PublicKey(poolAddress),
         new PublicKey(tokenVault0),
         new PublicKey(tokenVault1),
-        parseFloat(amountToken0) * 1e9,
-        parseFloat(amountToken1) * 1e9,
+        new BN(Math.floor(parseFloat(lpAmount) * 1e9)),
         new PublicKey(tokenMint0),
         new PublicKey(tokenMint1)
       );
@@ -125,8 +127,7 @@ export function PoolManagement() {
       alert('Liquidity removed successfully!');
       
       // Reset form
-      setAmountToken0('');
-      setAmountToken1('');
+      setLpAmount('');
     } catch (error: any) {
       console.error('Remove liquidity error:', error);
       alert(`Remove liquidity failed: ${error.message}`);
@@ -424,34 +425,18 @@ export function PoolManagement() {
             </div>
           </div>
 
-          <div className="grid grid-cols-2 gap-4">
-            <div>
-              <label className="block text-sm font-medium text-gray-700 mb-2">
-                Amount Token 0
-              </label>
-              <input
-                type="number"
-                value={amountToken0}
-                onChange={(e) => setAmountToken0(e.target.value)}
-                className="w-full px-4 py-2 border border-gray-300 rounded-md focus:ring-2 focus:ring-blue-500 focus:border-transparent"
-                placeholder="0.0"
-                step="0.000000001"
-              />
-            </div>
-
-            <div>
-              <label className="block text-sm font-medium text-gray-700 mb-2">
-                Amount Token 1
-              </label>
-              <input
-                type="number"
-                value={amountToken1}
-                onChange={(e) => setAmountToken1(e.target.value)}
-                className="w-full px-4 py-2 border border-gray-300 rounded-md focus:ring-2 focus:ring-blue-500 focus:border-transparent"
-                placeholder="0.0"
-                step="0.000000001"
-              />
-            </div>
+          <div>
+            <label className="block text-sm font-medium text-gray-700 mb-2">
+              LP Shares
+            </label>
+            <input
+              type="number"
+              value={lpAmount}
+              onChange={(e) => setLpAmount(e.target.value)}
+              className="w-full px-4 py-2 border border-gray-300 rounded-md focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+              placeholder="0.0"
+              step="0.000000001"
+            />
           </div>
 
           <Button
@@ -6,6 +6,8 @@ import orcaIdl from '@/idl/orca.json';
 import { useMemo } from 'react';
 
 const PROGRAM_ID = new PublicKey('9P6cZJHLnLu77ur6CdpNEgTZPnVVHweozgo4ykc9LMVZ');
+const MIN_SQRT_PRICE = new BN('4295048016');
+const MAX_SQRT_PRICE = new BN('79226673515401279992447579061');
 
 export function useOrcaProgram() {
   const { connection } = useConnection();
@@ -25,17 +27,29 @@ export function useOrcaProgram() {
     return new Program(orcaIdl as any, provider);
   }, [provider]);
 
-  const findAmmConfigPDA = () => {
+  // Configs are derived from a u16 index; pools are bound to one config
+  const findAmmConfigPDA = (index = 0) => {
+    const indexBytes = Buffer.alloc(2);
+    indexBytes.writeUInt16LE(index);
     return PublicKey.findProgramAddressSync(
-      [Buffer.from('amm_config')],
+      [Buffer.from('amm_config'), indexBytes],
       PROGRAM_ID
     );
   };
 
-  const findPoolPDA = (tokenMint0: PublicKey, tokenMint1: PublicKey) => {
+  const findProgramDataPDA = () => {
+    return PublicKey.findProgramAddressSync(
+      [PROGRAM_ID.toBuffer()],
+      new PublicKey('BPFLoaderUpgradeab1e11111111111111111111111')
+    );
+  };
+
+  const findPoolPDA = (tokenMint0: PublicKey, tokenMint1: PublicKey, configIndex = 0) => {
+    const [ammConfig] = findAmmConfigPDA(configIndex);
     return PublicKey.findProgramAddressSync(
       [
         Buffer.from('pool'),
+        ammConfig.toBuffer(),
         tokenMint0.toBuffer(),
         tokenMint1.toBuffer(),
       ],
@@ -45,7 +59,7 @@ export function useOrcaProgram() {
 
   const findPoolAuthority = (poolPubkey: PublicKey) => {
     return PublicKey.findProgramAddressSync(
-      [Buffer.from('vault_authority'), poolPubkey.toBuffer()],
+      [Buffer.from('pool_authority'), poolPubkey.toBuffer()],
       PROGRAM_ID
     );
   };
@@ -68,39 +82,70 @@ export function useOrcaProgram() {
     );
   };
 
+  const findLpMintPDA = (pool: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('lp_mint'), pool.toBuffer()],
+      PROGRAM_ID
+    );
+  };
+
+  const findLimitOrderBookPDA = (pool: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('limit_order_book'), pool.toBuffer()],
+      PROGRAM_ID
+    );
+  };
+
+  const findEventAuthority = () => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('__event_authority')],
+      PROGRAM_ID
+    );
+  };
+
+  // Only the program's upgrade authority may create configs; `authority` then runs the new one
   const initializeAmm = async (
+    index: number,
     feeRate: number,
     protocolFeeRate: number,
+    authority: PublicKey,
     protocolFeeDestination: PublicKey
   ) => {
     if (!program || !wallet.publicKey) throw new Error('Wallet not connected');
 
-    const [ammConfig] = findAmmConfigPDA();
+    const [ammConfig] = findAmmConfigPDA(index);
+    const [programData] = findProgramDataPDA();
+    const [eventAuthority] = findEventAuthority();
 
     const tx = await program.methods
-      .initializeAmm(feeRate, protocolFeeRate)
+      .initializeAmm(index, feeRate, protocolFeeRate)
       .accounts({
         ammConfig,
-        authority: wallet.publicKey,
+        upgradeAuthority: wallet.publicKey,
+        programData,
+        authority,
         protocolFeeDestination,
         systemProgram: SystemProgram.programId,
         rent: web3.SYSVAR_RENT_PUBKEY,
+        eventAuthority,
+        program: PROGRAM_ID,
       })
       .rpc();
 
-    return tx;
+    return { tx, ammConfig };
   };
 
   const createPool = async (
     tokenMint0: PublicKey,
     tokenMint1: PublicKey,
     sqrtPriceX64: string,
-    tickSpacing: number
+    tickSpacing: number,
+    configIndex = 0
   ) => {
     if (!program || !wallet.publicKey) throw new Error('Wallet not connected');
 
-    const [ammConfig] = findAmmConfigPDA();
-    const [pool] = findPoolPDA(tokenMint0, tokenMint1);
+    const [ammConfig] = findAmmConfigPDA(configIndex);
+    const [pool] = findPoolPDA(tokenMint0, tokenMint1, configIndex);
     const [poolAuthority] = findPoolAuthority(pool);
     const [observationState] = findObservationPDA(pool);
 
@@ -141,9 +186,21 @@ export function useOrcaProgram() {
     if (!program || !wallet.publicKey) throw new Error('Wallet not connected');
 
     const [poolAuthority] = findPoolAuthority(pool);
+    const [lpMint] = findLpMintPDA(pool);
+    const [limitOrderBook] = findLimitOrderBookPDA(pool);
+    const [eventAuthority] = findEventAuthority();
     
     const userTokenAccount0 = await getAssociatedTokenAddress(tokenMint0, wallet.publicKey);
     const userTokenAccount1 = await getAssociatedTokenAddress(tokenMint1, wallet.publicKey);
+    const userLpTokenAccount = await getAssociatedTokenAddress(lpMint, wallet.publicKey);
+
+    // Shares are minted to the user's LP token account, created here on a first deposit
+    const preInstructions = [];
+    if (!(await connection.getAccountInfo(userLpTokenAccount))) {
+      preInstructions.push(
+        createAssociatedTokenAccountInstruction(wallet.publicKey, userLpTokenAccount, wallet.publicKey, lpMint)
+      );
+    }
 
     const tx = await program.methods
       .addLiquidity(new BN(amountToken0), new BN(amountToken1))
@@ -153,10 +210,16 @@ export function useOrcaProgram() {
         tokenVault1,
         userTokenAccount0,
         userTokenAccount1,
+        lpMint,
+        userLpTokenAccount,
         poolAuthority,
+        limitOrderBook,
         user: wallet.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
+        eventAuthority,
+        program: PROGRAM_ID,
       })
+      .preInstructions(preInstructions)
       .rpc();
 
     return tx;
@@ -166,29 +229,37 @@ export function useOrcaProgram() {
     pool: PublicKey,
     tokenVault0: PublicKey,
     tokenVault1: PublicKey,
-    amountToken0: number,
-    amountToken1: number,
+    lpAmount: BN,
     tokenMint0: PublicKey,
     tokenMint1: PublicKey
   ) => {
     if (!program || !wallet.publicKey) throw new Error('Wallet not connected');
 
     const [poolAuthority] = findPoolAuthority(pool);
+    const [lpMint] = findLpMintPDA(pool);
+    const [limitOrderBook] = findLimitOrderBookPDA(pool);
+    const [eventAuthority] = findEventAuthority();
     
     const userTokenAccount0 = await getAssociatedTokenAddress(tokenMint0, wallet.publicKey);
     const userTokenAccount1 = await getAssociatedTokenAddress(tokenMint1, wallet.publicKey);
+    const userLpTokenAccount = await getAssociatedTokenAddress(lpMint, wallet.publicKey);
 
     const tx = await program.methods
-      .removeLiquidity(new BN(amountToken0), new BN(amountToken1))
+      .removeLiquidity(lpAmount)
       .accounts({
         pool,
         tokenVault0,
         tokenVault1,
         userTokenAccount0,
         userTokenAccount1,
+        lpMint,
+        userLpTokenAccount,
         poolAuthority,
+        limitOrderBook,
         user: wallet.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
+        eventAuthority,
+        program: PROGRAM_ID,
       })
       .rpc();
 
@@ -203,11 +274,13 @@ export function useOrcaProgram() {
     tokenVaultOut: PublicKey,
     amountIn: number,
     minimumAmountOut: number,
-    zeroForOne: boolean
+    zeroForOne: boolean,
+    sqrtPriceLimitX64?: BN
   ) => {
     if (!program || !wallet.publicKey) throw new Error('Wallet not connected');
 
-    const [ammConfig] = findAmmConfigPDA();
+    // The pool's own config, which for older pools may predate config indices
+    const { ammConfig } = await (program.account as any).pool.fetch(pool);
     const [poolAuthority] = findPoolAuthority(pool);
     const [observationState] = findObservationPDA(pool);
 
@@ -215,7 +288,12 @@ export function useOrcaProgram() {
     const userTokenAccountOut = await getAssociatedTokenAddress(tokenMintOut, wallet.publicKey);
 
     const tx = await program.methods
-      .swap(new BN(amountIn), new BN(minimumAmountOut), zeroForOne)
+      .swap(
+        new BN(amountIn),
+        new BN(minimumAmountOut),
+        zeroForOne,
+        sqrtPriceLimitX64 ?? (zeroForOne ? MIN_SQRT_PRICE : MAX_SQRT_PRICE)
+      )
       .accounts({
         pool,
         ammConfig,
@@ -329,9 +407,9 @@ export function useOrcaProgram() {
     return await (program.account as any).position.fetch(positionAddress);
   };
 
-  const getAmmConfig = async () => {
+  const getAmmConfig = async (index = 0) => {
     if (!program) throw new Error('Program not initialized');
-    const [ammConfig] = findAmmConfigPDA();
+    const [ammConfig] = findAmmConfigPDA(index);
     return await (program.account as any).ammConfig.fetch(ammConfig);
   };
 
@@ -348,7 +426,9 @@ export function useOrcaProgram() {
     getPool,
     getPosition,
     getAmmConfig,
+    findAmmConfigPDA,
     findPoolPDA,
     findPositionPDA,
+    findLpMintPDA,
   };
 }